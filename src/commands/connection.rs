@@ -0,0 +1,135 @@
+use super::server::SERVER_VERSION;
+use super::{arg_str, wrong_arity, CommandFlags, CommandRegistry, CommandSpec, Context, KeySpec};
+use crate::resp::{Protocol, Reply};
+
+pub(super) fn register(registry: &mut CommandRegistry) {
+    for name in ["PING", "HEALTH"] {
+        registry.add(CommandSpec {
+            name,
+            arity: -1,
+            flags: CommandFlags::NONE,
+            keys: KeySpec::NONE,
+            handler: ping,
+        });
+    }
+    registry.add(CommandSpec {
+        name: "ECHO",
+        arity: 2,
+        flags: CommandFlags::NONE,
+        keys: KeySpec::NONE,
+        handler: echo,
+    });
+    registry.add(CommandSpec {
+        name: "CLIENT",
+        arity: -2,
+        flags: CommandFlags::NONE,
+        keys: KeySpec::NONE,
+        handler: client,
+    });
+    registry.add(CommandSpec {
+        name: "HELLO",
+        arity: -1,
+        flags: CommandFlags::NONE,
+        keys: KeySpec::NONE,
+        handler: hello,
+    });
+}
+
+/// PING [message]
+fn ping(_ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match args.get(1) {
+        Some(message) => Reply::bulk(message.clone()),
+        None => Reply::Simple("PONG".to_string()),
+    }
+}
+
+/// ECHO message
+fn echo(_ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    Reply::bulk(args[1].clone())
+}
+
+/// CLIENT SETNAME tenant | CLIENT GETNAME | CLIENT ID
+fn client(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let subcommand = arg_str(&args[1]);
+    match (subcommand.to_uppercase().as_str(), args.len()) {
+        ("SETNAME", 3) => match tenant_name(&args[2]) {
+            Ok(tenant) => {
+                ctx.connection.tenant = Some(tenant);
+                Reply::ok()
+            }
+            Err(reply) => reply,
+        },
+        ("GETNAME", 2) => match &ctx.connection.tenant {
+            Some(tenant) => Reply::bulk(tenant.as_str()),
+            None => Reply::Null,
+        },
+        ("ID", 2) => Reply::Integer(ctx.connection.id as i64),
+        (name @ ("SETNAME" | "GETNAME" | "ID"), _) => wrong_arity(&format!("client|{}", name)),
+        _ => Reply::error(format!(
+            "ERR unknown subcommand '{}'. Try CLIENT HELP.",
+            subcommand
+        )),
+    }
+}
+
+/// Validates a client name used as tenant. Keys are stored as `tenant:key`,
+/// so a ':' in the name would let tenant `a:b` read into tenant `a`.
+fn tenant_name(arg: &[u8]) -> Result<String, Reply> {
+    let name = arg_str(arg);
+    if name.contains(':') {
+        return Err(Reply::error("ERR Client names cannot contain ':'"));
+    }
+    Ok(name)
+}
+
+/// HELLO [protover [AUTH username password] [SETNAME clientname]]
+fn hello(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let connection = &mut *ctx.connection;
+    let protocol = match args.get(1).map(|v| v.as_slice()) {
+        None => connection.protocol,
+        Some(b"2") => Protocol::Resp2,
+        Some(b"3") => Protocol::Resp3,
+        Some(_) => return Reply::error("NOPROTO unsupported protocol version"),
+    };
+
+    let mut tenant = None;
+    let mut i = 2;
+    while i < args.len() {
+        match arg_str(&args[i]).to_uppercase().as_str() {
+            // There is no authentication; credentials are accepted and ignored
+            "AUTH" if i + 2 < args.len() => i += 3,
+            "SETNAME" if i + 1 < args.len() => {
+                match tenant_name(&args[i + 1]) {
+                    Ok(name) => tenant = Some(name),
+                    Err(reply) => return reply,
+                }
+                i += 2;
+            }
+            _ => {
+                return Reply::error(format!(
+                    "ERR Syntax error in HELLO option '{}'",
+                    arg_str(&args[i])
+                ))
+            }
+        }
+    }
+
+    connection.protocol = protocol;
+    if tenant.is_some() {
+        connection.tenant = tenant;
+    }
+
+    let proto = match protocol {
+        Protocol::Resp2 => 2,
+        Protocol::Resp3 => 3,
+    };
+    Reply::Map(vec![
+        (Reply::bulk("server"), Reply::bulk("redis")),
+        (Reply::bulk("version"), Reply::bulk(SERVER_VERSION)),
+        (Reply::bulk("proto"), Reply::Integer(proto)),
+        (Reply::bulk("id"), Reply::Integer(connection.id as i64)),
+        (Reply::bulk("mode"), Reply::bulk("standalone")),
+        (Reply::bulk("role"), Reply::bulk("master")),
+        (Reply::bulk("modules"), Reply::Array(vec![])),
+    ])
+}
@@ -0,0 +1,195 @@
+use super::{
+    arg_str, parse_int, CommandFlags, CommandRegistry, CommandSpec, Context, Handler, KeySpec,
+};
+use crate::resp::Reply;
+use crate::types::{ExpireCondition, ExpiryComparison, TtlRequirement, TtlState};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const NX_CONFLICT: &str = "ERR NX and XX, GT or LT options at the same time are not compatible";
+const GT_LT_CONFLICT: &str = "ERR GT and LT options at the same time are not compatible";
+
+pub(super) fn register(registry: &mut CommandRegistry) {
+    for (name, handler) in [
+        ("EXPIRE", expire as Handler),
+        ("PEXPIRE", pexpire),
+        ("EXPIREAT", expireat),
+        ("PEXPIREAT", pexpireat),
+    ] {
+        registry.add(CommandSpec {
+            name,
+            arity: -3,
+            flags: CommandFlags::WRITE,
+            keys: KeySpec::FIRST,
+            handler,
+        });
+    }
+    for (name, handler) in [
+        ("TTL", ttl as Handler),
+        ("PTTL", pttl),
+        ("EXPIRETIME", expiretime),
+        ("PEXPIRETIME", pexpiretime),
+    ] {
+        registry.add(CommandSpec {
+            name,
+            arity: 2,
+            flags: CommandFlags::READONLY,
+            keys: KeySpec::FIRST,
+            handler,
+        });
+    }
+    registry.add(CommandSpec {
+        name: "PERSIST",
+        arity: 2,
+        flags: CommandFlags::WRITE,
+        keys: KeySpec::FIRST,
+        handler: persist,
+    });
+}
+
+/// EXPIRE key seconds [NX | XX | GT | LT]
+fn expire(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    set_expiry(ctx, args, "expire", SystemTime::now(), 1000)
+}
+
+/// PEXPIRE key milliseconds [NX | XX | GT | LT]
+fn pexpire(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    set_expiry(ctx, args, "pexpire", SystemTime::now(), 1)
+}
+
+/// EXPIREAT key unix-time-seconds [NX | XX | GT | LT]
+fn expireat(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    set_expiry(ctx, args, "expireat", UNIX_EPOCH, 1000)
+}
+
+/// PEXPIREAT key unix-time-milliseconds [NX | XX | GT | LT]
+fn pexpireat(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    set_expiry(ctx, args, "pexpireat", UNIX_EPOCH, 1)
+}
+
+/// Shared body of the EXPIRE family: the expiry is `base` plus the amount
+/// argument scaled by `unit_ms`. Negative results expire the key at once.
+fn set_expiry(
+    ctx: &mut Context,
+    args: &[Vec<u8>],
+    command: &str,
+    base: SystemTime,
+    unit_ms: i64,
+) -> Reply {
+    let at = match expire_at(&args[2], command, base, unit_ms) {
+        Ok(at) => at,
+        Err(reply) => return reply,
+    };
+    let condition = match parse_expire_condition(&args[3..]) {
+        Ok(condition) => condition,
+        Err(reply) => return reply,
+    };
+
+    let changed = ctx.store.expire(&arg_str(&args[1]), at, condition);
+    Reply::Integer(changed as i64)
+}
+
+/// The instant named by an EXPIRE-style amount argument: `base` plus the
+/// amount scaled by `unit_ms`, clamped to the epoch if negative.
+pub(super) fn expire_at(
+    arg: &[u8],
+    command: &str,
+    base: SystemTime,
+    unit_ms: i64,
+) -> Result<SystemTime, Reply> {
+    let invalid = || Reply::error(format!("ERR invalid expire time in '{}' command", command));
+    let millis = parse_int(arg)?.checked_mul(unit_ms).ok_or_else(invalid)?;
+    if millis >= 0 {
+        base.checked_add(Duration::from_millis(millis as u64))
+            .ok_or_else(invalid)
+    } else {
+        Ok(base
+            .checked_sub(Duration::from_millis(millis.unsigned_abs()))
+            .unwrap_or(UNIX_EPOCH))
+    }
+}
+
+pub(super) fn parse_expire_condition(args: &[Vec<u8>]) -> Result<ExpireCondition, Reply> {
+    let mut condition = ExpireCondition::default();
+    for arg in args {
+        match arg_str(arg).to_uppercase().as_str() {
+            "NX" => set_once(&mut condition.require, TtlRequirement::NoTtl, NX_CONFLICT)?,
+            "XX" => set_once(&mut condition.require, TtlRequirement::HasTtl, NX_CONFLICT)?,
+            "GT" => set_once(
+                &mut condition.compare,
+                ExpiryComparison::Greater,
+                GT_LT_CONFLICT,
+            )?,
+            "LT" => set_once(
+                &mut condition.compare,
+                ExpiryComparison::Less,
+                GT_LT_CONFLICT,
+            )?,
+            other => {
+                return Err(Reply::error(format!("ERR Unsupported option {}", other)));
+            }
+        }
+    }
+    // XX combines with GT/LT; NX combines with neither
+    if condition.require == Some(TtlRequirement::NoTtl) && condition.compare.is_some() {
+        return Err(Reply::error(NX_CONFLICT));
+    }
+    Ok(condition)
+}
+
+/// Records an option, allowing repeats but not a conflicting second choice.
+fn set_once<T: PartialEq>(slot: &mut Option<T>, value: T, conflict: &str) -> Result<(), Reply> {
+    match slot {
+        Some(current) if *current != value => Err(Reply::error(conflict)),
+        _ => {
+            *slot = Some(value);
+            Ok(())
+        }
+    }
+}
+
+/// Milliseconds from now until `at`, never negative.
+pub(super) fn remaining_ms(at: SystemTime) -> i64 {
+    at.duration_since(SystemTime::now())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+pub(super) fn unix_ms(at: SystemTime) -> i64 {
+    at.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Applies the -2 (missing) / -1 (no TTL) conventions shared by TTL queries.
+fn ttl_reply(ctx: &mut Context, args: &[Vec<u8>], f: fn(SystemTime) -> i64) -> Reply {
+    match ctx.store.ttl(&arg_str(&args[1])) {
+        TtlState::Missing => Reply::Integer(-2),
+        TtlState::Persistent => Reply::Integer(-1),
+        TtlState::ExpiresAt(at) => Reply::Integer(f(at)),
+    }
+}
+
+/// TTL key
+fn ttl(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    ttl_reply(ctx, args, |at| (remaining_ms(at) + 500) / 1000)
+}
+
+/// PTTL key
+fn pttl(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    ttl_reply(ctx, args, remaining_ms)
+}
+
+/// EXPIRETIME key
+fn expiretime(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    ttl_reply(ctx, args, |at| unix_ms(at) / 1000)
+}
+
+/// PEXPIRETIME key
+fn pexpiretime(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    ttl_reply(ctx, args, unix_ms)
+}
+
+/// PERSIST key
+fn persist(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    Reply::Integer(ctx.store.persist(&arg_str(&args[1])) as i64)
+}
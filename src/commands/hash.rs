@@ -0,0 +1,339 @@
+use super::expire::{expire_at, parse_expire_condition, remaining_ms, unix_ms};
+use super::{
+    arg_str, parse_int, syntax_error, wrong_arity, CommandFlags, CommandRegistry, CommandSpec,
+    Context, Handler, KeySpec,
+};
+use crate::glob::glob_match;
+use crate::resp::Reply;
+use crate::types::TtlState;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub(super) fn register(registry: &mut CommandRegistry) {
+    for (name, arity, flags, handler) in [
+        ("HSET", -4, CommandFlags::WRITE, hset as Handler),
+        ("HGET", 3, CommandFlags::READONLY, hget),
+        ("HMGET", -3, CommandFlags::READONLY, hmget),
+        ("HGETALL", 2, CommandFlags::READONLY, hgetall),
+        ("HDEL", -3, CommandFlags::WRITE, hdel),
+        ("HEXISTS", 3, CommandFlags::READONLY, hexists),
+        ("HINCRBY", 4, CommandFlags::WRITE, hincrby),
+        ("HKEYS", 2, CommandFlags::READONLY, hkeys),
+        ("HVALS", 2, CommandFlags::READONLY, hvals),
+        ("HLEN", 2, CommandFlags::READONLY, hlen),
+        ("HSCAN", -3, CommandFlags::READONLY, hscan),
+        ("HEXPIRE", -6, CommandFlags::WRITE, hexpire),
+        ("HPEXPIRE", -6, CommandFlags::WRITE, hpexpire),
+        ("HEXPIREAT", -6, CommandFlags::WRITE, hexpireat),
+        ("HPEXPIREAT", -6, CommandFlags::WRITE, hpexpireat),
+        ("HTTL", -5, CommandFlags::READONLY, httl),
+        ("HPTTL", -5, CommandFlags::READONLY, hpttl),
+        ("HEXPIRETIME", -5, CommandFlags::READONLY, hexpiretime),
+        ("HPEXPIRETIME", -5, CommandFlags::READONLY, hpexpiretime),
+        ("HPERSIST", -5, CommandFlags::WRITE, hpersist),
+    ] {
+        registry.add(CommandSpec {
+            name,
+            arity,
+            flags,
+            keys: KeySpec::FIRST,
+            handler,
+        });
+    }
+}
+
+/// HSET key field value [field value ...]
+fn hset(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    if !args.len().is_multiple_of(2) {
+        return wrong_arity("hset");
+    }
+    let pairs = args[2..]
+        .chunks(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()))
+        .collect();
+    match ctx.store.hash_set(arg_str(&args[1]), pairs) {
+        Ok(added) => Reply::Integer(added as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// HGET key field
+fn hget(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.hash_get(&arg_str(&args[1]), &args[2]) {
+        Ok(value) => value.map_or(Reply::Null, Reply::bulk),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// HMGET key field [field ...]
+fn hmget(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.hash_get_many(&arg_str(&args[1]), &args[2..]) {
+        Ok(values) => Reply::Array(
+            values
+                .into_iter()
+                .map(|value| value.map_or(Reply::Null, Reply::bulk))
+                .collect(),
+        ),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// HGETALL key
+fn hgetall(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.hash_get_all(&arg_str(&args[1])) {
+        Ok(pairs) => Reply::Map(
+            pairs
+                .into_iter()
+                .map(|(field, value)| (Reply::bulk(field), Reply::bulk(value)))
+                .collect(),
+        ),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// HDEL key field [field ...]
+fn hdel(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.hash_delete(&arg_str(&args[1]), &args[2..]) {
+        Ok(removed) => Reply::Integer(removed as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// HEXISTS key field
+fn hexists(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.hash_exists(&arg_str(&args[1]), &args[2]) {
+        Ok(exists) => Reply::Integer(exists as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// HINCRBY key field increment
+fn hincrby(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let delta = match parse_int(&args[3]) {
+        Ok(delta) => delta,
+        Err(reply) => return reply,
+    };
+    match ctx
+        .store
+        .hash_incr_by(arg_str(&args[1]), args[2].clone(), delta)
+    {
+        Ok(value) => Reply::Integer(value),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// HKEYS key
+fn hkeys(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.hash_get_all(&arg_str(&args[1])) {
+        Ok(pairs) => Reply::Array(
+            pairs
+                .into_iter()
+                .map(|(field, _)| Reply::bulk(field))
+                .collect(),
+        ),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// HVALS key
+fn hvals(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.hash_get_all(&arg_str(&args[1])) {
+        Ok(pairs) => Reply::Array(
+            pairs
+                .into_iter()
+                .map(|(_, value)| Reply::bulk(value))
+                .collect(),
+        ),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// HLEN key
+fn hlen(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.hash_len(&arg_str(&args[1])) {
+        Ok(len) => Reply::Integer(len as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// HSCAN key cursor [MATCH pattern] [COUNT count] [NOVALUES]
+fn hscan(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let Ok(cursor) = arg_str(&args[2]).parse::<u64>() else {
+        return Reply::error("ERR invalid cursor");
+    };
+
+    let mut pattern = None;
+    let mut count = 10;
+    let mut values = true;
+    let mut i = 3;
+    while i < args.len() {
+        let option = arg_str(&args[i]).to_uppercase();
+        if option == "NOVALUES" {
+            values = false;
+            i += 1;
+            continue;
+        }
+        if i + 1 >= args.len() {
+            return syntax_error();
+        }
+        match option.as_str() {
+            "MATCH" => pattern = Some(args[i + 1].clone()),
+            "COUNT" => match parse_int(&args[i + 1]) {
+                Ok(n) if n >= 1 => count = n as usize,
+                Ok(_) => return syntax_error(),
+                Err(reply) => return reply,
+            },
+            _ => return syntax_error(),
+        }
+        i += 2;
+    }
+
+    let (next, pairs) = match ctx.store.hash_scan(&arg_str(&args[1]), cursor, count) {
+        Ok(scanned) => scanned,
+        Err(e) => return Reply::error(e.to_string()),
+    };
+    let mut items = Vec::new();
+    for (field, value) in pairs {
+        if pattern.as_ref().is_some_and(|p| !glob_match(p, &field)) {
+            continue;
+        }
+        items.push(Reply::bulk(field));
+        if values {
+            items.push(Reply::bulk(value));
+        }
+    }
+
+    Reply::Array(vec![Reply::bulk(next.to_string()), Reply::Array(items)])
+}
+
+/// Parses the trailing `FIELDS numfields field [field ...]` block of the
+/// field TTL commands, starting at `args[0]`.
+fn parse_fields(args: &[Vec<u8>]) -> Result<&[Vec<u8>], Reply> {
+    if args.len() < 2 || !arg_str(&args[0]).eq_ignore_ascii_case("FIELDS") {
+        return Err(Reply::error(
+            "ERR Mandatory argument FIELDS is missing or not at the right position",
+        ));
+    }
+    let numfields = parse_int(&args[1])?;
+    if numfields <= 0 {
+        return Err(Reply::error(
+            "ERR Parameter `numFields` should be greater than 0",
+        ));
+    }
+    if numfields as usize != args.len() - 2 {
+        return Err(Reply::error(
+            "ERR The `numfields` parameter must match the number of arguments",
+        ));
+    }
+    Ok(&args[2..])
+}
+
+/// Shared body of HEXPIRE and friends: `base` plus the amount in units of
+/// `unit_ms`, applied to each listed field.
+fn set_field_expiry(
+    ctx: &mut Context,
+    args: &[Vec<u8>],
+    command: &str,
+    base: SystemTime,
+    unit_ms: i64,
+) -> Reply {
+    let at = match expire_at(&args[2], command, base, unit_ms) {
+        Ok(at) => at,
+        Err(reply) => return reply,
+    };
+    // At most one condition may sit between the time and FIELDS
+    let fields_at = if arg_str(&args[3]).eq_ignore_ascii_case("FIELDS") {
+        3
+    } else {
+        4
+    };
+    let condition = match parse_expire_condition(&args[3..fields_at]) {
+        Ok(condition) => condition,
+        Err(reply) => return reply,
+    };
+    let fields = match parse_fields(&args[fields_at..]) {
+        Ok(fields) => fields,
+        Err(reply) => return reply,
+    };
+
+    match ctx
+        .store
+        .hash_expire(&arg_str(&args[1]), fields, at, condition)
+    {
+        Ok(results) => Reply::Array(results.into_iter().map(Reply::Integer).collect()),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// HEXPIRE key seconds [NX|XX|GT|LT] FIELDS numfields field [field ...]
+fn hexpire(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    set_field_expiry(ctx, args, "hexpire", SystemTime::now(), 1000)
+}
+
+/// HPEXPIRE key milliseconds [NX|XX|GT|LT] FIELDS numfields field [field ...]
+fn hpexpire(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    set_field_expiry(ctx, args, "hpexpire", SystemTime::now(), 1)
+}
+
+/// HEXPIREAT key unix-time-seconds [NX|XX|GT|LT] FIELDS numfields field [field ...]
+fn hexpireat(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    set_field_expiry(ctx, args, "hexpireat", UNIX_EPOCH, 1000)
+}
+
+/// HPEXPIREAT key unix-time-milliseconds [NX|XX|GT|LT] FIELDS numfields field [field ...]
+fn hpexpireat(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    set_field_expiry(ctx, args, "hpexpireat", UNIX_EPOCH, 1)
+}
+
+/// Per-field counterpart of the key TTL replies: -2 for a missing field,
+/// -1 for one without a TTL.
+fn field_ttl_reply(ctx: &mut Context, args: &[Vec<u8>], f: fn(SystemTime) -> i64) -> Reply {
+    let fields = match parse_fields(&args[2..]) {
+        Ok(fields) => fields,
+        Err(reply) => return reply,
+    };
+    match ctx.store.hash_ttl(&arg_str(&args[1]), fields) {
+        Ok(states) => Reply::Array(
+            states
+                .into_iter()
+                .map(|state| match state {
+                    TtlState::Missing => Reply::Integer(-2),
+                    TtlState::Persistent => Reply::Integer(-1),
+                    TtlState::ExpiresAt(at) => Reply::Integer(f(at)),
+                })
+                .collect(),
+        ),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// HTTL key FIELDS numfields field [field ...]
+fn httl(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    field_ttl_reply(ctx, args, |at| (remaining_ms(at) + 500) / 1000)
+}
+
+/// HPTTL key FIELDS numfields field [field ...]
+fn hpttl(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    field_ttl_reply(ctx, args, remaining_ms)
+}
+
+/// HEXPIRETIME key FIELDS numfields field [field ...]
+fn hexpiretime(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    field_ttl_reply(ctx, args, |at| unix_ms(at) / 1000)
+}
+
+/// HPEXPIRETIME key FIELDS numfields field [field ...]
+fn hpexpiretime(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    field_ttl_reply(ctx, args, unix_ms)
+}
+
+/// HPERSIST key FIELDS numfields field [field ...]
+fn hpersist(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let fields = match parse_fields(&args[2..]) {
+        Ok(fields) => fields,
+        Err(reply) => return reply,
+    };
+    match ctx.store.hash_persist(&arg_str(&args[1]), fields) {
+        Ok(results) => Reply::Array(results.into_iter().map(Reply::Integer).collect()),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
@@ -0,0 +1,256 @@
+use super::{
+    arg_str, parse_int, strip_tenant, syntax_error, tenant_prefix, wrong_arity, CommandFlags,
+    CommandRegistry, CommandSpec, Context, KeySpec,
+};
+use crate::glob::glob_match;
+use crate::metrics::METRICS_KEY;
+use crate::resp::Reply;
+
+/// Every argument after the command name is a key.
+const ALL_KEYS: KeySpec = KeySpec {
+    first: 1,
+    last: -1,
+    step: 1,
+};
+
+/// Source and destination keys.
+const TWO_KEYS: KeySpec = KeySpec {
+    first: 1,
+    last: 2,
+    step: 1,
+};
+
+pub(super) fn register(registry: &mut CommandRegistry) {
+    registry.add(CommandSpec {
+        name: "DEL",
+        arity: -2,
+        flags: CommandFlags::WRITE,
+        keys: ALL_KEYS,
+        handler: del,
+    });
+    registry.add(CommandSpec {
+        name: "UNLINK",
+        arity: -2,
+        flags: CommandFlags::WRITE,
+        keys: ALL_KEYS,
+        handler: unlink,
+    });
+    registry.add(CommandSpec {
+        name: "EXISTS",
+        arity: -2,
+        flags: CommandFlags::READONLY,
+        keys: ALL_KEYS,
+        handler: exists,
+    });
+    registry.add(CommandSpec {
+        name: "TYPE",
+        arity: 2,
+        flags: CommandFlags::READONLY,
+        keys: KeySpec::FIRST,
+        handler: key_type,
+    });
+    registry.add(CommandSpec {
+        name: "OBJECT",
+        arity: -2,
+        flags: CommandFlags::READONLY,
+        // OBJECT <subcommand> key
+        keys: KeySpec {
+            first: 2,
+            last: 2,
+            step: 1,
+        },
+        handler: object,
+    });
+    registry.add(CommandSpec {
+        name: "RENAME",
+        arity: 3,
+        flags: CommandFlags::WRITE,
+        keys: TWO_KEYS,
+        handler: rename,
+    });
+    registry.add(CommandSpec {
+        name: "RENAMENX",
+        arity: 3,
+        flags: CommandFlags::WRITE,
+        keys: TWO_KEYS,
+        handler: renamenx,
+    });
+    registry.add(CommandSpec {
+        name: "KEYS",
+        arity: 2,
+        flags: CommandFlags::READONLY,
+        keys: KeySpec::NONE,
+        handler: keys,
+    });
+    registry.add(CommandSpec {
+        name: "SCAN",
+        arity: -2,
+        flags: CommandFlags::READONLY,
+        keys: KeySpec::NONE,
+        handler: scan,
+    });
+    registry.add(CommandSpec {
+        name: "COPY",
+        arity: -3,
+        flags: CommandFlags::WRITE,
+        keys: TWO_KEYS,
+        handler: copy,
+    });
+}
+
+fn keys_from(args: &[Vec<u8>]) -> Vec<String> {
+    args.iter().map(|arg| arg_str(arg)).collect()
+}
+
+/// Keys the client may delete: the tenant's metrics log is kept.
+fn deletable_keys(ctx: &Context, args: &[Vec<u8>]) -> Vec<String> {
+    let metrics_key = format!("{}{}", tenant_prefix(ctx), METRICS_KEY);
+    let mut keys = keys_from(args);
+    keys.retain(|key| *key != metrics_key);
+    keys
+}
+
+/// DEL key [key ...]
+fn del(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let keys = deletable_keys(ctx, &args[1..]);
+    Reply::Integer(ctx.store.delete(&keys) as i64)
+}
+
+/// UNLINK key [key ...]
+fn unlink(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let keys = deletable_keys(ctx, &args[1..]);
+    Reply::Integer(ctx.store.unlink(&keys) as i64)
+}
+
+/// EXISTS key [key ...]
+fn exists(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    Reply::Integer(ctx.store.exists(&keys_from(&args[1..])) as i64)
+}
+
+/// TYPE key
+fn key_type(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    Reply::Simple(ctx.store.key_type(&arg_str(&args[1])).to_string())
+}
+
+/// OBJECT ENCODING key
+fn object(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let subcommand = arg_str(&args[1]);
+    match (subcommand.to_uppercase().as_str(), args.len()) {
+        ("ENCODING", 3) => ctx
+            .store
+            .object_encoding(&arg_str(&args[2]))
+            .map_or(Reply::Null, Reply::bulk),
+        ("ENCODING", _) => wrong_arity("object|encoding"),
+        _ => Reply::error(format!(
+            "ERR unknown subcommand '{}'. Try OBJECT HELP.",
+            subcommand
+        )),
+    }
+}
+
+/// RENAME key newkey
+fn rename(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx
+        .store
+        .rename(&arg_str(&args[1]), &arg_str(&args[2]), false)
+    {
+        Ok(_) => Reply::ok(),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// RENAMENX key newkey
+fn renamenx(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx
+        .store
+        .rename(&arg_str(&args[1]), &arg_str(&args[2]), true)
+    {
+        Ok(renamed) => Reply::Integer(renamed as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// COPY source destination [DB 0] [REPLACE]
+fn copy(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let mut replace = false;
+    let mut i = 3;
+    while i < args.len() {
+        match arg_str(&args[i]).to_uppercase().as_str() {
+            "REPLACE" => replace = true,
+            // Tenants replace numbered databases, so only DB 0 exists
+            "DB" if i + 1 < args.len() => {
+                i += 1;
+                if args[i] != b"0" {
+                    return Reply::error("ERR DB index is out of range");
+                }
+            }
+            _ => return syntax_error(),
+        }
+        i += 1;
+    }
+    match ctx
+        .store
+        .copy(&arg_str(&args[1]), &arg_str(&args[2]), replace)
+    {
+        Ok(copied) => Reply::Integer(copied as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// KEYS pattern
+fn keys(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let prefix = tenant_prefix(ctx);
+    Reply::Array(
+        ctx.store
+            .keys(&prefix)
+            .iter()
+            .map(|key| strip_tenant(&prefix, key))
+            .filter(|key| *key != METRICS_KEY && glob_match(&args[1], key.as_bytes()))
+            .map(Reply::bulk)
+            .collect(),
+    )
+}
+
+/// SCAN cursor [MATCH pattern] [COUNT count] [TYPE type]
+fn scan(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let Ok(cursor) = arg_str(&args[1]).parse::<u64>() else {
+        return Reply::error("ERR invalid cursor");
+    };
+
+    let mut pattern = None;
+    let mut count = 10;
+    let mut type_name = None;
+    let mut i = 2;
+    while i < args.len() {
+        if i + 1 >= args.len() {
+            return syntax_error();
+        }
+        match arg_str(&args[i]).to_uppercase().as_str() {
+            "MATCH" => pattern = Some(args[i + 1].clone()),
+            "COUNT" => match parse_int(&args[i + 1]) {
+                Ok(n) if n >= 1 => count = n as usize,
+                Ok(_) => return syntax_error(),
+                Err(reply) => return reply,
+            },
+            "TYPE" => type_name = Some(arg_str(&args[i + 1]).to_lowercase()),
+            _ => return syntax_error(),
+        }
+        i += 2;
+    }
+
+    let prefix = tenant_prefix(ctx);
+    let (next, keys) = ctx.store.scan(&prefix, cursor, count, type_name.as_deref());
+    let keys = keys
+        .iter()
+        .map(|key| strip_tenant(&prefix, key))
+        .filter(|key| {
+            *key != METRICS_KEY
+                && pattern
+                    .as_ref()
+                    .is_none_or(|p| glob_match(p, key.as_bytes()))
+        })
+        .map(Reply::bulk)
+        .collect();
+
+    Reply::Array(vec![Reply::bulk(next.to_string()), Reply::Array(keys)])
+}
@@ -0,0 +1,307 @@
+use super::{
+    arg_str, block_client, key_unprefixer, parse_int, syntax_error, wrong_arity, CommandFlags,
+    CommandRegistry, CommandSpec, Context, Handler, KeySpec,
+};
+use crate::resp::Reply;
+use crate::types::{BlockAction, ListEnd, PopOutcome, Popped, Served, StoreError};
+use std::time::Duration;
+
+pub(super) fn register(registry: &mut CommandRegistry) {
+    for (name, arity, flags, handler) in [
+        ("LPUSH", -3, CommandFlags::WRITE, lpush as Handler),
+        ("RPUSH", -3, CommandFlags::WRITE, rpush),
+        ("LPOP", -2, CommandFlags::WRITE, lpop),
+        ("RPOP", -2, CommandFlags::WRITE, rpop),
+        ("LRANGE", 4, CommandFlags::READONLY, lrange),
+        ("LLEN", 2, CommandFlags::READONLY, llen),
+        ("LINDEX", 3, CommandFlags::READONLY, lindex),
+        ("LSET", 4, CommandFlags::WRITE, lset),
+        ("LTRIM", 4, CommandFlags::WRITE, ltrim),
+        ("LREM", 4, CommandFlags::WRITE, lrem),
+    ] {
+        registry.add(CommandSpec {
+            name,
+            arity,
+            flags,
+            keys: KeySpec::FIRST,
+            handler,
+        });
+    }
+    registry.add(CommandSpec {
+        name: "LMOVE",
+        arity: 5,
+        flags: CommandFlags::WRITE,
+        keys: KeySpec {
+            first: 1,
+            last: 2,
+            step: 1,
+        },
+        handler: lmove,
+    });
+    for (name, handler) in [("BLPOP", blpop as Handler), ("BRPOP", brpop)] {
+        registry.add(CommandSpec {
+            name,
+            arity: -3,
+            flags: CommandFlags::WRITE,
+            // Every argument but the trailing timeout
+            keys: KeySpec {
+                first: 1,
+                last: -2,
+                step: 1,
+            },
+            handler,
+        });
+    }
+    registry.add(CommandSpec {
+        name: "BLMOVE",
+        arity: 6,
+        flags: CommandFlags::WRITE,
+        keys: KeySpec {
+            first: 1,
+            last: 2,
+            step: 1,
+        },
+        handler: blmove,
+    });
+}
+
+/// Parses a LEFT or RIGHT argument.
+fn parse_list_end(arg: &[u8]) -> Result<ListEnd, Reply> {
+    match arg_str(arg).to_uppercase().as_str() {
+        "LEFT" => Ok(ListEnd::Left),
+        "RIGHT" => Ok(ListEnd::Right),
+        _ => Err(syntax_error()),
+    }
+}
+
+fn push(ctx: &mut Context, args: &[Vec<u8>], end: ListEnd) -> Reply {
+    let values = args[2..].to_vec();
+    match ctx.store.push(arg_str(&args[1]), values, end) {
+        Ok(len) => Reply::Integer(len as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// LPUSH key element [element ...]
+fn lpush(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    push(ctx, args, ListEnd::Left)
+}
+
+/// RPUSH key element [element ...]
+fn rpush(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    push(ctx, args, ListEnd::Right)
+}
+
+fn pop(ctx: &mut Context, args: &[Vec<u8>], end: ListEnd) -> Reply {
+    if args.len() > 3 {
+        return wrong_arity(&arg_str(&args[0]));
+    }
+    let count = match args.get(2).map(|arg| parse_int(arg)) {
+        None => None,
+        Some(Ok(count)) if count >= 0 => Some(count as usize),
+        Some(Ok(_)) => return Reply::error("ERR value is out of range, must be positive"),
+        Some(Err(reply)) => return reply,
+    };
+
+    match (
+        ctx.store.pop(&arg_str(&args[1]), end, count.unwrap_or(1)),
+        count,
+    ) {
+        (Ok(Some(items)), Some(_)) => Reply::Array(items.into_iter().map(Reply::bulk).collect()),
+        (Ok(Some(items)), None) => items.into_iter().next().map_or(Reply::Null, Reply::bulk),
+        (Ok(None), Some(_)) => Reply::NullArray,
+        (Ok(None), None) => Reply::Null,
+        (Err(e), _) => Reply::error(e.to_string()),
+    }
+}
+
+/// LPOP key [count]
+fn lpop(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    pop(ctx, args, ListEnd::Left)
+}
+
+/// RPOP key [count]
+fn rpop(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    pop(ctx, args, ListEnd::Right)
+}
+
+/// LRANGE key start stop
+fn lrange(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let (start, stop) = match (parse_int(&args[2]), parse_int(&args[3])) {
+        (Ok(start), Ok(stop)) => (start, stop),
+        (Err(reply), _) | (_, Err(reply)) => return reply,
+    };
+    match ctx.store.list_range(&arg_str(&args[1]), start, stop) {
+        Ok(items) => Reply::Array(items.into_iter().map(Reply::bulk).collect()),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// LLEN key
+fn llen(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.list_len(&arg_str(&args[1])) {
+        Ok(len) => Reply::Integer(len as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// LINDEX key index
+fn lindex(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let index = match parse_int(&args[2]) {
+        Ok(index) => index,
+        Err(reply) => return reply,
+    };
+    match ctx.store.list_index(&arg_str(&args[1]), index) {
+        Ok(item) => item.map_or(Reply::Null, Reply::bulk),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// LSET key index element
+fn lset(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let index = match parse_int(&args[2]) {
+        Ok(index) => index,
+        Err(reply) => return reply,
+    };
+    match ctx
+        .store
+        .list_set(&arg_str(&args[1]), index, args[3].clone())
+    {
+        Ok(()) => Reply::ok(),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// LTRIM key start stop
+fn ltrim(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let (start, stop) = match (parse_int(&args[2]), parse_int(&args[3])) {
+        (Ok(start), Ok(stop)) => (start, stop),
+        (Err(reply), _) | (_, Err(reply)) => return reply,
+    };
+    match ctx.store.list_trim(&arg_str(&args[1]), start, stop) {
+        Ok(()) => Reply::ok(),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// LREM key count element
+fn lrem(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let count = match parse_int(&args[2]) {
+        Ok(count) => count,
+        Err(reply) => return reply,
+    };
+    match ctx.store.list_remove(&arg_str(&args[1]), count, &args[3]) {
+        Ok(removed) => Reply::Integer(removed as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// LMOVE source destination LEFT|RIGHT LEFT|RIGHT
+fn lmove(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let (from, to) = match (parse_list_end(&args[3]), parse_list_end(&args[4])) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(reply), _) | (_, Err(reply)) => return reply,
+    };
+    match ctx
+        .store
+        .list_move(&arg_str(&args[1]), &arg_str(&args[2]), from, to)
+    {
+        Ok(item) => item.map_or(Reply::Null, Reply::bulk),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// Parses a blocking timeout in seconds, where 0 means wait forever.
+fn parse_timeout(arg: &[u8]) -> Result<Option<Duration>, Reply> {
+    let seconds = std::str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|f| f.is_finite())
+        .ok_or_else(|| Reply::error("ERR timeout is not a float or out of range"))?;
+    if seconds < 0.0 {
+        return Err(Reply::error("ERR timeout is negative"));
+    }
+    if seconds == 0.0 {
+        return Ok(None);
+    }
+    Duration::try_from_secs_f64(seconds)
+        .map(Some)
+        .map_err(|_| Reply::error("ERR timeout is not a float or out of range"))
+}
+
+/// Replies straight away if an item was available, otherwise parks the
+/// command on the connection until it is served or `timeout` passes.
+fn serve_or_block(
+    ctx: &mut Context,
+    outcome: Result<PopOutcome, StoreError>,
+    timeout: Option<Duration>,
+    reply: impl FnOnce(Option<Result<Popped, StoreError>>) -> Reply + Send + 'static,
+) -> Reply {
+    match outcome {
+        Ok(PopOutcome::Ready(popped)) => reply(Some(Ok(popped))),
+        // List waiters are only ever handed list items
+        Ok(PopOutcome::Blocked(ticket)) => block_client(ctx, ticket, timeout, move |served| {
+            reply(served.map(|served| match served? {
+                Served::Item(popped) => Ok(popped),
+                Served::Entries { .. } => Err(StoreError::WrongType),
+            }))
+        }),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+fn blocking_pop(ctx: &mut Context, args: &[Vec<u8>], end: ListEnd) -> Reply {
+    let timeout = match parse_timeout(&args[args.len() - 1]) {
+        Ok(timeout) => timeout,
+        Err(reply) => return reply,
+    };
+    let keys = args[1..args.len() - 1]
+        .iter()
+        .map(|arg| arg_str(arg))
+        .collect();
+
+    let unprefix = key_unprefixer(ctx);
+    let outcome = ctx.store.pop_or_block(keys, BlockAction::Pop(end));
+    serve_or_block(ctx, outcome, timeout, move |served| match served {
+        Some(Ok(popped)) => Reply::Array(vec![
+            Reply::bulk(unprefix(popped.key)),
+            Reply::bulk(popped.value),
+        ]),
+        Some(Err(e)) => Reply::error(e.to_string()),
+        None => Reply::NullArray,
+    })
+}
+
+/// BLPOP key [key ...] timeout
+fn blpop(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    blocking_pop(ctx, args, ListEnd::Left)
+}
+
+/// BRPOP key [key ...] timeout
+fn brpop(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    blocking_pop(ctx, args, ListEnd::Right)
+}
+
+/// BLMOVE source destination LEFT|RIGHT LEFT|RIGHT timeout
+fn blmove(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let (from, to) = match (parse_list_end(&args[3]), parse_list_end(&args[4])) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(reply), _) | (_, Err(reply)) => return reply,
+    };
+    let timeout = match parse_timeout(&args[5]) {
+        Ok(timeout) => timeout,
+        Err(reply) => return reply,
+    };
+
+    let action = BlockAction::Move {
+        destination: arg_str(&args[2]),
+        from,
+        to,
+    };
+    let outcome = ctx.store.pop_or_block(vec![arg_str(&args[1])], action);
+    serve_or_block(ctx, outcome, timeout, |served| match served {
+        Some(Ok(popped)) => Reply::bulk(popped.value),
+        Some(Err(e)) => Reply::error(e.to_string()),
+        None => Reply::Null,
+    })
+}
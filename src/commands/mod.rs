@@ -0,0 +1,297 @@
+mod connection;
+mod expire;
+mod hash;
+mod keys;
+mod list;
+mod server;
+mod set;
+mod stream;
+mod string;
+mod zset;
+
+use crate::handler::Connection;
+use crate::resp::Reply;
+use crate::store::RedisStore;
+use crate::types::{BlockTicket, Served, StoreError};
+use std::collections::HashMap;
+use std::ops::BitOr;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+/// Behavioural flags attached to every command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandFlags(u8);
+
+impl CommandFlags {
+    pub const NONE: CommandFlags = CommandFlags(0);
+    /// May modify the keyspace.
+    pub const WRITE: CommandFlags = CommandFlags(1);
+    /// Reads the keyspace without modifying it.
+    pub const READONLY: CommandFlags = CommandFlags(1 << 1);
+    /// Administrative command affecting the whole server.
+    pub const ADMIN: CommandFlags = CommandFlags(1 << 2);
+    /// Recorded in the tenant's `_metrics` log.
+    pub const METRICS: CommandFlags = CommandFlags(1 << 3);
+
+    pub fn contains(self, other: CommandFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for CommandFlags {
+    type Output = CommandFlags;
+
+    fn bitor(self, rhs: CommandFlags) -> CommandFlags {
+        CommandFlags(self.0 | rhs.0)
+    }
+}
+
+/// Which arguments are keys, in the style of Redis' `first`, `last`, `step`.
+///
+/// `last` may be negative to count from the end of the argument list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeySpec {
+    pub first: usize,
+    pub last: i32,
+    pub step: usize,
+}
+
+impl KeySpec {
+    pub const NONE: KeySpec = KeySpec {
+        first: 0,
+        last: 0,
+        step: 0,
+    };
+    /// A single key in the first argument position.
+    pub const FIRST: KeySpec = KeySpec {
+        first: 1,
+        last: 1,
+        step: 1,
+    };
+
+    /// Returns the indices of key arguments for a command with `argc` arguments.
+    pub fn positions(&self, argc: usize) -> Vec<usize> {
+        if self.step == 0 || self.first >= argc {
+            return Vec::new();
+        }
+        let last = if self.last < 0 {
+            argc as i32 + self.last
+        } else {
+            self.last.min(argc as i32 - 1)
+        };
+        if last < self.first as i32 {
+            return Vec::new();
+        }
+        (self.first..=last as usize).step_by(self.step).collect()
+    }
+}
+
+/// State a command runs against: the shared store and the calling connection.
+pub struct Context<'a> {
+    pub store: &'a RedisStore,
+    pub connection: &'a mut Connection,
+    /// Set by a blocking command that has to wait; the connection replies
+    /// once it is served or times out, instead of with the returned reply.
+    pub blocked: Option<Blocked>,
+}
+
+/// Builds a blocked command's reply from what it was served, or from
+/// `None` if it timed out.
+pub type BlockedReply = Box<dyn FnOnce(Option<Result<Served, StoreError>>) -> Reply + Send>;
+
+/// A command parked until one of its keys receives data.
+pub struct Blocked {
+    pub ticket: BlockTicket,
+    /// How long to wait; `None` waits indefinitely.
+    pub timeout: Option<Duration>,
+    pub reply: BlockedReply,
+}
+
+pub trait Command: Send + Sync {
+    /// Upper-case command name used for lookup.
+    fn name(&self) -> &'static str;
+
+    /// Number of arguments including the command name; negative means "at least".
+    fn arity(&self) -> i32;
+
+    fn flags(&self) -> CommandFlags;
+
+    fn keys(&self) -> KeySpec {
+        KeySpec::NONE
+    }
+
+    /// Indices of the key arguments in `args`. Commands whose keys depend on
+    /// the arguments themselves (e.g. a `numkeys` count) override this.
+    fn key_positions(&self, args: &[Vec<u8>]) -> Vec<usize> {
+        self.keys().positions(args.len())
+    }
+
+    /// Runs the command. Key arguments have already been tenant-prefixed.
+    fn execute(&self, ctx: &mut Context, args: &[Vec<u8>]) -> Reply;
+
+    fn check_arity(&self, argc: usize) -> bool {
+        let arity = self.arity();
+        if arity >= 0 {
+            argc == arity as usize
+        } else {
+            argc >= arity.unsigned_abs() as usize
+        }
+    }
+
+    /// Whether the command touches the tenant keyspace and so needs a tenant.
+    fn is_keyspace(&self) -> bool {
+        let flags = self.flags();
+        flags.contains(CommandFlags::WRITE) || flags.contains(CommandFlags::READONLY)
+    }
+}
+
+/// Function implementing a table-driven command.
+pub type Handler = fn(&mut Context, &[Vec<u8>]) -> Reply;
+
+/// Table-driven command backed by a plain function.
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub arity: i32,
+    pub flags: CommandFlags,
+    pub keys: KeySpec,
+    pub handler: Handler,
+}
+
+impl Command for CommandSpec {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn arity(&self) -> i32 {
+        self.arity
+    }
+
+    fn flags(&self) -> CommandFlags {
+        self.flags
+    }
+
+    fn keys(&self) -> KeySpec {
+        self.keys
+    }
+
+    fn execute(&self, ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+        (self.handler)(ctx, args)
+    }
+}
+
+pub struct CommandRegistry {
+    commands: HashMap<&'static str, Box<dyn Command>>,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        let mut registry = CommandRegistry {
+            commands: HashMap::new(),
+        };
+        connection::register(&mut registry);
+        expire::register(&mut registry);
+        hash::register(&mut registry);
+        keys::register(&mut registry);
+        list::register(&mut registry);
+        server::register(&mut registry);
+        set::register(&mut registry);
+        stream::register(&mut registry);
+        string::register(&mut registry);
+        zset::register(&mut registry);
+        registry
+    }
+
+    pub fn add(&mut self, command: impl Command + 'static) {
+        self.commands.insert(command.name(), Box::new(command));
+    }
+
+    /// Looks a command up by name, case-insensitively.
+    pub fn get(&self, name: &[u8]) -> Option<&dyn Command> {
+        let name = String::from_utf8_lossy(name).to_ascii_uppercase();
+        self.commands.get(name.as_str()).map(|c| c.as_ref())
+    }
+}
+
+static REGISTRY: LazyLock<CommandRegistry> = LazyLock::new(CommandRegistry::new);
+
+/// The registry of every command the server understands.
+pub fn registry() -> &'static CommandRegistry {
+    &REGISTRY
+}
+
+/// Lossily decodes a key, name or option keyword. Values never pass through
+/// here; they are stored as the exact bytes sent.
+pub(crate) fn arg_str(arg: &[u8]) -> String {
+    String::from_utf8_lossy(arg).into_owned()
+}
+
+/// The `tenant:` prefix every key of the calling connection carries.
+pub(crate) fn tenant_prefix(ctx: &Context) -> String {
+    format!("{}:", ctx.connection.tenant.as_deref().unwrap_or_default())
+}
+
+/// `key` as the client named it, without the prefix from [`tenant_prefix`].
+/// Takes the prefix rather than the context so replies built after a
+/// blocking wait can use it too.
+pub(crate) fn strip_tenant<'a>(prefix: &str, key: &'a str) -> &'a str {
+    key.strip_prefix(prefix).unwrap_or(key)
+}
+
+/// Strips the caller's tenant prefix from keys in replies. Owns the prefix
+/// so replies built after a blocking wait, when `ctx` is gone, can use it.
+pub(crate) fn key_unprefixer(ctx: &Context) -> impl Fn(String) -> String + Send + 'static {
+    let prefix = tenant_prefix(ctx);
+    move |key| strip_tenant(&prefix, &key).to_string()
+}
+
+/// Parks the command on the connection until it is served or `timeout`
+/// passes; `reply` builds the reply from what was served, or from `None`
+/// on timeout.
+pub(crate) fn block_client(
+    ctx: &mut Context,
+    ticket: BlockTicket,
+    timeout: Option<Duration>,
+    reply: impl FnOnce(Option<Result<Served, StoreError>>) -> Reply + Send + 'static,
+) -> Reply {
+    ctx.blocked = Some(Blocked {
+        ticket,
+        timeout,
+        reply: Box::new(reply),
+    });
+    // Never sent: the connection replies once the wait is over
+    Reply::Null
+}
+
+pub(crate) fn wrong_arity(name: &str) -> Reply {
+    Reply::error(format!(
+        "ERR wrong number of arguments for '{}' command",
+        name.to_ascii_lowercase()
+    ))
+}
+
+pub(crate) fn syntax_error() -> Reply {
+    Reply::error("ERR syntax error")
+}
+
+/// Parses a signed 64-bit integer argument.
+pub(crate) fn parse_int(arg: &[u8]) -> Result<i64, Reply> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .ok_or_else(|| Reply::error("ERR value is not an integer or out of range"))
+}
+
+/// Parses a finite floating point argument.
+pub(crate) fn parse_float(arg: &[u8]) -> Result<f64, Reply> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|f| f.is_finite())
+        .ok_or_else(|| Reply::error("ERR value is not a valid float"))
+}
@@ -0,0 +1,48 @@
+use super::{CommandFlags, CommandRegistry, CommandSpec, Context, KeySpec};
+use crate::resp::{Protocol, Reply};
+
+pub const SERVER_VERSION: &str = "0.0.1";
+
+pub(super) fn register(registry: &mut CommandRegistry) {
+    registry.add(CommandSpec {
+        name: "INFO",
+        arity: -1,
+        flags: CommandFlags::NONE,
+        keys: KeySpec::NONE,
+        handler: info,
+    });
+}
+
+/// INFO is a `field:value` text blob on RESP2 and a map on RESP3.
+fn info(ctx: &mut Context, _args: &[Vec<u8>]) -> Reply {
+    let stats = ctx.store.expire_stats();
+    let fields = vec![
+        ("redis_version", SERVER_VERSION.to_string()),
+        ("expired_keys", stats.expired_keys.to_string()),
+        ("expired_subkeys", stats.expired_subkeys.to_string()),
+        (
+            "expired_stale_perc",
+            format!("{:.2}", stats.expired_stale_perc * 100.0),
+        ),
+        (
+            "expired_time_cap_reached_count",
+            stats.expired_time_cap_reached_count.to_string(),
+        ),
+    ];
+
+    match ctx.connection.protocol {
+        Protocol::Resp2 => Reply::bulk(
+            fields
+                .iter()
+                .map(|(field, value)| format!("{}:{}", field, value))
+                .collect::<Vec<_>>()
+                .join("\r\n"),
+        ),
+        Protocol::Resp3 => Reply::Map(
+            fields
+                .into_iter()
+                .map(|(field, value)| (Reply::bulk(field), Reply::bulk(value)))
+                .collect(),
+        ),
+    }
+}
@@ -0,0 +1,145 @@
+use super::{arg_str, CommandFlags, CommandRegistry, CommandSpec, Context, Handler, KeySpec};
+use crate::resp::Reply;
+use crate::types::SetOp;
+
+/// Every argument after the command name is a key.
+const ALL_KEYS: KeySpec = KeySpec {
+    first: 1,
+    last: -1,
+    step: 1,
+};
+
+pub(super) fn register(registry: &mut CommandRegistry) {
+    for (name, arity, flags, handler) in [
+        ("SADD", -3, CommandFlags::WRITE, sadd as Handler),
+        ("SREM", -3, CommandFlags::WRITE, srem),
+        ("SMEMBERS", 2, CommandFlags::READONLY, smembers),
+        ("SISMEMBER", 3, CommandFlags::READONLY, sismember),
+        ("SCARD", 2, CommandFlags::READONLY, scard),
+    ] {
+        registry.add(CommandSpec {
+            name,
+            arity,
+            flags,
+            keys: KeySpec::FIRST,
+            handler,
+        });
+    }
+    for (name, handler) in [
+        ("SINTER", sinter as Handler),
+        ("SUNION", sunion),
+        ("SDIFF", sdiff),
+    ] {
+        registry.add(CommandSpec {
+            name,
+            arity: -2,
+            flags: CommandFlags::READONLY,
+            keys: ALL_KEYS,
+            handler,
+        });
+    }
+    for (name, handler) in [
+        ("SINTERSTORE", sinterstore as Handler),
+        ("SUNIONSTORE", sunionstore),
+        ("SDIFFSTORE", sdiffstore),
+    ] {
+        registry.add(CommandSpec {
+            name,
+            arity: -3,
+            flags: CommandFlags::WRITE,
+            // The destination as well as every source
+            keys: ALL_KEYS,
+            handler,
+        });
+    }
+}
+
+fn members_reply(members: Vec<Vec<u8>>) -> Reply {
+    Reply::Set(members.into_iter().map(Reply::bulk).collect())
+}
+
+/// SADD key member [member ...]
+fn sadd(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.set_add(arg_str(&args[1]), args[2..].to_vec()) {
+        Ok(added) => Reply::Integer(added as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// SREM key member [member ...]
+fn srem(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.set_remove(&arg_str(&args[1]), &args[2..]) {
+        Ok(removed) => Reply::Integer(removed as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// SMEMBERS key
+fn smembers(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.set_members(&arg_str(&args[1])) {
+        Ok(members) => members_reply(members),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// SISMEMBER key member
+fn sismember(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.set_is_member(&arg_str(&args[1]), &args[2]) {
+        Ok(present) => Reply::Integer(present as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// SCARD key
+fn scard(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.set_card(&arg_str(&args[1])) {
+        Ok(len) => Reply::Integer(len as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+fn combine(ctx: &mut Context, args: &[Vec<u8>], op: SetOp) -> Reply {
+    let keys: Vec<String> = args[1..].iter().map(|arg| arg_str(arg)).collect();
+    match ctx.store.set_combine(&keys, op) {
+        Ok(members) => members_reply(members),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// SINTER key [key ...]
+fn sinter(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    combine(ctx, args, SetOp::Inter)
+}
+
+/// SUNION key [key ...]
+fn sunion(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    combine(ctx, args, SetOp::Union)
+}
+
+/// SDIFF key [key ...]
+fn sdiff(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    combine(ctx, args, SetOp::Diff)
+}
+
+fn combine_store(ctx: &mut Context, args: &[Vec<u8>], op: SetOp) -> Reply {
+    let keys: Vec<String> = args[2..].iter().map(|arg| arg_str(arg)).collect();
+    match ctx.store.set_combine_store(arg_str(&args[1]), &keys, op) {
+        Ok(len) => Reply::Integer(len as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// SINTERSTORE destination key [key ...]
+fn sinterstore(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    combine_store(ctx, args, SetOp::Inter)
+}
+
+/// SUNIONSTORE destination key [key ...]
+fn sunionstore(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    combine_store(ctx, args, SetOp::Union)
+}
+
+/// SDIFFSTORE destination key [key ...]
+fn sdiffstore(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    combine_store(ctx, args, SetOp::Diff)
+}
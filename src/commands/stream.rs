@@ -0,0 +1,810 @@
+use super::{
+    arg_str, block_client, key_unprefixer, parse_int, syntax_error, wrong_arity, Command,
+    CommandFlags, CommandRegistry, CommandSpec, Context, Handler, KeySpec,
+};
+use crate::resp::{Protocol, Reply};
+use crate::types::{
+    PendingInfo, ReadOutcome, Served, StoreError, StreamEntry, StreamId, StreamTrim, TrimThreshold,
+    XAddId, XClaimOptions,
+};
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::time::Duration;
+
+pub(super) fn register(registry: &mut CommandRegistry) {
+    for (name, arity, flags, handler) in [
+        ("XADD", -5, CommandFlags::WRITE, xadd as Handler),
+        ("XRANGE", -4, CommandFlags::READONLY, xrange),
+        ("XREVRANGE", -4, CommandFlags::READONLY, xrevrange),
+        ("XLEN", 2, CommandFlags::READONLY, xlen),
+        ("XTRIM", -4, CommandFlags::WRITE, xtrim),
+        ("XACK", -4, CommandFlags::WRITE, xack),
+        ("XPENDING", -3, CommandFlags::READONLY, xpending),
+        ("XCLAIM", -6, CommandFlags::WRITE, xclaim),
+        ("XAUTOCLAIM", -6, CommandFlags::WRITE, xautoclaim),
+    ] {
+        registry.add(CommandSpec {
+            name,
+            arity,
+            flags,
+            keys: KeySpec::FIRST,
+            handler,
+        });
+    }
+    registry.add(CommandSpec {
+        name: "XGROUP",
+        arity: -2,
+        flags: CommandFlags::WRITE,
+        // XGROUP <subcommand> key
+        keys: KeySpec {
+            first: 2,
+            last: 2,
+            step: 1,
+        },
+        handler: xgroup,
+    });
+    registry.add(XRead {
+        name: "XREAD",
+        arity: -4,
+        flags: CommandFlags::READONLY,
+    });
+    // Writes, as delivering entries updates the group's pending list
+    registry.add(XRead {
+        name: "XREADGROUP",
+        arity: -7,
+        flags: CommandFlags::WRITE,
+    });
+}
+
+/// XREAD and XREADGROUP, whose keys are the first half of the arguments
+/// after STREAMS.
+struct XRead {
+    name: &'static str,
+    arity: i32,
+    flags: CommandFlags,
+}
+
+impl Command for XRead {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn arity(&self) -> i32 {
+        self.arity
+    }
+
+    fn flags(&self) -> CommandFlags {
+        self.flags
+    }
+
+    fn keys(&self) -> KeySpec {
+        KeySpec::NONE
+    }
+
+    /// The stream keys, leaving their IDs alone.
+    fn key_positions(&self, args: &[Vec<u8>]) -> Vec<usize> {
+        let Some(streams) = streams_position(args) else {
+            return Vec::new();
+        };
+        let rest = args.len() - streams - 1;
+        if !rest.is_multiple_of(2) {
+            return Vec::new();
+        }
+        (streams + 1..streams + 1 + rest / 2).collect()
+    }
+
+    fn execute(&self, ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+        xread(ctx, args, self.name)
+    }
+}
+
+/// The index of the STREAMS argument, skipping the options before it.
+fn streams_position(args: &[Vec<u8>]) -> Option<usize> {
+    let mut i = 1;
+    while i < args.len() {
+        match arg_str(&args[i]).to_uppercase().as_str() {
+            "STREAMS" => return Some(i),
+            "COUNT" | "BLOCK" => i += 2,
+            "GROUP" => i += 3,
+            "NOACK" => i += 1,
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn invalid_id() -> Reply {
+    Reply::error("ERR Invalid stream ID specified as stream command argument")
+}
+
+/// Parses `ms-seq`, or a bare `ms` taking `default_seq`.
+fn parse_id(arg: &[u8], default_seq: u64) -> Result<StreamId, Reply> {
+    let arg = std::str::from_utf8(arg).map_err(|_| invalid_id())?;
+    let (ms, seq) = match arg.split_once('-') {
+        Some((ms, seq)) => (ms, seq.parse().map_err(|_| invalid_id())?),
+        None => (arg, default_seq),
+    };
+    let ms = ms.parse().map_err(|_| invalid_id())?;
+    Ok(StreamId::new(ms, seq))
+}
+
+/// Parses the XADD ID: `*`, `ms-*` or an explicit ID.
+fn parse_xadd_id(arg: &[u8]) -> Result<XAddId, Reply> {
+    match arg {
+        b"*" => Ok(XAddId::Auto),
+        _ => match arg.strip_suffix(b"-*") {
+            Some(ms) => arg_str(ms)
+                .parse()
+                .map(XAddId::AutoSeq)
+                .map_err(|_| invalid_id()),
+            None => parse_id(arg, 0).map(XAddId::Explicit),
+        },
+    }
+}
+
+/// Parses an XRANGE interval end: `-`, `+`, or an ID with `(` before it
+/// making it exclusive. A bare `ms` covers all of its sequence numbers.
+fn parse_range_bound(arg: &[u8], is_start: bool) -> Result<Bound<StreamId>, Reply> {
+    let default_seq = if is_start { 0 } else { u64::MAX };
+    match arg {
+        b"-" | b"+" => Ok(Bound::Unbounded),
+        _ => match arg.strip_prefix(b"(") {
+            Some(id) => parse_id(id, default_seq).map(Bound::Excluded),
+            None => parse_id(arg, default_seq).map(Bound::Included),
+        },
+    }
+}
+
+/// Parses `MAXLEN|MINID [=|~] threshold [LIMIT count]` starting at
+/// `args[i]`, returning the trim and the index just past it.
+fn parse_trim(args: &[Vec<u8>], mut i: usize) -> Result<(StreamTrim, usize), Reply> {
+    let by_len = arg_str(&args[i]).eq_ignore_ascii_case("MAXLEN");
+    i += 1;
+    let approximate = match args.get(i).map(|arg| arg.as_slice()) {
+        Some(b"~") => {
+            i += 1;
+            true
+        }
+        Some(b"=") => {
+            i += 1;
+            false
+        }
+        _ => false,
+    };
+
+    let Some(arg) = args.get(i) else {
+        return Err(syntax_error());
+    };
+    let threshold = if by_len {
+        match parse_int(arg)? {
+            len if len < 0 => return Err(Reply::error("ERR The MAXLEN argument must be >= 0.")),
+            len => TrimThreshold::MaxLen(len as usize),
+        }
+    } else {
+        TrimThreshold::MinId(parse_id(arg, 0)?)
+    };
+    i += 1;
+
+    let mut limit = None;
+    if args
+        .get(i)
+        .is_some_and(|arg| arg_str(arg).eq_ignore_ascii_case("LIMIT"))
+    {
+        let Some(arg) = args.get(i + 1) else {
+            return Err(syntax_error());
+        };
+        let count = parse_int(arg)?;
+        if count < 0 {
+            return Err(Reply::error("ERR The LIMIT argument must be >= 0."));
+        }
+        if !approximate {
+            return Err(Reply::error(
+                "ERR syntax error, LIMIT cannot be used without the special ~ option",
+            ));
+        }
+        // LIMIT 0 lifts the cap
+        limit = (count > 0).then_some(count as usize);
+        i += 2;
+    }
+    Ok((StreamTrim { threshold, limit }, i))
+}
+
+/// An entry as its ID and a flat field/value array.
+fn entry_reply(entry: StreamEntry) -> Reply {
+    Reply::Array(vec![
+        Reply::bulk(entry.id.to_string()),
+        Reply::Array(
+            entry
+                .fields
+                .into_iter()
+                .flat_map(|(field, value)| [Reply::bulk(field), Reply::bulk(value)])
+                .collect(),
+        ),
+    ])
+}
+
+fn entries_reply(entries: Vec<StreamEntry>) -> Reply {
+    Reply::Array(entries.into_iter().map(entry_reply).collect())
+}
+
+/// XREAD's per-stream results: key/entries pairs on RESP2, a map on RESP3,
+/// or a null array if nothing was read.
+fn streams_reply(protocol: Protocol, streams: Vec<(String, Vec<StreamEntry>)>) -> Reply {
+    if streams.is_empty() {
+        return Reply::NullArray;
+    }
+    match protocol {
+        Protocol::Resp2 => Reply::Array(
+            streams
+                .into_iter()
+                .map(|(key, entries)| Reply::Array(vec![Reply::bulk(key), entries_reply(entries)]))
+                .collect(),
+        ),
+        Protocol::Resp3 => Reply::Map(
+            streams
+                .into_iter()
+                .map(|(key, entries)| (Reply::bulk(key), entries_reply(entries)))
+                .collect(),
+        ),
+    }
+}
+
+/// XADD key [NOMKSTREAM] [MAXLEN|MINID [=|~] threshold [LIMIT count]]
+/// *|id field value [field value ...]
+fn xadd(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let mut create = true;
+    let mut trim = None;
+    let mut i = 2;
+    while let Some(arg) = args.get(i) {
+        match arg_str(arg).to_uppercase().as_str() {
+            "NOMKSTREAM" => {
+                create = false;
+                i += 1;
+            }
+            "MAXLEN" | "MINID" => match parse_trim(args, i) {
+                Ok((parsed, next)) => {
+                    trim = Some(parsed);
+                    i = next;
+                }
+                Err(reply) => return reply,
+            },
+            _ => break,
+        }
+    }
+
+    let Some(id) = args.get(i) else {
+        return wrong_arity("xadd");
+    };
+    let pairs = &args[i + 1..];
+    if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+        return wrong_arity("xadd");
+    }
+    let id = match parse_xadd_id(id) {
+        Ok(id) => id,
+        Err(reply) => return reply,
+    };
+    let fields = pairs
+        .chunks(2)
+        .map(|pair| (pair[0].clone(), pair[1].clone()))
+        .collect();
+
+    match ctx.store.xadd(arg_str(&args[1]), id, fields, create, trim) {
+        Ok(Some(id)) => Reply::bulk(id.to_string()),
+        Ok(None) => Reply::Null,
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+fn range(ctx: &mut Context, args: &[Vec<u8>], rev: bool) -> Reply {
+    // XREVRANGE takes the interval highest first
+    let (start, end) = if rev {
+        (&args[3], &args[2])
+    } else {
+        (&args[2], &args[3])
+    };
+    let (start, end) = match (
+        parse_range_bound(start, true),
+        parse_range_bound(end, false),
+    ) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Err(reply), _) | (_, Err(reply)) => return reply,
+    };
+    let count = match &args[4..] {
+        [] => None,
+        [option, count] if arg_str(option).eq_ignore_ascii_case("COUNT") => {
+            match parse_int(count) {
+                Ok(count) => Some(count.max(0) as usize),
+                Err(reply) => return reply,
+            }
+        }
+        _ => return syntax_error(),
+    };
+
+    match ctx.store.xrange(&arg_str(&args[1]), start, end, rev, count) {
+        Ok(entries) => entries_reply(entries),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// XRANGE key start end [COUNT count]
+fn xrange(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    range(ctx, args, false)
+}
+
+/// XREVRANGE key end start [COUNT count]
+fn xrevrange(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    range(ctx, args, true)
+}
+
+/// XLEN key
+fn xlen(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.xlen(&arg_str(&args[1])) {
+        Ok(len) => Reply::Integer(len as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// XTRIM key MAXLEN|MINID [=|~] threshold [LIMIT count]
+fn xtrim(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let strategy = arg_str(&args[2]);
+    if !strategy.eq_ignore_ascii_case("MAXLEN") && !strategy.eq_ignore_ascii_case("MINID") {
+        return syntax_error();
+    }
+    let trim = match parse_trim(args, 2) {
+        Ok((trim, next)) if next == args.len() => trim,
+        Ok(_) => return syntax_error(),
+        Err(reply) => return reply,
+    };
+    match ctx.store.xtrim(&arg_str(&args[1]), trim) {
+        Ok(evicted) => Reply::Integer(evicted as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// Parses the XREAD BLOCK timeout in milliseconds, 0 meaning forever.
+fn parse_block_timeout(arg: &[u8]) -> Result<Option<Duration>, Reply> {
+    let ms = std::str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .ok_or_else(|| Reply::error("ERR timeout is not an integer or out of range"))?;
+    match ms {
+        ms if ms < 0 => Err(Reply::error("ERR timeout is negative")),
+        0 => Ok(None),
+        ms => Ok(Some(Duration::from_millis(ms as u64))),
+    }
+}
+
+/// XREAD [COUNT count] [BLOCK milliseconds] STREAMS key [key ...] id [id ...]
+///
+/// XREADGROUP GROUP group consumer [COUNT count] [BLOCK milliseconds]
+/// [NOACK] STREAMS key [key ...] id [id ...]
+fn xread(ctx: &mut Context, args: &[Vec<u8>], name: &str) -> Reply {
+    let is_group = name == "XREADGROUP";
+    let mut group = None;
+    let mut count = None;
+    let mut block = None;
+    let mut no_ack = false;
+    let mut i = 1;
+    while i < args.len() {
+        let option = arg_str(&args[i]).to_uppercase();
+        match option.as_str() {
+            "STREAMS" => break,
+            "GROUP" | "NOACK" if !is_group => {
+                return Reply::error(format!(
+                    "ERR The {option} option is only supported by XREADGROUP. You called XREAD instead."
+                ))
+            }
+            "NOACK" => {
+                no_ack = true;
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        let Some(value) = args.get(i + 1) else {
+            return syntax_error();
+        };
+        match option.as_str() {
+            "COUNT" => match parse_int(value) {
+                Ok(n) => count = (n > 0).then_some(n as usize),
+                Err(reply) => return reply,
+            },
+            "BLOCK" => match parse_block_timeout(value) {
+                Ok(timeout) => block = Some(timeout),
+                Err(reply) => return reply,
+            },
+            "GROUP" => {
+                let Some(consumer) = args.get(i + 2) else {
+                    return syntax_error();
+                };
+                group = Some((arg_str(value), arg_str(consumer)));
+                i += 1;
+            }
+            _ => return syntax_error(),
+        }
+        i += 2;
+    }
+
+    let rest = args.len().saturating_sub(i + 1);
+    if i >= args.len() || rest == 0 {
+        return syntax_error();
+    }
+    if is_group && group.is_none() {
+        return Reply::error("ERR Missing GROUP option for XREADGROUP");
+    }
+    if !rest.is_multiple_of(2) {
+        return Reply::error(format!(
+            "ERR Unbalanced '{}' list of streams: for each stream key an ID or '{}' must be specified.",
+            name.to_lowercase(),
+            if is_group { ">" } else { "$" }
+        ));
+    }
+    let (keys, ids) = args[i + 1..].split_at(rest / 2);
+    let mut streams = Vec::with_capacity(keys.len());
+    for (key, id) in keys.iter().zip(ids) {
+        // `None` is `$` for XREAD and `>` for XREADGROUP
+        let id = match (id.as_slice(), is_group) {
+            (b"$", false) | (b">", true) => None,
+            (b"$", true) => return Reply::error(
+                "ERR The $ ID is meaningless in the context of XREADGROUP: you want to read the history of this consumer by specifying a proper ID, or use the > ID to get new messages. The $ ID would just return an empty result set.",
+            ),
+            (b">", false) => return Reply::error(
+                "ERR The > ID can be specified only when calling XREADGROUP using the GROUP <group> <consumer> option.",
+            ),
+            (id, _) => match parse_id(id, 0) {
+                Ok(id) => Some(id),
+                Err(reply) => return reply,
+            },
+        };
+        streams.push((arg_str(key), id));
+    }
+
+    let unprefix_key = key_unprefixer(ctx);
+    let protocol = ctx.connection.protocol;
+    let unprefix = move |streams: Vec<(String, Vec<StreamEntry>)>| {
+        streams
+            .into_iter()
+            .map(|(key, entries)| (unprefix_key(key), entries))
+            .collect()
+    };
+    let outcome = match group {
+        Some((group, consumer)) => {
+            ctx.store
+                .xreadgroup(&group, &consumer, streams, count, no_ack, block.is_some())
+        }
+        None => ctx.store.xread(streams, count, block.is_some()),
+    };
+    match outcome {
+        Ok(ReadOutcome::Ready(streams)) => streams_reply(protocol, unprefix(streams)),
+        Ok(ReadOutcome::Blocked(ticket)) => {
+            block_client(ctx, ticket, block.flatten(), move |served| match served {
+                Some(Ok(Served::Entries { key, entries })) => {
+                    streams_reply(protocol, unprefix(vec![(key, entries)]))
+                }
+                // Stream readers are only ever handed entries
+                Some(Ok(Served::Item(_))) => Reply::error(StoreError::WrongType.to_string()),
+                Some(Err(e)) => Reply::error(e.to_string()),
+                None => Reply::NullArray,
+            })
+        }
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// Parses an ID argument, or `$` as `None`.
+fn parse_id_or_last(arg: &[u8]) -> Result<Option<StreamId>, Reply> {
+    match arg {
+        b"$" => Ok(None),
+        _ => parse_id(arg, 0).map(Some),
+    }
+}
+
+/// XGROUP CREATE key group id|$ [MKSTREAM], XGROUP SETID key group id|$,
+/// XGROUP DESTROY key group, XGROUP CREATECONSUMER key group consumer,
+/// XGROUP DELCONSUMER key group consumer
+fn xgroup(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let subcommand = arg_str(&args[1]).to_uppercase();
+    let arity_ok = match subcommand.as_str() {
+        "CREATE" => args.len() == 5 || args.len() == 6,
+        "SETID" | "CREATECONSUMER" | "DELCONSUMER" => args.len() == 5,
+        "DESTROY" => args.len() == 4,
+        _ => {
+            return Reply::error(format!(
+                "ERR unknown subcommand '{}'. Try XGROUP HELP.",
+                arg_str(&args[1])
+            ))
+        }
+    };
+    if !arity_ok {
+        return wrong_arity(&format!("xgroup|{subcommand}"));
+    }
+
+    let (key, group) = (arg_str(&args[2]), arg_str(&args[3]));
+    let result = match subcommand.as_str() {
+        "CREATE" => {
+            let mkstream = match args.get(5) {
+                None => false,
+                Some(arg) if arg_str(arg).eq_ignore_ascii_case("MKSTREAM") => true,
+                Some(_) => return syntax_error(),
+            };
+            match parse_id_or_last(&args[4]) {
+                Ok(id) => ctx
+                    .store
+                    .xgroup_create(&key, &group, id, mkstream)
+                    .map(|_| Reply::ok()),
+                Err(reply) => return reply,
+            }
+        }
+        "SETID" => match parse_id_or_last(&args[4]) {
+            Ok(id) => ctx
+                .store
+                .xgroup_set_id(&key, &group, id)
+                .map(|_| Reply::ok()),
+            Err(reply) => return reply,
+        },
+        "DESTROY" => ctx
+            .store
+            .xgroup_destroy(&key, &group)
+            .map(|destroyed| Reply::Integer(destroyed as i64)),
+        "CREATECONSUMER" => ctx
+            .store
+            .xgroup_create_consumer(&key, &group, &arg_str(&args[4]))
+            .map(|created| Reply::Integer(created as i64)),
+        _ => ctx
+            .store
+            .xgroup_del_consumer(&key, &group, &arg_str(&args[4]))
+            .map(|pending| Reply::Integer(pending as i64)),
+    };
+    result.unwrap_or_else(|e| Reply::error(e.to_string()))
+}
+
+/// XACK key group id [id ...]
+fn xack(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let mut ids = Vec::with_capacity(args.len() - 3);
+    for arg in &args[3..] {
+        match parse_id(arg, 0) {
+            Ok(id) => ids.push(id),
+            Err(reply) => return reply,
+        }
+    }
+    match ctx.store.xack(&arg_str(&args[1]), &arg_str(&args[2]), &ids) {
+        Ok(acked) => Reply::Integer(acked as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// Parses a min-idle-time argument, where negative values count as 0.
+fn parse_idle(arg: &[u8]) -> Result<u64, Reply> {
+    parse_int(arg).map(|ms| ms.max(0) as u64)
+}
+
+/// XPENDING key group [[IDLE min-idle-time] start end count [consumer]]
+fn xpending(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let (key, group) = (arg_str(&args[1]), arg_str(&args[2]));
+    if args.len() == 3 {
+        return match ctx.store.xpending(
+            &key,
+            &group,
+            (Bound::Unbounded, Bound::Unbounded),
+            usize::MAX,
+            None,
+            0,
+        ) {
+            Ok(pending) => pending_summary(pending),
+            Err(e) => Reply::error(e.to_string()),
+        };
+    }
+
+    let mut i = 3;
+    let mut min_idle_ms = 0;
+    if arg_str(&args[i]).eq_ignore_ascii_case("IDLE") {
+        match args.get(i + 1).map(|arg| parse_idle(arg)) {
+            Some(Ok(ms)) => min_idle_ms = ms,
+            Some(Err(reply)) => return reply,
+            None => return syntax_error(),
+        }
+        i += 2;
+    }
+    if args.len() != i + 3 && args.len() != i + 4 {
+        return syntax_error();
+    }
+    let (start, end) = match (
+        parse_range_bound(&args[i], true),
+        parse_range_bound(&args[i + 1], false),
+    ) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Err(reply), _) | (_, Err(reply)) => return reply,
+    };
+    let count = match parse_int(&args[i + 2]) {
+        Ok(count) => count.max(0) as usize,
+        Err(reply) => return reply,
+    };
+    let consumer = args.get(i + 3).map(|arg| arg_str(arg));
+
+    match ctx.store.xpending(
+        &key,
+        &group,
+        (start, end),
+        count,
+        consumer.as_deref(),
+        min_idle_ms,
+    ) {
+        Ok(pending) => Reply::Array(
+            pending
+                .into_iter()
+                .map(|info| {
+                    Reply::Array(vec![
+                        Reply::bulk(info.id.to_string()),
+                        Reply::bulk(info.consumer),
+                        Reply::Integer(info.idle_ms as i64),
+                        Reply::Integer(info.deliveries as i64),
+                    ])
+                })
+                .collect(),
+        ),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// The XPENDING summary: how many entries are pending, the lowest and
+/// highest of their IDs, and how many each consumer has. Per-consumer
+/// counts are bulk strings, as Redis sends them.
+fn pending_summary(pending: Vec<PendingInfo>) -> Reply {
+    let (Some(first), Some(last)) = (pending.first(), pending.last()) else {
+        return Reply::Array(vec![
+            Reply::Integer(0),
+            Reply::Null,
+            Reply::Null,
+            Reply::NullArray,
+        ]);
+    };
+    let (first, last) = (first.id.to_string(), last.id.to_string());
+    let mut per_consumer: BTreeMap<String, usize> = BTreeMap::new();
+    for info in &pending {
+        *per_consumer.entry(info.consumer.clone()).or_default() += 1;
+    }
+    Reply::Array(vec![
+        Reply::Integer(pending.len() as i64),
+        Reply::bulk(first),
+        Reply::bulk(last),
+        Reply::Array(
+            per_consumer
+                .into_iter()
+                .map(|(consumer, count)| {
+                    Reply::Array(vec![Reply::bulk(consumer), Reply::bulk(count.to_string())])
+                })
+                .collect(),
+        ),
+    ])
+}
+
+/// Claimed entries, or with JUSTID only their IDs.
+fn claimed_reply(entries: Vec<StreamEntry>, just_id: bool) -> Reply {
+    if just_id {
+        Reply::Array(
+            entries
+                .into_iter()
+                .map(|entry| Reply::bulk(entry.id.to_string()))
+                .collect(),
+        )
+    } else {
+        entries_reply(entries)
+    }
+}
+
+/// XCLAIM key group consumer min-idle-time id [id ...] [IDLE ms]
+/// [TIME unix-time-milliseconds] [RETRYCOUNT count] [FORCE] [JUSTID]
+/// [LASTID lastid]
+fn xclaim(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let min_idle_ms = match parse_idle(&args[4]) {
+        Ok(ms) => ms,
+        Err(reply) => return reply,
+    };
+    // IDs run until the first argument that is not one
+    let mut ids = Vec::new();
+    let mut i = 5;
+    while let Some(Ok(id)) = args.get(i).map(|arg| parse_id(arg, 0)) {
+        ids.push(id);
+        i += 1;
+    }
+    if ids.is_empty() {
+        return invalid_id();
+    }
+
+    let mut options = XClaimOptions::default();
+    while i < args.len() {
+        let option = arg_str(&args[i]).to_uppercase();
+        match option.as_str() {
+            "FORCE" => options.force = true,
+            "JUSTID" => options.just_id = true,
+            "IDLE" | "TIME" | "RETRYCOUNT" | "LASTID" if i + 1 < args.len() => {
+                let value = &args[i + 1];
+                let parsed = match option.as_str() {
+                    "IDLE" => parse_idle(value).map(|ms| options.idle_ms = Some(ms)),
+                    "TIME" => parse_idle(value).map(|ms| options.time_ms = Some(ms)),
+                    "RETRYCOUNT" => parse_idle(value).map(|n| options.retry_count = Some(n)),
+                    _ => parse_id(value, 0).map(|id| options.last_id = Some(id)),
+                };
+                if let Err(reply) = parsed {
+                    return reply;
+                }
+                i += 1;
+            }
+            _ => {
+                return Reply::error(format!(
+                    "ERR Unrecognized XCLAIM option '{}'",
+                    arg_str(&args[i])
+                ))
+            }
+        }
+        i += 1;
+    }
+
+    match ctx.store.xclaim(
+        &arg_str(&args[1]),
+        &arg_str(&args[2]),
+        &arg_str(&args[3]),
+        min_idle_ms,
+        &ids,
+        options,
+    ) {
+        Ok(claimed) => claimed_reply(claimed, options.just_id),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// XAUTOCLAIM key group consumer min-idle-time start [COUNT count] [JUSTID]
+fn xautoclaim(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let min_idle_ms = match parse_idle(&args[4]) {
+        Ok(ms) => ms,
+        Err(reply) => return reply,
+    };
+    let start = match &args[5][..] {
+        b"-" => StreamId::MIN,
+        arg => match parse_id(arg, 0) {
+            Ok(id) => id,
+            Err(reply) => return reply,
+        },
+    };
+    let mut count = 100;
+    let mut just_id = false;
+    let mut i = 6;
+    while i < args.len() {
+        match arg_str(&args[i]).to_uppercase().as_str() {
+            "JUSTID" => just_id = true,
+            "COUNT" if i + 1 < args.len() => {
+                match parse_int(&args[i + 1]) {
+                    Ok(n) if n > 0 => count = n as usize,
+                    Ok(_) => return Reply::error("ERR COUNT must be > 0"),
+                    Err(reply) => return reply,
+                }
+                i += 1;
+            }
+            _ => return syntax_error(),
+        }
+        i += 1;
+    }
+
+    match ctx.store.xautoclaim(
+        &arg_str(&args[1]),
+        &arg_str(&args[2]),
+        &arg_str(&args[3]),
+        min_idle_ms,
+        start,
+        count,
+        just_id,
+    ) {
+        Ok(claim) => Reply::Array(vec![
+            Reply::bulk(claim.next.to_string()),
+            claimed_reply(claim.claimed, just_id),
+            Reply::Array(
+                claim
+                    .deleted
+                    .into_iter()
+                    .map(|id| Reply::bulk(id.to_string()))
+                    .collect(),
+            ),
+        ]),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
@@ -0,0 +1,373 @@
+use super::{
+    arg_str, parse_float, parse_int, syntax_error, wrong_arity, CommandFlags, CommandRegistry,
+    CommandSpec, Context, Handler, KeySpec,
+};
+use crate::resp::{Protocol, Reply};
+use crate::types::{RedisGetResult, SetCondition, SetExpiry, SetOptions, StoreError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub(super) fn register(registry: &mut CommandRegistry) {
+    registry.add(CommandSpec {
+        name: "GET",
+        arity: 2,
+        flags: CommandFlags::READONLY | CommandFlags::METRICS,
+        keys: KeySpec::FIRST,
+        handler: get,
+    });
+    registry.add(CommandSpec {
+        name: "SET",
+        arity: -3,
+        flags: CommandFlags::WRITE | CommandFlags::METRICS,
+        keys: KeySpec::FIRST,
+        handler: set,
+    });
+    registry.add(CommandSpec {
+        name: "MGET",
+        arity: -2,
+        flags: CommandFlags::READONLY,
+        keys: KeySpec {
+            first: 1,
+            last: -1,
+            step: 1,
+        },
+        handler: mget,
+    });
+    for (name, handler) in [("MSET", mset as Handler), ("MSETNX", msetnx)] {
+        registry.add(CommandSpec {
+            name,
+            arity: -3,
+            flags: CommandFlags::WRITE,
+            keys: KeySpec {
+                first: 1,
+                last: -1,
+                step: 2,
+            },
+            handler,
+        });
+    }
+    for (name, arity, handler) in [
+        ("INCR", 2, incr as Handler),
+        ("DECR", 2, decr),
+        ("INCRBY", 3, incrby),
+        ("DECRBY", 3, decrby),
+        ("INCRBYFLOAT", 3, incrbyfloat),
+    ] {
+        registry.add(CommandSpec {
+            name,
+            arity,
+            flags: CommandFlags::WRITE,
+            keys: KeySpec::FIRST,
+            handler,
+        });
+    }
+    for (name, arity, flags, handler) in [
+        ("GETRANGE", 4, CommandFlags::READONLY, getrange as Handler),
+        ("SETRANGE", 4, CommandFlags::WRITE, setrange),
+        ("STRLEN", 2, CommandFlags::READONLY, strlen),
+        ("GETDEL", 2, CommandFlags::WRITE, getdel),
+        ("GETEX", -2, CommandFlags::WRITE, getex),
+        ("GETSET", 3, CommandFlags::WRITE, getset),
+    ] {
+        registry.add(CommandSpec {
+            name,
+            arity,
+            flags,
+            keys: KeySpec::FIRST,
+            handler,
+        });
+    }
+    registry.add(CommandSpec {
+        name: "APPEND",
+        arity: 3,
+        flags: CommandFlags::WRITE | CommandFlags::METRICS,
+        keys: KeySpec::FIRST,
+        handler: append,
+    });
+}
+
+/// GET key, where key may carry a `?field=value` JSON search
+fn get(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let key = arg_str(&args[1]);
+    match ctx.store.get(&key) {
+        // Filtered searches come back as native maps on RESP3
+        RedisGetResult::Value(value)
+            if key.contains('?') && ctx.connection.protocol == Protocol::Resp3 =>
+        {
+            match serde_json::from_slice(&value) {
+                Ok(json) => Reply::from_json(&json),
+                Err(_) => Reply::bulk(value),
+            }
+        }
+        RedisGetResult::Value(value) => Reply::bulk(value),
+        RedisGetResult::None | RedisGetResult::Expired => Reply::Null,
+        RedisGetResult::WrongType => Reply::error(StoreError::WrongType.to_string()),
+    }
+}
+
+/// SET key value [NX | XX] [GET] [EX seconds | PX milliseconds |
+/// EXAT unix-time-seconds | PXAT unix-time-milliseconds | KEEPTTL]
+fn set(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let options = match parse_set_options(&args[3..]) {
+        Ok(options) => options,
+        Err(reply) => return reply,
+    };
+
+    let outcome = match ctx
+        .store
+        .set_with_options(arg_str(&args[1]), args[2].clone(), &options)
+    {
+        Ok(outcome) => outcome,
+        Err(e) => return Reply::error(e.to_string()),
+    };
+
+    match (options.get, outcome.written) {
+        (true, _) => outcome.previous.map_or(Reply::Null, Reply::bulk),
+        (false, true) => Reply::ok(),
+        (false, false) => Reply::Null,
+    }
+}
+
+fn parse_set_options(args: &[Vec<u8>]) -> Result<SetOptions, Reply> {
+    let mut options = SetOptions::default();
+    // Repeating a flag is allowed; combining conflicting ones is not
+    let mut expiry_option: Option<String> = None;
+    let mut i = 0;
+
+    while i < args.len() {
+        let option = arg_str(&args[i]).to_uppercase();
+        match option.as_str() {
+            "NX" | "XX" => {
+                let condition = if option == "NX" {
+                    SetCondition::IfNotExists
+                } else {
+                    SetCondition::IfExists
+                };
+                if options.condition != SetCondition::Always && options.condition != condition {
+                    return Err(syntax_error());
+                }
+                options.condition = condition;
+            }
+            "GET" => options.get = true,
+            "KEEPTTL" | "EX" | "PX" | "EXAT" | "PXAT" => {
+                if expiry_option.as_ref().is_some_and(|seen| *seen != option) {
+                    return Err(syntax_error());
+                }
+                options.expiry = if option == "KEEPTTL" {
+                    SetExpiry::Keep
+                } else if i + 1 < args.len() {
+                    i += 1;
+                    SetExpiry::At(parse_expire_time(&option, &args[i], "set")?)
+                } else {
+                    return Err(syntax_error());
+                };
+                expiry_option = Some(option);
+            }
+            _ => return Err(syntax_error()),
+        }
+        i += 1;
+    }
+
+    Ok(options)
+}
+
+/// Resolves an EX/PX/EXAT/PXAT argument to the instant it names.
+pub(super) fn parse_expire_time(
+    unit: &str,
+    arg: &[u8],
+    command: &str,
+) -> Result<SystemTime, Reply> {
+    let invalid = || Reply::error(format!("ERR invalid expire time in '{}' command", command));
+    let amount = parse_int(arg)?;
+    if amount <= 0 {
+        return Err(invalid());
+    }
+    let amount = amount as u64;
+    let at = match unit {
+        "EX" => SystemTime::now().checked_add(Duration::from_secs(amount)),
+        "PX" => SystemTime::now().checked_add(Duration::from_millis(amount)),
+        "EXAT" => UNIX_EPOCH.checked_add(Duration::from_secs(amount)),
+        _ => UNIX_EPOCH.checked_add(Duration::from_millis(amount)),
+    };
+    at.ok_or_else(invalid)
+}
+
+/// APPEND key json, pushing onto the JSON array stored at key
+fn append(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.append(arg_str(&args[1]), args[2].clone()) {
+        Ok(_) => Reply::ok(),
+        Err(e) if e.is::<StoreError>() => Reply::error(e.to_string()),
+        Err(e) => Reply::error(format!("ERR {}", e)),
+    }
+}
+
+/// MGET key [key ...]
+fn mget(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let keys: Vec<String> = args[1..].iter().map(|arg| arg_str(arg)).collect();
+    Reply::Array(
+        ctx.store
+            .get_many(&keys)
+            .into_iter()
+            .map(|value| value.map_or(Reply::Null, Reply::bulk))
+            .collect(),
+    )
+}
+
+/// Splits MSET-style arguments into key/value pairs.
+fn pairs_from(args: &[Vec<u8>]) -> Option<Vec<(String, Vec<u8>)>> {
+    if !args.len().is_multiple_of(2) {
+        return None;
+    }
+    Some(
+        args.chunks(2)
+            .map(|pair| (arg_str(&pair[0]), pair[1].clone()))
+            .collect(),
+    )
+}
+
+/// MSET key value [key value ...]
+fn mset(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match pairs_from(&args[1..]) {
+        Some(pairs) => {
+            ctx.store.set_many(pairs, false);
+            Reply::ok()
+        }
+        None => wrong_arity("mset"),
+    }
+}
+
+/// MSETNX key value [key value ...]
+fn msetnx(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match pairs_from(&args[1..]) {
+        Some(pairs) => Reply::Integer(ctx.store.set_many(pairs, true) as i64),
+        None => wrong_arity("msetnx"),
+    }
+}
+
+fn incr_reply(ctx: &mut Context, args: &[Vec<u8>], delta: i64) -> Reply {
+    match ctx.store.incr_by(arg_str(&args[1]), delta) {
+        Ok(value) => Reply::Integer(value),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// INCR key
+fn incr(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    incr_reply(ctx, args, 1)
+}
+
+/// DECR key
+fn decr(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    incr_reply(ctx, args, -1)
+}
+
+/// INCRBY key increment
+fn incrby(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match parse_int(&args[2]) {
+        Ok(delta) => incr_reply(ctx, args, delta),
+        Err(reply) => reply,
+    }
+}
+
+/// DECRBY key decrement
+fn decrby(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match parse_int(&args[2]).map(i64::checked_neg) {
+        Ok(Some(delta)) => incr_reply(ctx, args, delta),
+        Ok(None) => Reply::error("ERR decrement would overflow"),
+        Err(reply) => reply,
+    }
+}
+
+/// INCRBYFLOAT key increment
+fn incrbyfloat(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let delta = match parse_float(&args[2]) {
+        Ok(delta) => delta,
+        Err(reply) => return reply,
+    };
+    match ctx.store.incr_by_float(arg_str(&args[1]), delta) {
+        Ok(value) => Reply::bulk(value),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// GETRANGE key start end
+fn getrange(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let (start, end) = match (parse_int(&args[2]), parse_int(&args[3])) {
+        (Ok(start), Ok(end)) => (start, end),
+        (Err(reply), _) | (_, Err(reply)) => return reply,
+    };
+    match ctx.store.get_range(&arg_str(&args[1]), start, end) {
+        Ok(bytes) => Reply::Bulk(bytes),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// SETRANGE key offset value
+fn setrange(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let offset = match parse_int(&args[2]) {
+        Ok(offset) if offset >= 0 => offset as usize,
+        Ok(_) => return Reply::error("ERR offset is out of range"),
+        Err(reply) => return reply,
+    };
+    match ctx.store.set_range(arg_str(&args[1]), offset, &args[3]) {
+        Ok(len) => Reply::Integer(len as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// STRLEN key
+fn strlen(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.strlen(&arg_str(&args[1])) {
+        Ok(len) => Reply::Integer(len as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// GETDEL key
+fn getdel(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    bulk_or_null(ctx.store.get_del(&arg_str(&args[1])))
+}
+
+/// GETEX key [EX seconds | PX milliseconds | EXAT unix-time-seconds |
+/// PXAT unix-time-milliseconds | PERSIST]
+fn getex(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let mut expiry = SetExpiry::Keep;
+    let mut i = 2;
+    while i < args.len() {
+        let option = arg_str(&args[i]).to_uppercase();
+        match option.as_str() {
+            "PERSIST" if expiry == SetExpiry::Keep => expiry = SetExpiry::Persist,
+            "EX" | "PX" | "EXAT" | "PXAT" if expiry == SetExpiry::Keep && i + 1 < args.len() => {
+                i += 1;
+                match parse_expire_time(&option, &args[i], "getex") {
+                    Ok(at) => expiry = SetExpiry::At(at),
+                    Err(reply) => return reply,
+                }
+            }
+            _ => return syntax_error(),
+        }
+        i += 1;
+    }
+
+    bulk_or_null(ctx.store.get_ex(&arg_str(&args[1]), expiry))
+}
+
+/// GETSET key value
+fn getset(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let options = SetOptions {
+        get: true,
+        ..SetOptions::default()
+    };
+    bulk_or_null(
+        ctx.store
+            .set_with_options(arg_str(&args[1]), args[2].clone(), &options)
+            .map(|outcome| outcome.previous),
+    )
+}
+
+/// Replies with the value read by a store call, nil if absent.
+fn bulk_or_null(result: Result<Option<Vec<u8>>, StoreError>) -> Reply {
+    match result {
+        Ok(value) => value.map_or(Reply::Null, Reply::bulk),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
@@ -0,0 +1,440 @@
+use super::{
+    arg_str, parse_int, syntax_error, Command, CommandFlags, CommandRegistry, CommandSpec, Context,
+    Handler, KeySpec,
+};
+use crate::resp::{Protocol, Reply};
+use crate::types::{Aggregate, LexBound, SetOp, ZAddOptions, ZRangeBy};
+use std::ops::Bound;
+
+pub(super) fn register(registry: &mut CommandRegistry) {
+    for (name, arity, flags, handler) in [
+        ("ZADD", -4, CommandFlags::WRITE, zadd as Handler),
+        ("ZINCRBY", 4, CommandFlags::WRITE, zincrby),
+        ("ZREM", -3, CommandFlags::WRITE, zrem),
+        ("ZSCORE", 3, CommandFlags::READONLY, zscore),
+        ("ZCARD", 2, CommandFlags::READONLY, zcard),
+        ("ZRANK", -3, CommandFlags::READONLY, zrank),
+        ("ZREVRANK", -3, CommandFlags::READONLY, zrevrank),
+        ("ZCOUNT", 4, CommandFlags::READONLY, zcount),
+        ("ZRANGE", -4, CommandFlags::READONLY, zrange),
+        ("ZPOPMIN", -2, CommandFlags::WRITE, zpopmin),
+        ("ZPOPMAX", -2, CommandFlags::WRITE, zpopmax),
+    ] {
+        registry.add(CommandSpec {
+            name,
+            arity,
+            flags,
+            keys: KeySpec::FIRST,
+            handler,
+        });
+    }
+    registry.add(ZStore {
+        name: "ZUNIONSTORE",
+        op: SetOp::Union,
+    });
+    registry.add(ZStore {
+        name: "ZINTERSTORE",
+        op: SetOp::Inter,
+    });
+}
+
+/// ZUNIONSTORE and ZINTERSTORE, whose source keys are counted by a
+/// `numkeys` argument rather than sitting at fixed positions.
+struct ZStore {
+    name: &'static str,
+    op: SetOp,
+}
+
+impl Command for ZStore {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn arity(&self) -> i32 {
+        -4
+    }
+
+    fn flags(&self) -> CommandFlags {
+        CommandFlags::WRITE
+    }
+
+    fn keys(&self) -> KeySpec {
+        KeySpec::FIRST
+    }
+
+    /// The destination, then as many sources as `numkeys` names.
+    fn key_positions(&self, args: &[Vec<u8>]) -> Vec<usize> {
+        let numkeys = args
+            .get(2)
+            .and_then(|arg| arg_str(arg).parse::<usize>().ok())
+            .unwrap_or(0);
+        let sources = (3..args.len()).take(numkeys);
+        std::iter::once(1).chain(sources).collect()
+    }
+
+    fn execute(&self, ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+        zcombine_store(ctx, args, self.name, self.op)
+    }
+}
+
+/// Parses a score, where `inf`, `+inf` and `-inf` are allowed.
+fn parse_score(arg: &[u8]) -> Result<f64, Reply> {
+    std::str::from_utf8(arg)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .filter(|f| !f.is_nan())
+        .ok_or_else(|| Reply::error("ERR value is not a valid float"))
+}
+
+/// Parses a score interval end: a score, `(` before it making it exclusive.
+fn parse_score_bound(arg: &[u8]) -> Result<Bound<f64>, Reply> {
+    let (exclusive, score) = match arg.strip_prefix(b"(") {
+        Some(score) => (true, score),
+        None => (false, arg),
+    };
+    let score = parse_score(score).map_err(|_| Reply::error("ERR min or max is not a float"))?;
+    Ok(if exclusive {
+        Bound::Excluded(score)
+    } else {
+        Bound::Included(score)
+    })
+}
+
+/// Parses a lexicographic interval end: `-`, `+`, `[member` or `(member`.
+fn parse_lex_bound(arg: &[u8]) -> Result<LexBound, Reply> {
+    match arg.split_first() {
+        Some((b'-', [])) => Ok(LexBound::Min),
+        Some((b'+', [])) => Ok(LexBound::Max),
+        Some((b'[', member)) => Ok(LexBound::Included(member.to_vec())),
+        Some((b'(', member)) => Ok(LexBound::Excluded(member.to_vec())),
+        _ => Err(Reply::error("ERR min or max not valid string range item")),
+    }
+}
+
+/// Members, optionally with their scores: flattened on RESP2, as
+/// member/score pairs on RESP3.
+fn scored_reply(ctx: &Context, items: Vec<(Vec<u8>, f64)>, with_scores: bool) -> Reply {
+    if !with_scores {
+        return Reply::Array(
+            items
+                .into_iter()
+                .map(|(member, _)| Reply::bulk(member))
+                .collect(),
+        );
+    }
+    match ctx.connection.protocol {
+        Protocol::Resp2 => Reply::Array(
+            items
+                .into_iter()
+                .flat_map(|(member, score)| [Reply::bulk(member), Reply::Double(score)])
+                .collect(),
+        ),
+        Protocol::Resp3 => Reply::Array(
+            items
+                .into_iter()
+                .map(|(member, score)| {
+                    Reply::Array(vec![Reply::bulk(member), Reply::Double(score)])
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// ZADD key [NX|XX] [GT|LT] [CH] [INCR] score member [score member ...]
+fn zadd(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let mut options = ZAddOptions::default();
+    let mut incr = false;
+    let mut i = 2;
+    while i < args.len() {
+        match arg_str(&args[i]).to_uppercase().as_str() {
+            "NX" => options.only_new = true,
+            "XX" => options.only_existing = true,
+            "GT" => options.only_greater = true,
+            "LT" => options.only_less = true,
+            "CH" => options.count_changed = true,
+            "INCR" => incr = true,
+            _ => break,
+        }
+        i += 1;
+    }
+
+    let pairs = &args[i..];
+    if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+        return syntax_error();
+    }
+    if incr && pairs.len() > 2 {
+        return Reply::error("ERR INCR option supports a single increment-element pair");
+    }
+    if options.only_new && options.only_existing {
+        return Reply::error("ERR XX and NX options at the same time are not compatible");
+    }
+    if (options.only_greater && options.only_less)
+        || (options.only_new && (options.only_greater || options.only_less))
+    {
+        return Reply::error("ERR GT, LT, and/or NX options at the same time are not compatible");
+    }
+    let mut entries = Vec::with_capacity(pairs.len() / 2);
+    for pair in pairs.chunks(2) {
+        match parse_score(&pair[0]) {
+            Ok(score) => entries.push((score, pair[1].clone())),
+            Err(reply) => return reply,
+        }
+    }
+
+    let key = arg_str(&args[1]);
+    if incr {
+        let (delta, member) = entries.pop().unwrap();
+        return match ctx.store.zincr_by(key, member, delta, options) {
+            Ok(score) => score.map_or(Reply::Null, Reply::Double),
+            Err(e) => Reply::error(e.to_string()),
+        };
+    }
+    match ctx.store.zadd(key, entries, options) {
+        Ok(counted) => Reply::Integer(counted as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// ZINCRBY key increment member
+fn zincrby(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let delta = match parse_score(&args[2]) {
+        Ok(delta) => delta,
+        Err(reply) => return reply,
+    };
+    match ctx.store.zincr_by(
+        arg_str(&args[1]),
+        args[3].clone(),
+        delta,
+        ZAddOptions::default(),
+    ) {
+        Ok(score) => score.map_or(Reply::Null, Reply::Double),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// ZREM key member [member ...]
+fn zrem(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.zrem(&arg_str(&args[1]), &args[2..]) {
+        Ok(removed) => Reply::Integer(removed as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// ZSCORE key member
+fn zscore(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.zscore(&arg_str(&args[1]), &args[2]) {
+        Ok(score) => score.map_or(Reply::Null, Reply::Double),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// ZCARD key
+fn zcard(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    match ctx.store.zcard(&arg_str(&args[1])) {
+        Ok(len) => Reply::Integer(len as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+fn rank(ctx: &mut Context, args: &[Vec<u8>], rev: bool) -> Reply {
+    let with_score = match args.get(3) {
+        None => false,
+        Some(arg) if args.len() == 4 && arg_str(arg).eq_ignore_ascii_case("WITHSCORE") => true,
+        Some(_) => return syntax_error(),
+    };
+    match (
+        ctx.store.zrank(&arg_str(&args[1]), &args[2], rev),
+        with_score,
+    ) {
+        (Ok(Some((rank, score))), true) => {
+            Reply::Array(vec![Reply::Integer(rank as i64), Reply::Double(score)])
+        }
+        (Ok(Some((rank, _))), false) => Reply::Integer(rank as i64),
+        (Ok(None), true) => Reply::NullArray,
+        (Ok(None), false) => Reply::Null,
+        (Err(e), _) => Reply::error(e.to_string()),
+    }
+}
+
+/// ZRANK key member [WITHSCORE]
+fn zrank(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    rank(ctx, args, false)
+}
+
+/// ZREVRANK key member [WITHSCORE]
+fn zrevrank(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    rank(ctx, args, true)
+}
+
+/// ZCOUNT key min max
+fn zcount(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let (min, max) = match (parse_score_bound(&args[2]), parse_score_bound(&args[3])) {
+        (Ok(min), Ok(max)) => (min, max),
+        (Err(reply), _) | (_, Err(reply)) => return reply,
+    };
+    match ctx.store.zcount(&arg_str(&args[1]), min, max) {
+        Ok(count) => Reply::Integer(count as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// ZRANGE key start stop [BYSCORE|BYLEX] [REV] [LIMIT offset count] [WITHSCORES]
+fn zrange(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    let mut by_score = false;
+    let mut by_lex = false;
+    let mut rev = false;
+    let mut limit = None;
+    let mut with_scores = false;
+    let mut i = 4;
+    while i < args.len() {
+        match arg_str(&args[i]).to_uppercase().as_str() {
+            "BYSCORE" => by_score = true,
+            "BYLEX" => by_lex = true,
+            "REV" => rev = true,
+            "WITHSCORES" => with_scores = true,
+            "LIMIT" if i + 2 < args.len() => {
+                match (parse_int(&args[i + 1]), parse_int(&args[i + 2])) {
+                    (Ok(offset), Ok(count)) => limit = Some((offset, count)),
+                    (Err(reply), _) | (_, Err(reply)) => return reply,
+                }
+                i += 2;
+            }
+            _ => return syntax_error(),
+        }
+        i += 1;
+    }
+    if by_score && by_lex {
+        return syntax_error();
+    }
+    if limit.is_some() && !by_score && !by_lex {
+        return Reply::error(
+            "ERR syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX",
+        );
+    }
+    if with_scores && by_lex {
+        return Reply::error(
+            "ERR syntax error, WITHSCORES not supported in combination with BYLEX",
+        );
+    }
+
+    // Score and lex intervals are given highest first when reversed
+    let (min, max) = if rev && (by_score || by_lex) {
+        (&args[3], &args[2])
+    } else {
+        (&args[2], &args[3])
+    };
+    let by = if by_score {
+        match (parse_score_bound(min), parse_score_bound(max)) {
+            (Ok(min), Ok(max)) => ZRangeBy::Score(min, max),
+            (Err(reply), _) | (_, Err(reply)) => return reply,
+        }
+    } else if by_lex {
+        match (parse_lex_bound(min), parse_lex_bound(max)) {
+            (Ok(min), Ok(max)) => ZRangeBy::Lex(min, max),
+            (Err(reply), _) | (_, Err(reply)) => return reply,
+        }
+    } else {
+        match (parse_int(min), parse_int(max)) {
+            (Ok(start), Ok(stop)) => ZRangeBy::Index(start, stop),
+            (Err(reply), _) | (_, Err(reply)) => return reply,
+        }
+    };
+
+    match ctx.store.zrange(&arg_str(&args[1]), &by, rev, limit) {
+        Ok(items) => scored_reply(ctx, items, with_scores),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+fn pop(ctx: &mut Context, args: &[Vec<u8>], rev: bool) -> Reply {
+    if args.len() > 3 {
+        return syntax_error();
+    }
+    let count = match args.get(2).map(|arg| parse_int(arg)) {
+        None => None,
+        Some(Ok(count)) if count >= 0 => Some(count as usize),
+        Some(Ok(_)) => return Reply::error("ERR value is out of range, must be positive"),
+        Some(Err(reply)) => return reply,
+    };
+
+    match ctx.store.zpop(&arg_str(&args[1]), rev, count.unwrap_or(1)) {
+        Ok(items) if count.is_some() => scored_reply(ctx, items, true),
+        // A single pop is a flat member/score pair on either protocol
+        Ok(items) => Reply::Array(
+            items
+                .into_iter()
+                .flat_map(|(member, score)| [Reply::bulk(member), Reply::Double(score)])
+                .collect(),
+        ),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
+
+/// ZPOPMIN key [count]
+fn zpopmin(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    pop(ctx, args, false)
+}
+
+/// ZPOPMAX key [count]
+fn zpopmax(ctx: &mut Context, args: &[Vec<u8>]) -> Reply {
+    pop(ctx, args, true)
+}
+
+/// ZUNIONSTORE/ZINTERSTORE destination numkeys key [key ...]
+/// [WEIGHTS weight [weight ...]] [AGGREGATE SUM|MIN|MAX]
+fn zcombine_store(ctx: &mut Context, args: &[Vec<u8>], name: &str, op: SetOp) -> Reply {
+    let numkeys = match parse_int(&args[2]) {
+        Ok(n) if n >= 1 => n as usize,
+        Ok(_) => {
+            return Reply::error(format!(
+                "ERR at least 1 input key is needed for '{}' command",
+                name.to_lowercase()
+            ))
+        }
+        Err(reply) => return reply,
+    };
+    if numkeys > args.len() - 3 {
+        return syntax_error();
+    }
+    let keys: Vec<String> = args[3..3 + numkeys]
+        .iter()
+        .map(|arg| arg_str(arg))
+        .collect();
+
+    let mut weights = Vec::new();
+    let mut aggregate = Aggregate::default();
+    let mut i = 3 + numkeys;
+    while i < args.len() {
+        match arg_str(&args[i]).to_uppercase().as_str() {
+            "WEIGHTS" if i + numkeys < args.len() => {
+                weights.clear();
+                for arg in &args[i + 1..=i + numkeys] {
+                    match parse_score(arg) {
+                        Ok(weight) => weights.push(weight),
+                        Err(_) => return Reply::error("ERR weight value is not a float"),
+                    }
+                }
+                i += numkeys;
+            }
+            "AGGREGATE" if i + 1 < args.len() => {
+                aggregate = match arg_str(&args[i + 1]).to_uppercase().as_str() {
+                    "SUM" => Aggregate::Sum,
+                    "MIN" => Aggregate::Min,
+                    "MAX" => Aggregate::Max,
+                    _ => return syntax_error(),
+                };
+                i += 1;
+            }
+            _ => return syntax_error(),
+        }
+        i += 1;
+    }
+
+    match ctx
+        .store
+        .zcombine_store(arg_str(&args[1]), &keys, &weights, aggregate, op)
+    {
+        Ok(len) => Reply::Integer(len as i64),
+        Err(e) => Reply::error(e.to_string()),
+    }
+}
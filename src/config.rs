@@ -0,0 +1,36 @@
+use crate::parser::DEFAULT_PROTO_MAX_BULK_LEN;
+use std::env;
+
+/// Server settings read from the environment at startup.
+pub struct Config {
+    /// Largest bulk string a client may send (`PROTO_MAX_BULK_LEN`).
+    pub proto_max_bulk_len: usize,
+    /// Active expire cycles per second (`HZ`, 1-500).
+    pub hz: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            proto_max_bulk_len: DEFAULT_PROTO_MAX_BULK_LEN,
+            hz: 10,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        let defaults = Config::default();
+        Config {
+            proto_max_bulk_len: env::var("PROTO_MAX_BULK_LEN")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.proto_max_bulk_len),
+            hz: env::var("HZ")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(|hz| hz.clamp(1, 500))
+                .unwrap_or(defaults.hz),
+        }
+    }
+}
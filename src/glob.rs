@@ -0,0 +1,82 @@
+/// Redis-style glob matching as used by KEYS, SCAN MATCH and friends.
+///
+/// Supports `*`, `?`, `[abc]`, `[^abc]`, `[a-z]` and `\` escapes.
+pub fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // Where to resume after the most recent `*` if the rest fails to match
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while s < string.len() {
+        let step = match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, s));
+                p += 1;
+                continue;
+            }
+            Some(b'?') => Some(p + 1),
+            Some(b'[') => match_class(pattern, p, string[s]),
+            Some(b'\\') if p + 1 < pattern.len() => (pattern[p + 1] == string[s]).then_some(p + 2),
+            Some(&c) => (c == string[s]).then_some(p + 1),
+            None => None,
+        };
+
+        match step {
+            Some(next) => {
+                p = next;
+                s += 1;
+            }
+            None => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    s = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Matches `c` against the `[...]` class starting at `pattern[start]`,
+/// returning the index just past the class on success.
+fn match_class(pattern: &[u8], start: usize, c: u8) -> Option<usize> {
+    let mut p = start + 1;
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+
+    let mut matched = false;
+    loop {
+        match pattern.get(p) {
+            // An unterminated class ends at the end of the pattern
+            None => break,
+            Some(b']') => {
+                p += 1;
+                break;
+            }
+            Some(b'\\') if p + 1 < pattern.len() => {
+                matched |= pattern[p + 1] == c;
+                p += 2;
+            }
+            Some(&low) if pattern.get(p + 1) == Some(&b'-') && p + 2 < pattern.len() => {
+                let high = pattern[p + 2];
+                let (low, high) = if low <= high {
+                    (low, high)
+                } else {
+                    (high, low)
+                };
+                matched |= low <= c && c <= high;
+                p += 3;
+            }
+            Some(&other) => {
+                matched |= other == c;
+                p += 1;
+            }
+        }
+    }
+
+    (matched != negate).then_some(p)
+}
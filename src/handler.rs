@@ -1,131 +1,313 @@
-use crate::metrics::{MetricsCollector, METRICS_KEY};
-use crate::parser::parse_command;
+use crate::commands::{registry, wrong_arity, Blocked, CommandFlags, Context};
+use crate::config::Config;
+use crate::metrics::{MetricsCollector, METRICS_KEY, METRICS_MAX_LEN};
+use crate::parser::RespParser;
+use crate::resp::{Protocol, Reply};
 use crate::store::RedisStore;
-use crate::types::RedisGetResult;
+use crate::types::{BlockTicket, Served, StoreError, StreamTrim, TrimThreshold, XAddId};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tokio::time;
+
+static NEXT_CLIENT_ID: AtomicU64 = AtomicU64::new(1);
 
 pub struct Connection {
-    tenant: Option<String>,
+    pub(crate) id: u64,
+    pub(crate) tenant: Option<String>,
+    pub(crate) protocol: Protocol,
 }
 
-pub async fn handle_connection(mut stream: TcpStream, store: Arc<RedisStore>) {
-    let mut connection = Connection { tenant: None };
-
-    let mut buf = [0; 4056];
-    loop {
-        let n = stream.read(&mut buf).await.unwrap();
-        let request = String::from_utf8_lossy(&buf[..n]);
-
-        let _ = handle_request(&request, &mut stream, store.clone(), &mut connection).await;
-        if n == 0 {
-            break;
+impl Connection {
+    fn new() -> Self {
+        Connection {
+            id: NEXT_CLIENT_ID.fetch_add(1, Ordering::Relaxed),
+            tenant: None,
+            protocol: Protocol::default(),
         }
     }
 }
-async fn handle_request(
-    request: &str,
-    stream: &mut TcpStream,
-    store: Arc<RedisStore>,
-    connection: &mut Connection,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let command_parts = parse_command(request);
-    let start = std::time::Instant::now();
-    let mut response_bytes = 0;
-
-    if !command_parts.is_empty() {
-        match command_parts[0].as_str().to_uppercase().as_str() {
-            "CLIENT" => {
-                if command_parts.len() > 2 && command_parts[1].to_uppercase() == "SETNAME" {
-                    connection.tenant = Some(command_parts[2].clone());
-                    stream.write_all("+OK\r\n".as_bytes()).await?;
-                }
-            }
-            cmd @ ("SET" | "GET" | "APPEND") => {
-                match &connection.tenant {
-                    Some(tenant) => {
-                        // Add tenant prefix to key
-                        let key = if command_parts.len() > 1 {
-                            format!("{}:{}", tenant, command_parts[1])
-                        } else {
-                            return Ok(());
-                        };
-
-                        let response = match cmd {
-                            "SET" => {
-                                if command_parts.len() > 2 {
-                                    let px = if command_parts.len() > 4 {
-                                        Some(command_parts[4].parse::<u64>().unwrap())
-                                    } else {
-                                        None
-                                    };
-                                    store.set(key, command_parts[2].clone(), px)?;
-                                    "+OK\r\n".to_string()
-                                } else {
-                                    "-ERR wrong number of arguments\r\n".to_string()
-                                }
-                            }
-                            "GET" => match store.get(&key) {
-                                RedisGetResult::Value(value) => format!("+{}\r\n", value),
-                                RedisGetResult::None => "+\r\n".to_string(),
-                                RedisGetResult::Expired => "$-1\r\n".to_string(),
-                            },
-                            "APPEND" => {
-                                if command_parts.len() > 2 {
-                                    match store.append(key, command_parts[2].clone()) {
-                                        Ok(_) => "+OK\r\n".to_string(),
-                                        Err(e) => format!("-ERR {}\r\n", e),
-                                    }
-                                } else {
-                                    "-ERR wrong number of arguments\r\n".to_string()
-                                }
+
+pub async fn handle_connection(mut stream: TcpStream, store: Arc<RedisStore>, config: Arc<Config>) {
+    let mut connection = Connection::new();
+
+    // Bytes received but not yet consumed by the parser; grows to fit large frames.
+    let mut buffer: Vec<u8> = Vec::with_capacity(4096);
+    let mut parser = RespParser::new(config.proto_max_bulk_len);
+    loop {
+        buffer.reserve(4096);
+        match stream.read_buf(&mut buffer).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+
+        // Replies for every frame in this read, flushed with a single write.
+        let mut out: Vec<u8> = Vec::new();
+        let mut offset = 0;
+        loop {
+            match parser.parse(&buffer[offset..]) {
+                Ok((consumed, frame)) => {
+                    offset += consumed;
+                    match frame {
+                        Some(args) => {
+                            let Some(mut pending) =
+                                handle_request(args, &mut out, &store, &mut connection)
+                            else {
+                                continue;
+                            };
+                            // Replies to earlier pipelined commands go out before the wait
+                            if !out.is_empty() && stream.write_all(&out).await.is_err() {
+                                return;
                             }
-                            _ => unreachable!(),
-                        };
-
-                        // Update response bytes and send response
-                        response_bytes = response.len();
-                        stream.write_all(response.as_bytes()).await?;
-
-                        // record metrics
-                        if let Some(tenant) = &connection.tenant {
-                            let metrics = MetricsCollector::new(tenant.to_string());
-                            let metric_entry = metrics.create_entry(
-                                command_parts.get(1).cloned().unwrap_or_default(), // endpoint (key)
-                                cmd.to_string(), // method (command)
-                                response_bytes,
-                                start.elapsed().as_micros() as u64,
-                            )?;
-
-                            // Store metric
-                            store.append(format!("{}:{}", tenant, METRICS_KEY), metric_entry)?;
+                            out.clear();
+                            let Some(served) = wait_until_served(
+                                &mut stream,
+                                &mut buffer,
+                                &store,
+                                &mut pending.blocked.ticket,
+                                pending.blocked.timeout,
+                            )
+                            .await
+                            else {
+                                return;
+                            };
+                            pending.finish(served, &mut out, &store, &connection);
                         }
-                    }
-                    None => {
-                        let response = "-ERR Tenant name required (use CLIENT SETNAME)\r\n";
-                        stream.write_all(response.as_bytes()).await?;
+                        None => break,
                     }
                 }
-            }
-            "PING" | "HEALTH" => {
-                stream.write_all("+PONG\r\n".as_bytes()).await?;
-            }
-            "ECHO" => {
-                if command_parts.len() > 1 {
-                    let response = format!("+{}\r\n", command_parts[1]);
-                    stream.write_all(response.as_bytes()).await?;
+                Err(e) => {
+                    out.extend_from_slice(format!("-ERR {}\r\n", e).as_bytes());
+                    let _ = stream.write_all(&out).await;
+                    return;
                 }
             }
-            "INFO" => {
-                let response = format!("+{}\r\n", "redis_version:0.0.1");
-                stream.write_all(response.as_bytes()).await?;
-            }
-            _ => {
-                println!("Unknown command: {:?}", command_parts);
+        }
+        buffer.drain(..offset);
+
+        if !out.is_empty() && stream.write_all(&out).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// A blocking command waiting to be served, with what its reply and
+/// metrics need once it is.
+struct Pending {
+    blocked: Blocked,
+    tenant: Option<String>,
+    endpoint: String,
+    method: &'static str,
+    start: Instant,
+}
+
+impl Pending {
+    fn finish(
+        self,
+        served: Option<Result<Served, StoreError>>,
+        out: &mut Vec<u8>,
+        store: &RedisStore,
+        connection: &Connection,
+    ) {
+        let response_start = out.len();
+        (self.blocked.reply)(served).encode(out, connection.protocol);
+        if let Some(tenant) = self.tenant {
+            record_metrics(
+                store,
+                tenant,
+                self.endpoint,
+                self.method,
+                out.len() - response_start,
+                self.start,
+            );
+        }
+    }
+}
+
+/// Waits for a blocked command to be served or time out, returning what it
+/// was served (`None` on timeout). Input arriving meanwhile is buffered for
+/// later; if the client disconnects instead, the wait is abandoned, any
+/// popped item put back, and `None` returned in place of the outcome.
+async fn wait_until_served(
+    stream: &mut TcpStream,
+    buffer: &mut Vec<u8>,
+    store: &RedisStore,
+    ticket: &mut BlockTicket,
+    timeout: Option<Duration>,
+) -> Option<Option<Result<Served, StoreError>>> {
+    let deadline = timeout.map(|timeout| time::Instant::now() + timeout);
+    let timed_out = async {
+        match deadline {
+            Some(deadline) => time::sleep_until(deadline).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(timed_out);
+
+    let connected = loop {
+        tokio::select! {
+            // A disconnect is noticed before an item that arrived with it,
+            // so the item is put back rather than written to a dead socket
+            biased;
+            read = stream.read_buf(buffer) => {
+                if matches!(read, Ok(0) | Err(_)) {
+                    break false;
+                }
             }
+            served = &mut ticket.receiver => return Some(served.ok()),
+            _ = &mut timed_out => break true,
+        }
+    };
+
+    // An item may have been handed over just before we gave up
+    let served = if store.unblock(ticket.id) {
+        None
+    } else {
+        ticket.receiver.try_recv().ok()
+    };
+    if connected {
+        return Some(served);
+    }
+    // Nobody is left to receive it, so the item goes back where it came from
+    if let (Some(Ok(Served::Item(popped))), Some(end)) = (served, ticket.requeue) {
+        let _ = store.push(popped.key, vec![popped.value], end);
+    }
+    None
+}
+
+/// Runs one request, appending its reply to `out`. A blocking command that
+/// has to wait appends nothing and is returned instead.
+fn handle_request(
+    mut args: Vec<Vec<u8>>,
+    out: &mut Vec<u8>,
+    store: &RedisStore,
+    connection: &mut Connection,
+) -> Option<Pending> {
+    if args.is_empty() {
+        return None;
+    }
+    let Some(command) = registry().get(&args[0]) else {
+        unknown_command(&args).encode(out, connection.protocol);
+        return None;
+    };
+    if !command.check_arity(args.len()) {
+        wrong_arity(command.name()).encode(out, connection.protocol);
+        return None;
+    }
+
+    let start = Instant::now();
+    let endpoint = args
+        .get(1)
+        .map(|key| String::from_utf8_lossy(key).into_owned())
+        .unwrap_or_default();
+    let tenant = if command.is_keyspace() {
+        let Some(tenant) = connection.tenant.clone() else {
+            Reply::error("ERR Tenant name required (use CLIENT SETNAME)")
+                .encode(out, connection.protocol);
+            return None;
+        };
+        let positions = command.key_positions(&args);
+        // Keys are stored as strings; decoding lossily would merge distinct keys
+        if positions
+            .iter()
+            .any(|&i| std::str::from_utf8(&args[i]).is_err())
+        {
+            Reply::error("ERR invalid key: keys must be valid UTF-8")
+                .encode(out, connection.protocol);
+            return None;
+        }
+        // Add tenant prefix to every key argument
+        for i in positions {
+            args[i] = [tenant.as_bytes(), b":", &args[i]].concat();
+        }
+        Some(tenant)
+    } else {
+        None
+    };
+    // Only commands flagged for it are recorded in the tenant's metrics
+    let tenant = tenant.filter(|_| command.flags().contains(CommandFlags::METRICS));
+
+    let mut ctx = Context {
+        store,
+        connection,
+        blocked: None,
+    };
+    let response = command.execute(&mut ctx, &args);
+    if let Some(blocked) = ctx.blocked {
+        return Some(Pending {
+            blocked,
+            tenant,
+            endpoint,
+            method: command.name(),
+            start,
+        });
+    }
+
+    // Update response bytes and send response
+    let response_start = out.len();
+    response.encode(out, connection.protocol);
+    let response_bytes = out.len() - response_start;
+
+    // record metrics
+    if let Some(tenant) = tenant {
+        record_metrics(
+            store,
+            tenant,
+            endpoint,
+            command.name(),
+            response_bytes,
+            start,
+        );
+    }
+    None
+}
+
+fn record_metrics(
+    store: &RedisStore,
+    tenant: String,
+    endpoint: String,
+    method: &str,
+    response_bytes: usize,
+    start: Instant,
+) {
+    let metrics = MetricsCollector::new(tenant.clone());
+    if let Ok(metric_entry) = metrics.create_entry(
+        endpoint,           // endpoint (key)
+        method.to_string(), // method (command)
+        response_bytes,
+        start.elapsed().as_micros() as u64,
+    ) {
+        // Store metric as a stream entry, keeping the log bounded
+        let trim = StreamTrim {
+            threshold: TrimThreshold::MaxLen(METRICS_MAX_LEN),
+            limit: None,
+        };
+        let _ = store.xadd(
+            format!("{}:{}", tenant, METRICS_KEY),
+            XAddId::Auto,
+            metric_entry.fields(),
+            true,
+            Some(trim),
+        );
+    }
+}
+
+/// Redis-style reply for a command name that is not registered.
+fn unknown_command(args: &[Vec<u8>]) -> Reply {
+    let mut preview = String::new();
+    for arg in &args[1..] {
+        if preview.len() >= 128 {
+            break;
         }
+        preview.push_str(&format!("'{}' ", String::from_utf8_lossy(arg)));
     }
-    Ok(())
+    let preview: String = preview.chars().take(128).collect();
+    Reply::error(format!(
+        "ERR unknown command '{}', with args beginning with: {}",
+        String::from_utf8_lossy(&args[0]),
+        preview
+    ))
 }
@@ -0,0 +1,234 @@
+use crate::store::blocking::BlockedClients;
+use crate::types::{ExpireStats, RedisData, RedisValue};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The key/value map guarded by `RedisStore`'s lock, together with indexes
+/// of keys carrying a TTL and of hashes with field TTLs so the active
+/// expire cycle can sample them, and an ordered index used as a stable
+/// SCAN cursor. Clients blocked on list
+/// keys are queued here too, so pushes can serve them under the same lock.
+pub(crate) struct Keyspace {
+    pub(crate) entries: HashMap<String, RedisValue>,
+    // Each key gets a sequence number on creation. Keys are ordered by
+    // (namespace, sequence), so a SCAN cursor is simply the next sequence
+    // number to visit and is unaffected by later inserts or HashMap growth.
+    sequence: HashMap<String, u64>,
+    order: BTreeMap<(String, u64), String>,
+    next_sequence: u64,
+    volatile: SampleIndex,
+    // Hashes that may have fields with a TTL. Entries go stale when the
+    // last such field is persisted or overwritten; the sweeper drops them.
+    volatile_fields: SampleIndex,
+    rng: u64,
+    pub(crate) stats: ExpireStats,
+    pub(crate) blocked: BlockedClients,
+}
+
+impl Keyspace {
+    pub(crate) fn new() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        Keyspace {
+            entries: HashMap::new(),
+            sequence: HashMap::new(),
+            order: BTreeMap::new(),
+            next_sequence: 1,
+            volatile: SampleIndex::default(),
+            volatile_fields: SampleIndex::default(),
+            rng: seed | 1,
+            stats: ExpireStats::default(),
+            blocked: BlockedClients::default(),
+        }
+    }
+
+    /// Inserts `value`, indexing the key if it carries a TTL or is a hash
+    /// with field TTLs.
+    pub(crate) fn insert(&mut self, key: String, value: RedisValue) -> Option<RedisValue> {
+        if value.expires_at.is_some() {
+            self.volatile.insert(&key);
+        } else {
+            self.volatile.remove(&key);
+        }
+        match &value.data {
+            RedisData::Hash(hash) if hash.has_field_ttls() => self.volatile_fields.insert(&key),
+            _ => self.volatile_fields.remove(&key),
+        }
+        if !self.sequence.contains_key(&key) {
+            let sequence = self.next_sequence;
+            self.next_sequence += 1;
+            self.sequence.insert(key.clone(), sequence);
+            self.order
+                .insert((namespace_of(&key).to_string(), sequence), key.clone());
+        }
+        self.entries.insert(key, value)
+    }
+
+    /// Removes `key` if its TTL has elapsed, returning whether it did.
+    ///
+    /// Every read and write path goes through this (directly or via
+    /// `get_live`) so an expired key is never observed.
+    ///
+    /// Hash fields past their own TTL are dropped here too, and a hash left
+    /// without fields is removed (without counting as an expired key).
+    pub(crate) fn expire_if_needed(&mut self, key: &str) -> bool {
+        let now = SystemTime::now();
+        let expired = self
+            .entries
+            .get(key)
+            .and_then(|v| v.expires_at)
+            .is_some_and(|expiry| now > expiry);
+        if expired {
+            self.remove(key);
+            self.stats.expired_keys += 1;
+        } else if let Some(RedisValue {
+            data: RedisData::Hash(hash),
+            ..
+        }) = self.entries.get_mut(key)
+        {
+            let removed = hash.remove_expired(now);
+            self.stats.expired_subkeys += removed as u64;
+            // A hash emptied by its last field expiring goes with it
+            if removed > 0 && hash.is_empty() {
+                self.remove(key);
+            }
+        }
+        expired
+    }
+
+    /// Looks up `key`, treating an expired key as absent.
+    pub(crate) fn get_live(&mut self, key: &str) -> Option<&RedisValue> {
+        self.expire_if_needed(key);
+        self.entries.get(key)
+    }
+
+    pub(crate) fn get_live_mut(&mut self, key: &str) -> Option<&mut RedisValue> {
+        self.expire_if_needed(key);
+        self.entries.get_mut(key)
+    }
+
+    pub(crate) fn contains_live(&mut self, key: &str) -> bool {
+        self.get_live(key).is_some()
+    }
+
+    pub(crate) fn remove(&mut self, key: &str) -> Option<RedisValue> {
+        self.volatile.remove(key);
+        self.volatile_fields.remove(key);
+        if let Some(sequence) = self.sequence.remove(key) {
+            self.order
+                .remove(&(namespace_of(key).to_string(), sequence));
+        }
+        self.entries.remove(key)
+    }
+
+    /// Visits up to `count` keys of `namespace` starting at `cursor`,
+    /// returning them with the cursor to resume from (0 once finished).
+    pub(crate) fn scan(&self, namespace: &str, cursor: u64, count: usize) -> (u64, Vec<String>) {
+        let start = (namespace.to_string(), cursor);
+        let end = (namespace.to_string(), u64::MAX);
+        let mut visited = self.order.range(start..=end);
+
+        let keys: Vec<String> = visited
+            .by_ref()
+            .take(count)
+            .map(|(_, key)| key.clone())
+            .collect();
+        let next = visited.next().map_or(0, |((_, sequence), _)| *sequence);
+        (next, keys)
+    }
+
+    /// Changes the expiry of an existing key, keeping the TTL index in step.
+    pub(crate) fn set_expiry(&mut self, key: &str, expires_at: Option<SystemTime>) {
+        let Some(value) = self.entries.get_mut(key) else {
+            return;
+        };
+        value.expires_at = expires_at;
+        self.set_expiry_tracked(key, expires_at.is_some());
+    }
+
+    /// Indexes `key` as carrying a TTL, or drops it from that index.
+    pub(crate) fn set_expiry_tracked(&mut self, key: &str, has_ttl: bool) {
+        if has_ttl {
+            self.volatile.insert(key);
+        } else {
+            self.volatile.remove(key);
+        }
+    }
+
+    /// Indexes `key` as a hash with field TTLs, or drops it from that index.
+    pub(crate) fn set_field_ttls(&mut self, key: &str, has_field_ttls: bool) {
+        if has_field_ttls {
+            self.volatile_fields.insert(key);
+        } else {
+            self.volatile_fields.remove(key);
+        }
+    }
+
+    pub(crate) fn volatile_len(&self) -> usize {
+        self.volatile.len()
+    }
+
+    pub(crate) fn volatile_fields_len(&self) -> usize {
+        self.volatile_fields.len()
+    }
+
+    /// Picks a random key that carries a TTL.
+    pub(crate) fn random_volatile_key(&mut self) -> Option<String> {
+        let index = self.random_index(self.volatile.len())?;
+        Some(self.volatile.keys[index].clone())
+    }
+
+    /// Picks a random hash that may have fields with a TTL.
+    pub(crate) fn random_volatile_fields_key(&mut self) -> Option<String> {
+        let index = self.random_index(self.volatile_fields.len())?;
+        Some(self.volatile_fields.keys[index].clone())
+    }
+
+    fn random_index(&mut self, len: usize) -> Option<usize> {
+        if len == 0 {
+            return None;
+        }
+        // xorshift64
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        Some((self.rng % len as u64) as usize)
+    }
+}
+
+/// A set of keys kept in a vector so one can be sampled uniformly, with
+/// each key's position so removal is O(1).
+#[derive(Default)]
+struct SampleIndex {
+    keys: Vec<String>,
+    positions: HashMap<String, usize>,
+}
+
+impl SampleIndex {
+    fn insert(&mut self, key: &str) {
+        if !self.positions.contains_key(key) {
+            self.positions.insert(key.to_string(), self.keys.len());
+            self.keys.push(key.to_string());
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(pos) = self.positions.remove(key) {
+            self.keys.swap_remove(pos);
+            if let Some(moved) = self.keys.get(pos) {
+                self.positions.insert(moved.clone(), pos);
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.keys.len()
+    }
+}
+
+/// The tenant portion of a `tenant:key` name.
+pub(crate) fn namespace_of(key: &str) -> &str {
+    key.split_once(':').map_or("", |(namespace, _)| namespace)
+}
@@ -1,7 +1,13 @@
 // src/lib.rs
+pub mod commands;
+pub mod config;
+pub mod glob;
 pub mod handler;
+mod keyspace;
 pub mod metrics;
 pub mod parser;
+pub mod resp;
 pub mod search_parser;
 pub mod store;
+pub mod sweeper;
 pub mod types;
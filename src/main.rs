@@ -1,14 +1,9 @@
-mod handler;
-mod metrics;
-mod parser;
-mod search_parser;
-mod store;
-mod types;
-
-use handler::handle_connection;
+use redis_test_simple::config::Config;
+use redis_test_simple::handler::handle_connection;
+use redis_test_simple::store::RedisStore;
+use redis_test_simple::sweeper::run_active_expire;
 use std::env;
 use std::sync::Arc;
-use store::RedisStore;
 use tokio::net::TcpListener;
 
 #[tokio::main]
@@ -19,6 +14,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("⚡ Starting server on {}", addr);
 
     let store = Arc::new(RedisStore::new());
+    let config = Arc::new(Config::from_env());
+
+    tokio::spawn(run_active_expire(Arc::clone(&store), config.hz));
 
     let listener = match TcpListener::bind(&addr).await {
         Ok(l) => {
@@ -36,8 +34,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             Ok((stream, addr)) => {
                 println!("📡 Connection from: {}", addr);
                 let store_clone = Arc::clone(&store);
+                let config_clone = Arc::clone(&config);
                 tokio::spawn(async move {
-                    handle_connection(stream, store_clone).await;
+                    handle_connection(stream, store_clone, config_clone).await;
                 });
             }
             Err(e) => eprintln!("❌ Accept error: {}", e),
@@ -1,5 +1,6 @@
+use crate::types::FieldValues;
 use serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{SystemTime, SystemTimeError, UNIX_EPOCH};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MetricEntry {
@@ -11,6 +12,23 @@ pub struct MetricEntry {
     pub ms: f64,
 }
 
+impl MetricEntry {
+    /// The entry as stream fields, in declaration order.
+    pub fn fields(&self) -> FieldValues {
+        [
+            ("tenant", self.tenant.clone()),
+            ("unix", self.unix.to_string()),
+            ("endpoint", self.endpoint.clone()),
+            ("method", self.method.clone()),
+            ("bytes", self.bytes.to_string()),
+            ("ms", self.ms.to_string()),
+        ]
+        .into_iter()
+        .map(|(field, value)| (field.as_bytes().to_vec(), value.into_bytes()))
+        .collect()
+    }
+}
+
 pub struct MetricsCollector {
     tenant: String,
 }
@@ -26,21 +44,21 @@ impl MetricsCollector {
         method: String,
         bytes: usize,
         ms: u64,
-    ) -> Result<String, Box<dyn std::error::Error>> {
+    ) -> Result<MetricEntry, SystemTimeError> {
         let unix = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
 
-        let entry = MetricEntry {
+        Ok(MetricEntry {
             tenant: self.tenant.clone(),
             unix,
             endpoint,
             method,
             bytes,
             ms: ms as f64 / 1_000.0,
-        };
-
-        Ok(serde_json::to_string(&entry)?)
+        })
     }
 }
 
 // Constants for metrics
 pub const METRICS_KEY: &str = "_metrics";
+/// Entries kept per tenant; older ones are trimmed as new ones are added.
+pub const METRICS_MAX_LEN: usize = 10_000;
@@ -1,81 +1,293 @@
 use crate::types::ParserState;
+use std::fmt;
 
-pub fn parse_command(request: &str) -> Vec<String> {
-    let mut state = ParserState::Start;
-    let mut command_parts: Vec<String> = Vec::new();
-
-    for ch in request.chars() {
-        state = match state {
-            ParserState::Start => {
-                if ch == '*' {
-                    ParserState::ReadingArrayLength(String::new())
-                } else {
-                    ParserState::Start
-                }
+/// Default upper bound for a single bulk string, matching Redis' `proto-max-bulk-len`.
+pub const DEFAULT_PROTO_MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+
+/// Upper bound for the number of arguments in a single multibulk frame.
+const MAX_MULTIBULK_LEN: usize = 1024 * 1024;
+
+/// Longest length header we will buffer while waiting for its `\r\n`.
+const MAX_HEADER_LEN: usize = 64 * 1024;
+
+#[derive(Debug, PartialEq)]
+pub enum ProtocolError {
+    UnbalancedQuotes,
+    InlineTooLong,
+    ExpectedBulk(u8),
+    InvalidMultibulkLength,
+    InvalidBulkLength,
+    HeaderTooLong,
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::UnbalancedQuotes => {
+                write!(f, "Protocol error: unbalanced quotes in request")
             }
-            ParserState::ReadingArrayLength(mut num) => {
-                if ch == '\r' {
-                    match num.parse::<i32>() {
-                        Ok(_n) => ParserState::ExpectingBulkString,
-                        Err(_e) => ParserState::Start,
-                    }
-                } else {
-                    num.push(ch);
-                    ParserState::ReadingArrayLength(num)
-                }
+            ProtocolError::InlineTooLong => write!(f, "Protocol error: too big inline request"),
+            ProtocolError::ExpectedBulk(b) => {
+                write!(f, "Protocol error: expected '$', got '{}'", *b as char)
             }
-            ParserState::ExpectingBulkString => {
-                if ch == '$' {
-                    ParserState::ReadingBulkLength(String::new())
-                } else if ch != '\n' {
-                    ParserState::ExpectingBulkString
-                } else {
-                    ParserState::ExpectingBulkString
-                }
+            ProtocolError::InvalidMultibulkLength => {
+                write!(f, "Protocol error: invalid multibulk length")
             }
-            ParserState::ReadingBulkLength(mut num) => {
-                if ch == '\r' {
-                    match num.parse::<i32>() {
-                        Ok(n) => ParserState::ReadingBulkContent {
-                            expected_length: n,
-                            current_content: String::new(),
-                        },
-                        Err(_e) => ParserState::Start,
+            ProtocolError::InvalidBulkLength => write!(f, "Protocol error: invalid bulk length"),
+            ProtocolError::HeaderTooLong => write!(f, "Protocol error: too big count string"),
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+/// Arguments of one command frame, each an exact byte string.
+pub type Frame = Vec<Vec<u8>>;
+
+/// Incremental RESP parser.
+///
+/// Accepts both multibulk frames (`*<n>\r\n$<len>\r\n...`) and inline
+/// commands (`PING\r\n`), as sent by telnet or netcat. Bytes are fed
+/// through [`RespParser::parse`] as they arrive; the parser remembers how
+/// far it got so a frame split across several TCP reads is resumed rather
+/// than re-scanned.
+pub struct RespParser {
+    state: ParserState,
+    parts: Frame,
+    max_bulk_len: usize,
+}
+
+impl RespParser {
+    pub fn new(max_bulk_len: usize) -> Self {
+        RespParser {
+            state: ParserState::Start,
+            parts: Vec::new(),
+            max_bulk_len,
+        }
+    }
+
+    /// Advances the parser over `buf`.
+    ///
+    /// Returns the number of bytes consumed together with the arguments of a
+    /// completed frame, or `None` when more data is needed. Consumed bytes
+    /// must not be passed in again; unconsumed bytes must be.
+    pub fn parse(&mut self, buf: &[u8]) -> Result<(usize, Option<Frame>), ProtocolError> {
+        let mut pos = 0;
+
+        loop {
+            let state = std::mem::replace(&mut self.state, ParserState::Start);
+            self.state = match state {
+                ParserState::Start => match buf.get(pos) {
+                    None => return Ok((pos, None)),
+                    Some(b'*') => {
+                        pos += 1;
+                        ParserState::ReadingArrayLength
                     }
-                } else {
-                    num.push(ch);
-                    ParserState::ReadingBulkLength(num)
-                }
-            }
-            ParserState::ReadingBulkContent {
-                expected_length,
-                mut current_content,
-            } => {
-                if ch == '\r' {
-                    if current_content.len() == expected_length as usize {
-                        command_parts.push(current_content.clone());
-                        ParserState::ExpectingBulkString
-                    } else {
-                        ParserState::ReadingBulkContent {
-                            expected_length,
-                            current_content,
+                    Some(_) => {
+                        let Some(end) = buf[pos..].iter().position(|&b| b == b'\n') else {
+                            if buf.len() - pos > MAX_HEADER_LEN {
+                                return Err(ProtocolError::InlineTooLong);
+                            }
+                            return Ok((pos, None));
+                        };
+                        let line = &buf[pos..pos + end];
+                        let line = line.strip_suffix(b"\r").unwrap_or(line);
+                        let args = split_inline_args(line)?;
+                        return Ok((pos + end + 1, Some(args)));
+                    }
+                },
+                ParserState::ReadingArrayLength => {
+                    let Some((line, next)) = read_line(buf, pos)? else {
+                        self.state = ParserState::ReadingArrayLength;
+                        return Ok((pos, None));
+                    };
+                    pos = next;
+                    match parse_multibulk_length(line)? {
+                        0 => ParserState::Done,
+                        n => {
+                            self.parts.reserve(n.min(1024));
+                            ParserState::ExpectingBulkString { remaining: n }
                         }
                     }
-                } else if ch != '\n' {
-                    current_content.push(ch);
-                    ParserState::ReadingBulkContent {
-                        expected_length,
-                        current_content,
+                }
+                ParserState::ExpectingBulkString { remaining } => match buf.get(pos) {
+                    None => {
+                        self.state = ParserState::ExpectingBulkString { remaining };
+                        return Ok((pos, None));
                     }
-                } else {
+                    Some(b'$') => {
+                        pos += 1;
+                        ParserState::ReadingBulkLength { remaining }
+                    }
+                    Some(&b) => return Err(ProtocolError::ExpectedBulk(b)),
+                },
+                ParserState::ReadingBulkLength { remaining } => {
+                    let Some((line, next)) = read_line(buf, pos)? else {
+                        self.state = ParserState::ReadingBulkLength { remaining };
+                        return Ok((pos, None));
+                    };
+                    pos = next;
+                    let expected_length = parse_length(line)
+                        .filter(|&len| len <= self.max_bulk_len)
+                        .ok_or(ProtocolError::InvalidBulkLength)?;
                     ParserState::ReadingBulkContent {
+                        remaining,
                         expected_length,
-                        current_content,
                     }
                 }
+                ParserState::ReadingBulkContent {
+                    remaining,
+                    expected_length,
+                } => {
+                    let end = pos + expected_length;
+                    if buf.len() < end + 2 {
+                        self.state = ParserState::ReadingBulkContent {
+                            remaining,
+                            expected_length,
+                        };
+                        return Ok((pos, None));
+                    }
+                    if &buf[end..end + 2] != b"\r\n" {
+                        return Err(ProtocolError::InvalidBulkLength);
+                    }
+                    self.parts.push(buf[pos..end].to_vec());
+                    pos = end + 2;
+                    match remaining - 1 {
+                        0 => ParserState::Done,
+                        n => ParserState::ExpectingBulkString { remaining: n },
+                    }
+                }
+                ParserState::Done => return Ok((pos, Some(std::mem::take(&mut self.parts)))),
+            };
+        }
+    }
+}
+
+/// Parses a single RESP multibulk frame from `request`.
+///
+/// Bulk lengths are exact byte counts, so arguments may contain arbitrary
+/// binary data including `\r\n`. Incomplete or malformed frames yield an
+/// empty vec.
+pub fn parse_command(request: &[u8]) -> Vec<Vec<u8>> {
+    let mut parser = RespParser::new(DEFAULT_PROTO_MAX_BULK_LEN);
+    match parser.parse(request) {
+        Ok((_, Some(parts))) => parts,
+        _ => Vec::new(),
+    }
+}
+
+/// Splits an inline command line into arguments.
+///
+/// Arguments are separated by whitespace. Double-quoted arguments support
+/// `\n`, `\r`, `\t`, `\b`, `\a`, `\\`, `\"` and `\xHH` escapes; single-quoted
+/// arguments only support `\'`. A closing quote must be followed by
+/// whitespace or the end of the line.
+fn split_inline_args(line: &[u8]) -> Result<Frame, ProtocolError> {
+    let mut args = Vec::new();
+    let mut i = 0;
+
+    loop {
+        while i < line.len() && line[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i == line.len() {
+            return Ok(args);
+        }
+
+        let mut current = Vec::new();
+        match line[i] {
+            b'"' => {
+                i += 1;
+                loop {
+                    match line.get(i) {
+                        None => return Err(ProtocolError::UnbalancedQuotes),
+                        Some(b'\\') if i + 1 < line.len() => {
+                            let hex = line.get(i + 2..i + 4).and_then(|h| {
+                                u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok()
+                            });
+                            match (line[i + 1], hex) {
+                                (b'x', Some(byte)) => {
+                                    current.push(byte);
+                                    i += 4;
+                                    continue;
+                                }
+                                (b'n', _) => current.push(b'\n'),
+                                (b'r', _) => current.push(b'\r'),
+                                (b't', _) => current.push(b'\t'),
+                                (b'b', _) => current.push(0x08),
+                                (b'a', _) => current.push(0x07),
+                                (other, _) => current.push(other),
+                            }
+                            i += 2;
+                        }
+                        Some(b'"') => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&b) => {
+                            current.push(b);
+                            i += 1;
+                        }
+                    }
+                }
+            }
+            b'\'' => {
+                i += 1;
+                loop {
+                    match line.get(i) {
+                        None => return Err(ProtocolError::UnbalancedQuotes),
+                        Some(b'\\') if line.get(i + 1) == Some(&b'\'') => {
+                            current.push(b'\'');
+                            i += 2;
+                        }
+                        Some(b'\'') => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&b) => {
+                            current.push(b);
+                            i += 1;
+                        }
+                    }
+                }
+            }
+            _ => {
+                while i < line.len() && !line[i].is_ascii_whitespace() {
+                    current.push(line[i]);
+                    i += 1;
+                }
             }
-        };
+        }
+
+        if i < line.len() && !line[i].is_ascii_whitespace() {
+            return Err(ProtocolError::UnbalancedQuotes);
+        }
+        args.push(current);
     }
+}
+
+/// Returns the bytes from `pos` up to the next `\r\n` and the position after
+/// it, or `None` if the line is not complete yet.
+fn read_line(buf: &[u8], pos: usize) -> Result<Option<(&[u8], usize)>, ProtocolError> {
+    let rest = &buf[pos..];
+    match rest.windows(2).position(|w| w == b"\r\n") {
+        Some(end) => Ok(Some((&rest[..end], pos + end + 2))),
+        None if rest.len() > MAX_HEADER_LEN => Err(ProtocolError::HeaderTooLong),
+        None => Ok(None),
+    }
+}
+
+/// Parses a multibulk count; null (`*-1`) and empty arrays both count as zero.
+fn parse_multibulk_length(line: &[u8]) -> Result<usize, ProtocolError> {
+    if line == b"-1" {
+        return Ok(0);
+    }
+    parse_length(line)
+        .filter(|&len| len <= MAX_MULTIBULK_LEN)
+        .ok_or(ProtocolError::InvalidMultibulkLength)
+}
 
-    command_parts
+/// Parses a non-negative decimal length header.
+fn parse_length(line: &[u8]) -> Option<usize> {
+    std::str::from_utf8(line).ok()?.parse::<usize>().ok()
 }
@@ -0,0 +1,193 @@
+use serde_json::Value;
+
+/// Wire protocol negotiated by the client through `HELLO`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Protocol {
+    #[default]
+    Resp2,
+    Resp3,
+}
+
+/// A reply to be sent back to the client, encoded as RESP.
+///
+/// RESP3-only types are downgraded to their RESP2 equivalents when the
+/// connection has not negotiated RESP3.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reply {
+    Simple(String),
+    Error(String),
+    Integer(i64),
+    Bulk(Vec<u8>),
+    Null,
+    /// Nil in place of an array, `*-1` on RESP2.
+    NullArray,
+    Array(Vec<Reply>),
+    Map(Vec<(Reply, Reply)>),
+    Set(Vec<Reply>),
+    Double(f64),
+    Boolean(bool),
+    Verbatim {
+        format: String,
+        text: String,
+    },
+    Push(Vec<Reply>),
+}
+
+impl Reply {
+    pub fn ok() -> Reply {
+        Reply::Simple("OK".to_string())
+    }
+
+    /// Builds an error reply; `message` should start with an error code such as `ERR`.
+    pub fn error(message: impl Into<String>) -> Reply {
+        Reply::Error(message.into())
+    }
+
+    pub fn bulk(value: impl Into<Vec<u8>>) -> Reply {
+        Reply::Bulk(value.into())
+    }
+
+    /// Converts a JSON document into native RESP3 types: objects become maps,
+    /// arrays become arrays, and scalars map onto their closest reply type.
+    pub fn from_json(value: &Value) -> Reply {
+        match value {
+            Value::Null => Reply::Null,
+            Value::Bool(b) => Reply::Boolean(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => Reply::Integer(i),
+                None => Reply::Double(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            Value::String(s) => Reply::bulk(s.as_str()),
+            Value::Array(items) => Reply::Array(items.iter().map(Reply::from_json).collect()),
+            Value::Object(fields) => Reply::Map(
+                fields
+                    .iter()
+                    .map(|(k, v)| (Reply::bulk(k.as_str()), Reply::from_json(v)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Appends the encoding of this reply for `protocol` to `out`.
+    pub fn encode(&self, out: &mut Vec<u8>, protocol: Protocol) {
+        let resp3 = protocol == Protocol::Resp3;
+        match self {
+            Reply::Simple(s) => write_line(out, b'+', s),
+            Reply::Error(e) => write_line(out, b'-', e),
+            Reply::Integer(n) => write_header(out, b':', *n),
+            Reply::Bulk(data) => write_blob(out, b'$', data),
+            Reply::Null if resp3 => out.extend_from_slice(b"_\r\n"),
+            Reply::Null => out.extend_from_slice(b"$-1\r\n"),
+            Reply::NullArray if resp3 => out.extend_from_slice(b"_\r\n"),
+            Reply::NullArray => out.extend_from_slice(b"*-1\r\n"),
+            Reply::Array(items) => write_aggregate(out, b'*', items, protocol),
+            Reply::Map(pairs) => {
+                if resp3 {
+                    write_header(out, b'%', pairs.len() as i64);
+                } else {
+                    write_header(out, b'*', pairs.len() as i64 * 2);
+                }
+                for (key, value) in pairs {
+                    key.encode(out, protocol);
+                    value.encode(out, protocol);
+                }
+            }
+            Reply::Set(items) if resp3 => write_aggregate(out, b'~', items, protocol),
+            Reply::Set(items) => write_aggregate(out, b'*', items, protocol),
+            Reply::Double(d) if resp3 => write_line(out, b',', &format_double(*d)),
+            Reply::Double(d) => write_blob(out, b'$', format_double(*d).as_bytes()),
+            Reply::Boolean(b) if resp3 => write_line(out, b'#', if *b { "t" } else { "f" }),
+            Reply::Boolean(b) => write_header(out, b':', *b as i64),
+            Reply::Verbatim { format, text } if resp3 => {
+                write_blob(out, b'=', format!("{}:{}", format, text).as_bytes())
+            }
+            Reply::Verbatim { text, .. } => write_blob(out, b'$', text.as_bytes()),
+            Reply::Push(items) if resp3 => write_aggregate(out, b'>', items, protocol),
+            Reply::Push(items) => write_aggregate(out, b'*', items, protocol),
+        }
+    }
+
+    pub fn to_bytes(&self, protocol: Protocol) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode(&mut out, protocol);
+        out
+    }
+}
+
+fn write_header(out: &mut Vec<u8>, prefix: u8, n: i64) {
+    out.push(prefix);
+    out.extend_from_slice(n.to_string().as_bytes());
+    out.extend_from_slice(b"\r\n");
+}
+
+fn write_blob(out: &mut Vec<u8>, prefix: u8, data: &[u8]) {
+    write_header(out, prefix, data.len() as i64);
+    out.extend_from_slice(data);
+    out.extend_from_slice(b"\r\n");
+}
+
+fn write_aggregate(out: &mut Vec<u8>, prefix: u8, items: &[Reply], protocol: Protocol) {
+    write_header(out, prefix, items.len() as i64);
+    for item in items {
+        item.encode(out, protocol);
+    }
+}
+
+/// Writes a single-line reply, replacing line breaks so the frame stays intact.
+fn write_line(out: &mut Vec<u8>, prefix: u8, s: &str) {
+    out.push(prefix);
+    out.extend(
+        s.bytes()
+            .map(|b| if b == b'\r' || b == b'\n' { b' ' } else { b }),
+    );
+    out.extend_from_slice(b"\r\n");
+}
+
+/// Significant digits Redis prints doubles with (`%.17g`).
+pub(crate) const DOUBLE_DIGITS: usize = 17;
+
+/// Formats a double the way Redis does with `%.17g`, including `inf`,
+/// `-inf` and `nan`.
+pub fn format_double(d: f64) -> String {
+    if d.is_nan() {
+        return "nan".to_string();
+    }
+    if d.is_infinite() {
+        return if d > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    // `{:e}` rounds to the requested significant digits: "3.0000000000000004e-1"
+    let scientific = format!("{:.*e}", DOUBLE_DIGITS - 1, d.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    format_significant(d.is_sign_negative(), &digits, exponent.parse().unwrap())
+}
+
+/// Lays out a number whose significant `digits` are `d.ddd × 10^exponent`
+/// the way C's `%g` does: trailing zeros dropped, and exponent notation
+/// when the exponent is below -4 or reaches the digit count.
+pub(crate) fn format_significant(negative: bool, digits: &str, exponent: i32) -> String {
+    let digits = digits.trim_end_matches('0');
+    if digits.is_empty() {
+        return if negative { "-0" } else { "0" }.to_string();
+    }
+    let sign = if negative { "-" } else { "" };
+    if exponent < -4 || exponent >= DOUBLE_DIGITS as i32 {
+        let (first, rest) = digits.split_at(1);
+        let point = if rest.is_empty() { "" } else { "." };
+        let exp_sign = if exponent < 0 { '-' } else { '+' };
+        return format!(
+            "{sign}{first}{point}{rest}e{exp_sign}{:02}",
+            exponent.unsigned_abs()
+        );
+    }
+    if exponent < 0 {
+        let zeros = "0".repeat(exponent.unsigned_abs() as usize - 1);
+        return format!("{sign}0.{zeros}{digits}");
+    }
+    let int_len = exponent as usize + 1;
+    if digits.len() <= int_len {
+        format!("{sign}{digits}{}", "0".repeat(int_len - digits.len()))
+    } else {
+        format!("{sign}{}.{}", &digits[..int_len], &digits[int_len..])
+    }
+}
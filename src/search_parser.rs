@@ -139,13 +139,10 @@ impl SearchParser {
         F: Fn(f64, f64) -> bool,
     {
         if let Ok(search_num) = search_value.parse::<f64>() {
-            match field {
-                Value::Number(n) => {
-                    if let Some(n) = n.as_f64() {
-                        return comparator(n, search_num);
-                    }
+            if let Value::Number(n) = field {
+                if let Some(n) = n.as_f64() {
+                    return comparator(n, search_num);
                 }
-                _ => (),
             }
         }
         false
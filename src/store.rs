@@ -1,30 +1,255 @@
+pub(crate) mod blocking;
+mod hash;
+mod list;
+mod set;
+mod stream;
+mod zset;
+
+use crate::keyspace::{namespace_of, Keyspace};
+use crate::parser::DEFAULT_PROTO_MAX_BULK_LEN;
+use crate::resp::{format_double, format_significant, DOUBLE_DIGITS};
 use crate::search_parser::SearchParser;
-use crate::types::{RedisGetResult, RedisValue};
+use crate::types::{
+    ExpireCondition, ExpireStats, RedisData, RedisGetResult, RedisValue, SetCondition, SetExpiry,
+    SetOptions, SetOutcome, StoreError, TtlState,
+};
 use serde_json::{json, Value};
-use std::collections::HashMap;
+use std::sync::mpsc::{self, Sender};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Values at least this large are handed to the lazy-free thread by UNLINK.
+const LAZYFREE_THRESHOLD: usize = 64 * 1024;
+
+/// Keys with a TTL sampled per iteration of the active expire cycle.
+const ACTIVE_EXPIRE_SAMPLE: usize = 20;
+
+/// Internal representation reported by OBJECT ENCODING.
+fn encoding(value: &RedisValue) -> &'static str {
+    match &value.data {
+        RedisData::String(s) if is_canonical_int(s) => "int",
+        RedisData::String(s) if s.len() <= 44 => "embstr",
+        RedisData::String(_) => "raw",
+        RedisData::Json(_) => "json",
+        RedisData::List(items) => list::encoding(items),
+        RedisData::Hash(hash) => hash.encoding(),
+        RedisData::Set(members) => set::encoding(members),
+        RedisData::SortedSet(zset) => zset.encoding(),
+        RedisData::Stream(_) => "stream",
+    }
+}
+
+/// Adds two plain decimals exactly, rendering the sum to 17 significant
+/// digits. This is what Redis' long double `%.17Lg` output amounts to, so
+/// 0.1 plus 0.2 gives 0.3 rather than the f64 sum. `None` if either side
+/// uses exponent notation or the sum does not fit in an `i128`.
+fn add_decimal(a: &str, b: &str) -> Option<String> {
+    fn parse(text: &str) -> Option<(i128, u32)> {
+        let (int, frac) = text.split_once('.').unwrap_or((text, ""));
+        if !frac.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let mantissa = format!("{}{}", int, frac).parse().ok()?;
+        Some((mantissa, frac.len() as u32))
+    }
+    let rescale = |(mantissa, scale): (i128, u32), to: u32| {
+        mantissa.checked_mul(10i128.checked_pow(to - scale)?)
+    };
+
+    let (a, b) = (parse(a)?, parse(b)?);
+    let scale = a.1.max(b.1);
+    let sum = rescale(a, scale)?.checked_add(rescale(b, scale)?)?;
+
+    let mut digits = sum.unsigned_abs().to_string();
+    let mut exponent = digits.len() as i32 - 1 - scale as i32;
+    if digits.len() > DOUBLE_DIGITS {
+        let round_up = digits.as_bytes()[DOUBLE_DIGITS] >= b'5';
+        let kept: u128 = digits[..DOUBLE_DIGITS].parse().ok()?;
+        digits = (kept + round_up as u128).to_string();
+        if digits.len() > DOUBLE_DIGITS {
+            exponent += 1;
+        }
+    }
+    Some(format_significant(sum < 0, &digits, exponent))
+}
+
+/// Whether `bytes` spell an integer exactly as Redis would print it, the
+/// condition for the `int` and `intset` encodings.
+fn is_canonical_int(bytes: &[u8]) -> bool {
+    std::str::from_utf8(bytes)
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .is_some_and(|n| n.to_string().as_bytes() == bytes)
+}
 
 pub struct RedisStore {
-    data: Arc<Mutex<HashMap<String, RedisValue>>>,
+    data: Arc<Mutex<Keyspace>>,
+    // Values removed by UNLINK are dropped on this thread, off the request path
+    lazyfree: Mutex<Sender<Vec<RedisValue>>>,
+}
+
+impl Default for RedisStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl RedisStore {
     pub fn new() -> Self {
+        let (lazyfree, garbage) = mpsc::channel::<Vec<RedisValue>>();
+        thread::spawn(move || {
+            for values in garbage {
+                drop(values);
+            }
+        });
+
         RedisStore {
-            data: Arc::new(Mutex::new(HashMap::new())),
+            data: Arc::new(Mutex::new(Keyspace::new())),
+            lazyfree: Mutex::new(lazyfree),
+        }
+    }
+
+    /// Removes `keys`, returning how many live keys were deleted.
+    pub fn delete(&self, keys: &[String]) -> usize {
+        let mut store = self.data.lock().unwrap();
+        Self::remove_live(&mut store, keys).len()
+    }
+
+    /// Like `delete`, but large values are freed on a background thread.
+    pub fn unlink(&self, keys: &[String]) -> usize {
+        let removed = {
+            let mut store = self.data.lock().unwrap();
+            Self::remove_live(&mut store, keys)
+        };
+        let count = removed.len();
+
+        let size: usize = removed.iter().map(RedisValue::size).sum();
+        if size >= LAZYFREE_THRESHOLD {
+            let _ = self.lazyfree.lock().unwrap().send(removed);
+        }
+        count
+    }
+
+    fn remove_live(store: &mut Keyspace, keys: &[String]) -> Vec<RedisValue> {
+        keys.iter()
+            .filter_map(|key| {
+                if store.expire_if_needed(key) {
+                    None
+                } else {
+                    store.remove(key)
+                }
+            })
+            .collect()
+    }
+
+    /// Counts how many of `keys` exist; repeated keys are counted each time.
+    pub fn exists(&self, keys: &[String]) -> usize {
+        let mut store = self.data.lock().unwrap();
+        keys.iter().filter(|key| store.contains_live(key)).count()
+    }
+
+    /// Returns the type name of the value at `key`, or `none` if it is absent.
+    pub fn key_type(&self, key: &str) -> &'static str {
+        let mut store = self.data.lock().unwrap();
+        store.get_live(key).map_or("none", |v| v.type_name())
+    }
+
+    /// Returns the OBJECT ENCODING of the value at `key`, if it exists.
+    pub fn object_encoding(&self, key: &str) -> Option<&'static str> {
+        let mut store = self.data.lock().unwrap();
+        store.get_live(key).map(encoding)
+    }
+
+    /// Returns every live key starting with `prefix` (a tenant's `tenant:`).
+    pub fn keys(&self, prefix: &str) -> Vec<String> {
+        let mut cursor = 0;
+        let mut keys = Vec::new();
+        loop {
+            let (next, batch) = self.scan(prefix, cursor, 1024, None);
+            keys.extend(batch);
+            if next == 0 {
+                return keys;
+            }
+            cursor = next;
+        }
+    }
+
+    /// Visits up to `count` keys starting with `prefix` from `cursor`,
+    /// returning the live ones (optionally only those of `type_name`) and the
+    /// next cursor, which is 0 once the iteration is complete.
+    ///
+    /// Keys present for the whole iteration are returned exactly once, even
+    /// if other keys are inserted or removed in between calls.
+    pub fn scan(
+        &self,
+        prefix: &str,
+        cursor: u64,
+        count: usize,
+        type_name: Option<&str>,
+    ) -> (u64, Vec<String>) {
+        let mut store = self.data.lock().unwrap();
+        let (next, candidates) = store.scan(namespace_of(prefix), cursor, count.max(1));
+        let keys = candidates
+            .into_iter()
+            .filter(|key| key.starts_with(prefix))
+            .filter(|key| {
+                store.get_live(key).is_some_and(|v| {
+                    type_name.is_none_or(|t| v.type_name().eq_ignore_ascii_case(t))
+                })
+            })
+            .collect();
+        (next, keys)
+    }
+
+    /// Moves `from` to `to`, carrying its TTL. With `only_if_absent` the move
+    /// is skipped (returning false) when `to` already exists.
+    pub fn rename(&self, from: &str, to: &str, only_if_absent: bool) -> Result<bool, StoreError> {
+        let mut store = self.data.lock().unwrap();
+
+        if !store.contains_live(from) {
+            return Err(StoreError::NoSuchKey);
+        }
+        if only_if_absent && store.contains_live(to) {
+            return Ok(false);
+        }
+        if from != to {
+            let value = store.remove(from).unwrap();
+            store.insert(to.to_string(), value);
+            serve_blocked(&mut store, to);
+        }
+        Ok(true)
+    }
+
+    /// Copies `from` to `to` including its TTL. Without `replace` an existing
+    /// destination is left alone and false is returned. Copying a key onto
+    /// itself is an error.
+    pub fn copy(&self, from: &str, to: &str, replace: bool) -> Result<bool, StoreError> {
+        if from == to {
+            return Err(StoreError::SameObject);
+        }
+        let mut store = self.data.lock().unwrap();
+
+        let Some(value) = store.get_live(from).cloned() else {
+            return Ok(false);
+        };
+        if !replace && store.contains_live(to) {
+            return Ok(false);
         }
+        store.insert(to.to_string(), value);
+        serve_blocked(&mut store, to);
+        Ok(true)
     }
 
     pub fn set(
         &self,
         key: String,
-        value: String,
+        value: impl Into<Vec<u8>>,
         px: Option<u64>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let expires_at = px.map(|millis| SystemTime::now() + Duration::from_millis(millis));
         let value = RedisValue {
-            data: value,
+            data: RedisData::String(value.into()),
             expires_at,
         };
         let mut store = self.data.lock().unwrap();
@@ -32,50 +257,341 @@ impl RedisStore {
         Ok(())
     }
 
-    pub fn append(&self, key: String, value: String) -> Result<(), Box<dyn std::error::Error>> {
+    /// Reads every key in `keys` under a single lock acquisition. Keys that
+    /// do not hold a string read as absent.
+    pub fn get_many(&self, keys: &[String]) -> Vec<Option<Vec<u8>>> {
         let mut store = self.data.lock().unwrap();
+        keys.iter()
+            .map(|key| store.get_live(key).and_then(|v| v.render().ok()))
+            .collect()
+    }
 
-        if let Some(existing) = store.get_mut(&key) {
-            // Parse the existing data as JSON array
-            let mut current_array: Value = serde_json::from_str(&existing.data)
-                .map_err(|_| "Existing data is not a valid JSON array")?;
+    /// Writes every pair atomically, clearing any TTLs. With `only_if_none_exist`
+    /// nothing is written (and false returned) if any key already exists.
+    pub fn set_many(&self, pairs: Vec<(String, Vec<u8>)>, only_if_none_exist: bool) -> bool {
+        let mut store = self.data.lock().unwrap();
+        if only_if_none_exist && pairs.iter().any(|(key, _)| store.contains_live(key)) {
+            return false;
+        }
+        for (key, data) in pairs {
+            store.insert(key, RedisValue::string(data));
+        }
+        true
+    }
 
-            // Parse the new value as JSON
-            let new_value: Value =
-                serde_json::from_str(&value).map_err(|_| "New value is not valid JSON")?;
+    /// Writes `value` subject to the NX/XX condition and expiry in `options`.
+    ///
+    /// An expired key counts as absent. The previous live value is always
+    /// returned so callers can honour SET ... GET, which fails with
+    /// `WrongType` (and writes nothing) if the key holds a non-string.
+    pub fn set_with_options(
+        &self,
+        key: String,
+        value: impl Into<Vec<u8>>,
+        options: &SetOptions,
+    ) -> Result<SetOutcome, StoreError> {
+        let mut store = self.data.lock().unwrap();
 
-            // Ensure we have an array
-            if !current_array.is_array() {
-                return Err("Existing data is not a JSON array".into());
+        let existing = store.get_live(&key);
+        let previous = match existing.map(RedisValue::render) {
+            Some(Err(e)) if options.get => return Err(e),
+            Some(rendered) => rendered.ok(),
+            None => None,
+        };
+        let previous_expiry = existing.and_then(|v| v.expires_at);
+
+        let allowed = match options.condition {
+            SetCondition::Always => true,
+            SetCondition::IfNotExists => existing.is_none(),
+            SetCondition::IfExists => existing.is_some(),
+        };
+        if !allowed {
+            return Ok(SetOutcome {
+                written: false,
+                previous,
+            });
+        }
+
+        let expires_at = match options.expiry {
+            SetExpiry::Persist => None,
+            SetExpiry::Keep => previous_expiry,
+            SetExpiry::At(at) => Some(at),
+        };
+        store.insert(
+            key,
+            RedisValue {
+                data: RedisData::String(value.into()),
+                expires_at,
+            },
+        );
+        Ok(SetOutcome {
+            written: true,
+            previous,
+        })
+    }
+
+    /// Sets the expiry of `key` to `at` if `condition` allows it, returning
+    /// whether the TTL was changed. An instant in the past deletes the key.
+    pub fn expire(&self, key: &str, at: SystemTime, condition: ExpireCondition) -> bool {
+        let mut store = self.data.lock().unwrap();
+        let Some(value) = store.get_live(key) else {
+            return false;
+        };
+
+        if !condition.allows(value.expires_at, at) {
+            return false;
+        }
+
+        if at <= SystemTime::now() {
+            store.remove(key);
+        } else {
+            store.set_expiry(key, Some(at));
+        }
+        true
+    }
+
+    /// Reports whether `key` exists and when it expires.
+    pub fn ttl(&self, key: &str) -> TtlState {
+        let mut store = self.data.lock().unwrap();
+        match store.get_live(key) {
+            Some(v) => match v.expires_at {
+                None => TtlState::Persistent,
+                Some(expiry) => TtlState::ExpiresAt(expiry),
+            },
+            None => TtlState::Missing,
+        }
+    }
+
+    /// Removes the TTL from `key`, returning whether it had one.
+    pub fn persist(&self, key: &str) -> bool {
+        let mut store = self.data.lock().unwrap();
+        match store.get_live(key) {
+            Some(v) if v.expires_at.is_some() => {
+                store.set_expiry(key, None);
+                true
             }
+            _ => false,
+        }
+    }
 
-            // Add the new value(s) to the array
-            if let Value::Array(ref mut arr) = current_array {
-                match new_value {
-                    Value::Array(values) => arr.extend(values),
-                    value => arr.push(value),
-                }
-                // Update the stored value
-                existing.data = serde_json::to_string(&current_array)?;
+    /// Atomically adds `delta` to the integer stored at `key`, treating a
+    /// missing key as 0. The key's TTL is preserved.
+    pub fn incr_by(&self, key: String, delta: i64) -> Result<i64, StoreError> {
+        let mut store = self.data.lock().unwrap();
+
+        let (current, expires_at) = match store.get_live(&key) {
+            Some(v) => (
+                std::str::from_utf8(v.as_bytes()?)
+                    .ok()
+                    .and_then(|s| s.parse::<i64>().ok())
+                    .ok_or(StoreError::NotInteger)?,
+                v.expires_at,
+            ),
+            None => (0, None),
+        };
+        let next = current.checked_add(delta).ok_or(StoreError::Overflow)?;
+
+        store.insert(
+            key,
+            RedisValue {
+                data: RedisData::String(next.to_string().into_bytes()),
+                expires_at,
+            },
+        );
+        Ok(next)
+    }
+
+    /// Atomically adds `delta` to the number stored at `key`, returning the
+    /// new value in its stored text form.
+    pub fn incr_by_float(&self, key: String, delta: f64) -> Result<String, StoreError> {
+        let mut store = self.data.lock().unwrap();
+
+        let (text, current, expires_at) = match store.get_live(&key) {
+            Some(v) => {
+                let text = std::str::from_utf8(v.as_bytes()?)
+                    .map_err(|_| StoreError::NotFloat)?
+                    .to_string();
+                let current = text
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|n| n.is_finite())
+                    .ok_or(StoreError::NotFloat)?;
+                (text, current, v.expires_at)
             }
+            None => ("0".to_string(), 0.0, None),
+        };
+        let next = current + delta;
+        if !next.is_finite() {
+            return Err(StoreError::NanOrInfinity);
+        }
+
+        let data = add_decimal(&text, &delta.to_string()).unwrap_or_else(|| format_double(next));
+        store.insert(
+            key,
+            RedisValue {
+                data: RedisData::String(data.clone().into_bytes()),
+                expires_at,
+            },
+        );
+        Ok(data)
+    }
+
+    /// Returns the bytes of the value at `key` between the inclusive offsets
+    /// `start` and `end`, where negative offsets count back from the end.
+    pub fn get_range(&self, key: &str, start: i64, end: i64) -> Result<Vec<u8>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(value) = store.get_live(key) else {
+            return Ok(Vec::new());
+        };
+        let bytes = value.as_bytes()?;
+        let len = bytes.len() as i64;
+
+        if start < 0 && end < 0 && start > end {
+            return Ok(Vec::new());
+        }
+        let start = if start < 0 {
+            (len + start).max(0)
+        } else {
+            start
+        };
+        let end = if end < 0 {
+            (len + end).max(0)
         } else {
-            // Parse the new value as JSON
-            let new_value: Value =
-                serde_json::from_str(&value).map_err(|_| "New value is not valid JSON")?;
+            end.min(len - 1)
+        };
+        if len == 0 || start > end {
+            return Ok(Vec::new());
+        }
+        Ok(bytes[start as usize..=end as usize].to_vec())
+    }
 
+    /// Overwrites the value at `key` starting at byte `offset`, zero-padding
+    /// a missing or shorter value. Returns the resulting length in bytes.
+    pub fn set_range(&self, key: String, offset: usize, value: &[u8]) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let existing = store.get_live(&key);
+        let current = existing
+            .map(RedisValue::as_bytes)
+            .transpose()?
+            .unwrap_or(&[]);
+
+        // An empty write never creates or changes the key
+        if value.is_empty() {
+            return Ok(current.len());
+        }
+        let end = offset
+            .checked_add(value.len())
+            .filter(|&end| end <= DEFAULT_PROTO_MAX_BULK_LEN)
+            .ok_or(StoreError::StringTooLong)?;
+
+        let expires_at = existing.and_then(|v| v.expires_at);
+        let mut bytes = current.to_vec();
+        if bytes.len() < end {
+            bytes.resize(end, 0);
+        }
+        bytes[offset..end].copy_from_slice(value);
+
+        let len = bytes.len();
+        store.insert(
+            key,
+            RedisValue {
+                data: RedisData::String(bytes),
+                expires_at,
+            },
+        );
+        Ok(len)
+    }
+
+    /// Length in bytes of the value at `key`, or 0 if it does not exist.
+    pub fn strlen(&self, key: &str) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        match store.get_live(key) {
+            Some(v) => Ok(v.as_bytes()?.len()),
+            None => Ok(0),
+        }
+    }
+
+    /// Removes `key`, returning the live value it held.
+    pub fn get_del(&self, key: &str) -> Result<Option<Vec<u8>>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(value) = store.get_live(key) else {
+            return Ok(None);
+        };
+        let data = value.render()?;
+        store.remove(key);
+        Ok(Some(data))
+    }
+
+    /// Returns the value at `key`, first applying `expiry` to it if given.
+    /// `SetExpiry::Keep` leaves the TTL alone; an instant in the past
+    /// deletes the key after it is read.
+    pub fn get_ex(&self, key: &str, expiry: SetExpiry) -> Result<Option<Vec<u8>>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(value) = store.get_live(key) else {
+            return Ok(None);
+        };
+        let data = value.render()?;
+        match expiry {
+            SetExpiry::Keep => {}
+            SetExpiry::Persist => store.set_expiry(key, None),
+            SetExpiry::At(at) if at <= SystemTime::now() => {
+                store.remove(key);
+            }
+            SetExpiry::At(at) => store.set_expiry(key, Some(at)),
+        }
+        Ok(Some(data))
+    }
+
+    /// Pushes the JSON in `value` onto the JSON array at `key`, creating it
+    /// if needed. A string holding a JSON array is converted in place.
+    pub fn append(
+        &self,
+        key: String,
+        value: impl Into<Vec<u8>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // Parse the new value as JSON
+        let new_value: Value =
+            serde_json::from_slice(&value.into()).map_err(|_| "New value is not valid JSON")?;
+
+        let mut store = self.data.lock().unwrap();
+
+        // An expired array is replaced rather than extended
+        if let Some(existing) = store.get_live_mut(&key) {
+            if let RedisData::String(text) = &existing.data {
+                let parsed: Value = serde_json::from_slice(text)
+                    .map_err(|_| "Existing data is not a valid JSON array")?;
+                if !parsed.is_array() {
+                    return Err("Existing data is not a JSON array".into());
+                }
+                existing.data = RedisData::Json(parsed);
+            }
+            let RedisData::Json(current) = &mut existing.data else {
+                return Err(StoreError::WrongType.into());
+            };
+
+            // Add the new value(s) to the array
+            let Value::Array(arr) = current else {
+                return Err("Existing data is not a JSON array".into());
+            };
+            match new_value {
+                Value::Array(values) => arr.extend(values),
+                value => arr.push(value),
+            }
+        } else {
             let array = match new_value {
                 Value::Array(arr) => json!(arr),
                 value => json!([value]),
             };
 
             let value = RedisValue {
-                data: serde_json::to_string(&array)?,
+                data: RedisData::Json(array),
                 expires_at: None,
             };
             store.insert(key, value);
         }
         Ok(())
     }
+
     pub fn get(&self, key: &str) -> RedisGetResult {
         let mut store = self.data.lock().unwrap();
 
@@ -84,10 +600,21 @@ impl RedisStore {
             let parts: Vec<&str> = key.split('?').collect();
             if parts.len() == 2 {
                 // Keep namespace on the key when searching
-                if let Some(value) = store.get(parts[0]) {
-                    let json_value: Value = match serde_json::from_str(&value.data) {
-                        Ok(v) => v,
-                        Err(_) => return RedisGetResult::None,
+                if store.expire_if_needed(parts[0]) {
+                    return RedisGetResult::Expired;
+                }
+                if let Some(value) = store.entries.get(parts[0]) {
+                    let parsed;
+                    let json_value: &Value = match &value.data {
+                        RedisData::Json(json) => json,
+                        RedisData::String(text) => match serde_json::from_slice(text) {
+                            Ok(v) => {
+                                parsed = v;
+                                &parsed
+                            }
+                            Err(_) => return RedisGetResult::None,
+                        },
+                        _ => return RedisGetResult::WrongType,
                     };
 
                     if let Value::Array(array) = json_value {
@@ -98,7 +625,7 @@ impl RedisStore {
                             .collect();
 
                         return RedisGetResult::Value(
-                            serde_json::to_string(&filtered_array).unwrap_or_default(),
+                            serde_json::to_vec(&filtered_array).unwrap_or_default(),
                         );
                     }
                     return RedisGetResult::None;
@@ -107,16 +634,135 @@ impl RedisStore {
         }
 
         // Normal get with namespaced key
-        if let Some(value) = store.get(key) {
-            if let Some(expiry) = value.expires_at {
-                if SystemTime::now() > expiry {
-                    store.remove(key);
-                    return RedisGetResult::Expired;
+        if store.expire_if_needed(key) {
+            return RedisGetResult::Expired;
+        }
+        match store.entries.get(key).map(RedisValue::render) {
+            Some(Ok(value)) => RedisGetResult::Value(value),
+            Some(Err(_)) => RedisGetResult::WrongType,
+            None => RedisGetResult::None,
+        }
+    }
+
+    /// Runs one adaptive active-expire cycle, Redis style: sample keys that
+    /// carry a TTL, evict the expired ones, and keep going while more than a
+    /// quarter of each sample was stale and `time_limit` has not elapsed.
+    /// Hash fields with a TTL are then swept the same way. Returns the
+    /// number of keys evicted.
+    pub fn active_expire_cycle(&self, time_limit: Duration) -> usize {
+        let start = Instant::now();
+        let mut sampled_total = 0;
+        let mut expired_total = 0;
+
+        loop {
+            let mut store = self.data.lock().unwrap();
+            let sample = ACTIVE_EXPIRE_SAMPLE.min(store.volatile_len());
+            if sample == 0 {
+                break;
+            }
+
+            let now = SystemTime::now();
+            let mut expired = 0;
+            for _ in 0..sample {
+                let Some(key) = store.random_volatile_key() else {
+                    break;
+                };
+                match store.entries.get(&key).map(|v| v.expires_at) {
+                    Some(Some(expiry)) if now > expiry => {
+                        store.remove(&key);
+                        expired += 1;
+                    }
+                    Some(Some(_)) => {}
+                    // Stale index entry: drop it without counting it as expired
+                    _ => store.set_expiry_tracked(&key, false),
                 }
             }
-            RedisGetResult::Value(value.data.clone())
-        } else {
-            RedisGetResult::None
+            store.stats.expired_keys += expired as u64;
+            sampled_total += sample;
+            expired_total += expired;
+
+            if expired * 4 <= sample {
+                break;
+            }
+            if start.elapsed() > time_limit {
+                store.stats.expired_time_cap_reached_count += 1;
+                break;
+            }
+            // Release the lock between iterations so requests can interleave
+            drop(store);
+        }
+
+        if sampled_total > 0 {
+            let mut store = self.data.lock().unwrap();
+            let current = expired_total as f64 / sampled_total as f64;
+            store.stats.expired_stale_perc = current * 0.05 + store.stats.expired_stale_perc * 0.95;
         }
+        self.active_expire_fields(start, time_limit);
+        expired_total
     }
+
+    /// The hash field half of the active expire cycle: sample hashes with
+    /// field TTLs, drop their expired fields, and delete hashes left empty.
+    /// Keeps going while more than a quarter of the sampled hashes had
+    /// expired fields. Returns the number of fields removed.
+    fn active_expire_fields(&self, start: Instant, time_limit: Duration) -> usize {
+        let mut removed_total = 0;
+
+        loop {
+            let mut store = self.data.lock().unwrap();
+            let sample = ACTIVE_EXPIRE_SAMPLE.min(store.volatile_fields_len());
+            if sample == 0 {
+                break;
+            }
+
+            let now = SystemTime::now();
+            let mut stale = 0;
+            for _ in 0..sample {
+                let Some(key) = store.random_volatile_fields_key() else {
+                    break;
+                };
+                let Some(RedisValue {
+                    data: RedisData::Hash(hash),
+                    ..
+                }) = store.entries.get_mut(&key)
+                else {
+                    store.set_field_ttls(&key, false);
+                    continue;
+                };
+                let removed = hash.remove_expired(now);
+                let (is_empty, has_field_ttls) = (hash.is_empty(), hash.has_field_ttls());
+                if is_empty {
+                    store.remove(&key);
+                } else {
+                    store.set_field_ttls(&key, has_field_ttls);
+                }
+                if removed > 0 {
+                    stale += 1;
+                    removed_total += removed;
+                    store.stats.expired_subkeys += removed as u64;
+                }
+            }
+
+            if stale * 4 <= sample {
+                break;
+            }
+            if start.elapsed() > time_limit {
+                store.stats.expired_time_cap_reached_count += 1;
+                break;
+            }
+            // Release the lock between iterations so requests can interleave
+            drop(store);
+        }
+        removed_total
+    }
+
+    pub fn expire_stats(&self) -> ExpireStats {
+        self.data.lock().unwrap().stats
+    }
+}
+
+/// Hands whatever `key` now holds to the clients blocked on it.
+fn serve_blocked(store: &mut Keyspace, key: &str) {
+    list::serve_blocked(store, key);
+    stream::serve_blocked(store, key);
 }
@@ -0,0 +1,75 @@
+use crate::types::{BlockAction, BlockTicket, Served, StoreError};
+use std::collections::{HashMap, VecDeque};
+use tokio::sync::oneshot;
+
+pub(crate) struct Waiter {
+    pub(crate) keys: Vec<String>,
+    pub(crate) action: BlockAction,
+    pub(crate) sender: oneshot::Sender<Result<Served, StoreError>>,
+}
+
+/// Clients blocked on list or stream keys, queued per key in the order they
+/// blocked.
+///
+/// Lives inside the keyspace lock so a write can hand its data straight to
+/// waiting clients before any other command observes it.
+#[derive(Default)]
+pub(crate) struct BlockedClients {
+    next_id: u64,
+    queues: HashMap<String, VecDeque<u64>>,
+    waiters: HashMap<u64, Waiter>,
+}
+
+impl BlockedClients {
+    /// Queues a client on each of `keys`.
+    pub(crate) fn add(&mut self, keys: Vec<String>, action: BlockAction) -> BlockTicket {
+        self.next_id += 1;
+        let id = self.next_id;
+        for key in &keys {
+            self.queues.entry(key.clone()).or_default().push_back(id);
+        }
+        let requeue = match action {
+            BlockAction::Pop(end) => Some(end),
+            _ => None,
+        };
+        let (sender, receiver) = oneshot::channel();
+        self.waiters.insert(
+            id,
+            Waiter {
+                keys,
+                action,
+                sender,
+            },
+        );
+        BlockTicket {
+            id,
+            receiver,
+            requeue,
+        }
+    }
+
+    /// Takes client `id` out of every queue it is in.
+    pub(crate) fn remove(&mut self, id: u64) -> Option<Waiter> {
+        let waiter = self.waiters.remove(&id)?;
+        for key in &waiter.keys {
+            if let Some(queue) = self.queues.get_mut(key) {
+                queue.retain(|&queued| queued != id);
+                if queue.is_empty() {
+                    self.queues.remove(key);
+                }
+            }
+        }
+        Some(waiter)
+    }
+
+    /// The clients blocked on `key`, longest-waiting first.
+    pub(crate) fn queue(&self, key: &str) -> Vec<u64> {
+        self.queues
+            .get(key)
+            .map_or_else(Vec::new, |queue| queue.iter().copied().collect())
+    }
+
+    pub(crate) fn action(&self, id: u64) -> Option<&BlockAction> {
+        self.waiters.get(&id).map(|waiter| &waiter.action)
+    }
+}
@@ -0,0 +1,332 @@
+use super::RedisStore;
+use crate::keyspace::Keyspace;
+use crate::types::{
+    ExpireCondition, FieldValues, RedisData, RedisHash, RedisValue, StoreError, TtlState,
+};
+use std::time::SystemTime;
+
+/// Hashes at or under both limits report the compact `listpack` encoding.
+const HASH_MAX_LISTPACK_ENTRIES: usize = 128;
+const HASH_MAX_LISTPACK_VALUE: usize = 64;
+
+/// The live hash at `key`, or `WrongType` if the key holds something else.
+fn hash_mut<'a>(
+    store: &'a mut Keyspace,
+    key: &str,
+) -> Result<Option<&'a mut RedisHash>, StoreError> {
+    match store.get_live_mut(key) {
+        Some(RedisValue {
+            data: RedisData::Hash(hash),
+            ..
+        }) => Ok(Some(hash)),
+        Some(_) => Err(StoreError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// The hash at `key`, created empty if the key does not exist.
+fn hash_entry<'a>(store: &'a mut Keyspace, key: &str) -> Result<&'a mut RedisHash, StoreError> {
+    if hash_mut(store, key)?.is_none() {
+        store.insert(
+            key.to_string(),
+            RedisValue {
+                data: RedisData::Hash(RedisHash::default()),
+                expires_at: None,
+            },
+        );
+    }
+    Ok(hash_mut(store, key)?.unwrap())
+}
+
+/// Deletes `key` once its hash has no fields left.
+fn remove_if_empty(store: &mut Keyspace, key: &str) {
+    if matches!(hash_mut(store, key), Ok(Some(hash)) if hash.is_empty()) {
+        store.remove(key);
+    }
+}
+
+impl RedisHash {
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn get(&self, field: &[u8]) -> Option<&Vec<u8>> {
+        self.fields.get(field).map(|(value, _)| value)
+    }
+
+    /// Sets `field`, returning whether it is new. Overwriting a field
+    /// clears its TTL.
+    pub fn insert(&mut self, field: Vec<u8>, value: Vec<u8>) -> bool {
+        self.expires.remove(&field);
+        if let Some(entry) = self.fields.get_mut(&field) {
+            entry.0 = value;
+            return false;
+        }
+        self.next_sequence += 1;
+        self.order.insert(self.next_sequence, field.clone());
+        self.fields.insert(field, (value, self.next_sequence));
+        true
+    }
+
+    pub fn remove(&mut self, field: &[u8]) -> bool {
+        self.expires.remove(field);
+        match self.fields.remove(field) {
+            Some((_, sequence)) => {
+                self.order.remove(&sequence);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Fields and values in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Vec<u8>, &Vec<u8>)> {
+        self.order
+            .values()
+            .map(|field| (field, &self.fields[field].0))
+    }
+
+    /// Visits up to `count` fields from `cursor`, returning them with the
+    /// cursor to resume from (0 once finished).
+    pub fn scan(&self, cursor: u64, count: usize) -> (u64, FieldValues) {
+        let mut visited = self.order.range(cursor..);
+        let fields = visited
+            .by_ref()
+            .take(count)
+            .map(|(_, field)| (field.clone(), self.fields[field].0.clone()))
+            .collect();
+        let next = visited.next().map_or(0, |(sequence, _)| *sequence);
+        (next, fields)
+    }
+
+    pub fn expiry(&self, field: &[u8]) -> Option<SystemTime> {
+        self.expires.get(field).copied()
+    }
+
+    /// Sets or clears the TTL of an existing field.
+    pub fn set_expiry(&mut self, field: &[u8], at: Option<SystemTime>) {
+        match at {
+            Some(at) if self.fields.contains_key(field) => {
+                self.expires.insert(field.to_vec(), at);
+            }
+            _ => {
+                self.expires.remove(field);
+            }
+        }
+    }
+
+    /// Whether any field carries a TTL.
+    pub fn has_field_ttls(&self) -> bool {
+        !self.expires.is_empty()
+    }
+
+    /// Drops fields whose TTL has elapsed by `now`, returning how many.
+    pub fn remove_expired(&mut self, now: SystemTime) -> usize {
+        if self.expires.is_empty() {
+            return 0;
+        }
+        let expired: Vec<Vec<u8>> = self
+            .expires
+            .iter()
+            .filter(|(_, &at)| at < now)
+            .map(|(field, _)| field.clone())
+            .collect();
+        for field in &expired {
+            self.remove(field);
+        }
+        expired.len()
+    }
+
+    /// Encoding reported by OBJECT ENCODING.
+    pub(crate) fn encoding(&self) -> &'static str {
+        let small = self.len() <= HASH_MAX_LISTPACK_ENTRIES
+            && self
+                .iter()
+                .all(|(k, v)| k.len().max(v.len()) <= HASH_MAX_LISTPACK_VALUE);
+        if small {
+            "listpack"
+        } else {
+            "hashtable"
+        }
+    }
+}
+
+impl RedisStore {
+    /// HSET: sets each field/value pair, creating the hash if needed, and
+    /// returns how many fields were new.
+    pub fn hash_set(&self, key: String, pairs: FieldValues) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let hash = hash_entry(&mut store, &key)?;
+        Ok(pairs
+            .into_iter()
+            .map(|(field, value)| hash.insert(field, value) as usize)
+            .sum())
+    }
+
+    /// HGET: the value of `field`.
+    pub fn hash_get(&self, key: &str, field: &[u8]) -> Result<Option<Vec<u8>>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(hash_mut(&mut store, key)?.and_then(|hash| hash.get(field).cloned()))
+    }
+
+    /// HMGET: the value of each of `fields`.
+    pub fn hash_get_many(
+        &self,
+        key: &str,
+        fields: &[Vec<u8>],
+    ) -> Result<Vec<Option<Vec<u8>>>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let hash = hash_mut(&mut store, key)?;
+        Ok(fields
+            .iter()
+            .map(|field| hash.as_ref().and_then(|h| h.get(field).cloned()))
+            .collect())
+    }
+
+    /// HGETALL: every field and value, in insertion order.
+    pub fn hash_get_all(&self, key: &str) -> Result<FieldValues, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(hash_mut(&mut store, key)?.map_or_else(Vec::new, |hash| {
+            hash.iter()
+                .map(|(field, value)| (field.clone(), value.clone()))
+                .collect()
+        }))
+    }
+
+    /// HDEL: removes `fields`, deleting the key once none are left, and
+    /// returns how many existed.
+    pub fn hash_delete(&self, key: &str, fields: &[Vec<u8>]) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(hash) = hash_mut(&mut store, key)? else {
+            return Ok(0);
+        };
+        let removed = fields.iter().filter(|field| hash.remove(field)).count();
+        remove_if_empty(&mut store, key);
+        Ok(removed)
+    }
+
+    /// HEXISTS: whether `field` is set.
+    pub fn hash_exists(&self, key: &str, field: &[u8]) -> Result<bool, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(hash_mut(&mut store, key)?.is_some_and(|hash| hash.get(field).is_some()))
+    }
+
+    /// HLEN: the number of fields.
+    pub fn hash_len(&self, key: &str) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(hash_mut(&mut store, key)?.map_or(0, |hash| hash.len()))
+    }
+
+    /// HINCRBY: adds `delta` to the integer in `field`, treating a missing
+    /// field as 0. The field keeps its TTL.
+    pub fn hash_incr_by(&self, key: String, field: Vec<u8>, delta: i64) -> Result<i64, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let hash = hash_entry(&mut store, &key)?;
+        let current = match hash.get(&field) {
+            Some(value) => std::str::from_utf8(value)
+                .ok()
+                .and_then(|s| s.parse::<i64>().ok())
+                .ok_or(StoreError::HashNotInteger)?,
+            None => 0,
+        };
+        let next = current.checked_add(delta).ok_or(StoreError::Overflow)?;
+
+        let expiry = hash.expiry(&field);
+        hash.insert(field.clone(), next.to_string().into_bytes());
+        hash.set_expiry(&field, expiry);
+        Ok(next)
+    }
+
+    /// HSCAN: visits up to `count` fields from `cursor`, returning them with
+    /// the cursor to resume from (0 once finished).
+    pub fn hash_scan(
+        &self,
+        key: &str,
+        cursor: u64,
+        count: usize,
+    ) -> Result<(u64, FieldValues), StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(hash_mut(&mut store, key)?
+            .map_or((0, Vec::new()), |hash| hash.scan(cursor, count.max(1))))
+    }
+
+    /// HEXPIRE and friends: sets the TTL of each of `fields` to `at` where
+    /// `condition` allows. Per field, returns -2 if it does not exist, 0 if
+    /// the condition was not met, 1 if the TTL was set, and 2 if `at` has
+    /// already passed and the field was deleted.
+    pub fn hash_expire(
+        &self,
+        key: &str,
+        fields: &[Vec<u8>],
+        at: SystemTime,
+        condition: ExpireCondition,
+    ) -> Result<Vec<i64>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(hash) = hash_mut(&mut store, key)? else {
+            return Ok(vec![-2; fields.len()]);
+        };
+
+        let now = SystemTime::now();
+        let results = fields
+            .iter()
+            .map(|field| {
+                if hash.get(field).is_none() {
+                    -2
+                } else if !condition.allows(hash.expiry(field), at) {
+                    0
+                } else if at <= now {
+                    hash.remove(field);
+                    2
+                } else {
+                    hash.set_expiry(field, Some(at));
+                    1
+                }
+            })
+            .collect();
+        let has_field_ttls = hash.has_field_ttls();
+        store.set_field_ttls(key, has_field_ttls);
+        remove_if_empty(&mut store, key);
+        Ok(results)
+    }
+
+    /// HTTL and friends: the TTL state of each of `fields`.
+    pub fn hash_ttl(&self, key: &str, fields: &[Vec<u8>]) -> Result<Vec<TtlState>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let hash = hash_mut(&mut store, key)?;
+        Ok(fields
+            .iter()
+            .map(
+                |field| match hash.as_ref().filter(|h| h.get(field).is_some()) {
+                    None => TtlState::Missing,
+                    Some(h) => h
+                        .expiry(field)
+                        .map_or(TtlState::Persistent, TtlState::ExpiresAt),
+                },
+            )
+            .collect())
+    }
+
+    /// HPERSIST: clears the TTL of each of `fields`. Per field, returns -2 if
+    /// it does not exist, -1 if it had no TTL, and 1 if the TTL was removed.
+    pub fn hash_persist(&self, key: &str, fields: &[Vec<u8>]) -> Result<Vec<i64>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let mut hash = hash_mut(&mut store, key)?;
+        Ok(fields
+            .iter()
+            .map(
+                |field| match hash.as_mut().filter(|h| h.get(field).is_some()) {
+                    None => -2,
+                    Some(h) if h.expiry(field).is_none() => -1,
+                    Some(h) => {
+                        h.set_expiry(field, None);
+                        1
+                    }
+                },
+            )
+            .collect())
+    }
+}
@@ -0,0 +1,366 @@
+use super::RedisStore;
+use crate::keyspace::Keyspace;
+use crate::types::{
+    BlockAction, ListEnd, PopOutcome, Popped, RedisData, RedisValue, Served, StoreError,
+};
+use std::collections::VecDeque;
+
+/// Lists at or under both limits report the compact `listpack` encoding.
+const LIST_MAX_LISTPACK_ENTRIES: usize = 128;
+const LIST_MAX_LISTPACK_VALUE: usize = 64;
+
+/// Encoding reported by OBJECT ENCODING.
+pub(super) fn encoding(items: &VecDeque<Vec<u8>>) -> &'static str {
+    if items.len() <= LIST_MAX_LISTPACK_ENTRIES
+        && items.iter().all(|s| s.len() <= LIST_MAX_LISTPACK_VALUE)
+    {
+        "listpack"
+    } else {
+        "quicklist"
+    }
+}
+
+/// The live list at `key`, or `WrongType` if the key holds something else.
+fn list_mut<'a>(
+    store: &'a mut Keyspace,
+    key: &str,
+) -> Result<Option<&'a mut VecDeque<Vec<u8>>>, StoreError> {
+    match store.get_live_mut(key) {
+        Some(RedisValue {
+            data: RedisData::List(items),
+            ..
+        }) => Ok(Some(items)),
+        Some(_) => Err(StoreError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// Pushes `values` one at a time onto `end` of the list at `key`, creating
+/// it if needed, and returns the new length.
+fn push_values(
+    store: &mut Keyspace,
+    key: String,
+    values: impl IntoIterator<Item = Vec<u8>>,
+    end: ListEnd,
+) -> Result<usize, StoreError> {
+    let push = |items: &mut VecDeque<Vec<u8>>, values: _| {
+        for value in values {
+            match end {
+                ListEnd::Left => items.push_front(value),
+                ListEnd::Right => items.push_back(value),
+            }
+        }
+        items.len()
+    };
+
+    let len = match list_mut(store, &key)? {
+        Some(items) => push(items, values),
+        None => {
+            let mut items = VecDeque::new();
+            let len = push(&mut items, values);
+            store.insert(
+                key.clone(),
+                RedisValue {
+                    data: RedisData::List(items),
+                    expires_at: None,
+                },
+            );
+            len
+        }
+    };
+    serve_blocked(store, &key);
+    Ok(len)
+}
+
+/// Pops one item from `end` of the list at `key`, deleting it once empty.
+fn pop_one(store: &mut Keyspace, key: &str, end: ListEnd) -> Result<Option<Vec<u8>>, StoreError> {
+    let Some(items) = list_mut(store, key)? else {
+        return Ok(None);
+    };
+    let value = match end {
+        ListEnd::Left => items.pop_front(),
+        ListEnd::Right => items.pop_back(),
+    };
+    if items.is_empty() {
+        store.remove(key);
+    }
+    Ok(value)
+}
+
+/// Moves one item between lists, refusing before popping if `destination`
+/// is not a list so a failed move leaves `source` intact.
+fn move_one(
+    store: &mut Keyspace,
+    source: &str,
+    destination: &str,
+    from: ListEnd,
+    to: ListEnd,
+) -> Result<Option<Vec<u8>>, StoreError> {
+    if list_mut(store, source)?.is_none() {
+        return Ok(None);
+    }
+    list_mut(store, destination)?;
+
+    let value = pop_one(store, source, from)?.unwrap();
+    push_values(store, destination.to_string(), [value.clone()], to)?;
+    Ok(Some(value))
+}
+
+/// Hands items on `key` to the clients blocked on it, longest-waiting
+/// first, for as long as both last.
+pub(super) fn serve_blocked(store: &mut Keyspace, key: &str) {
+    for id in store.blocked.queue(key) {
+        if !matches!(list_mut(store, key), Ok(Some(_))) {
+            return;
+        }
+        // Stream readers share the queue but are served by XADD; a waiter
+        // may also have been served already by a move back onto this key
+        if matches!(
+            store.blocked.action(id),
+            None | Some(BlockAction::Read { .. } | BlockAction::ReadGroup { .. })
+        ) {
+            continue;
+        }
+        let waiter = store.blocked.remove(id).unwrap();
+        // The client already gave up; leave the item for the next one
+        if waiter.sender.is_closed() {
+            continue;
+        }
+
+        let served = match &waiter.action {
+            BlockAction::Pop(end) => pop_one(store, key, *end),
+            BlockAction::Move {
+                destination,
+                from,
+                to,
+            } => move_one(store, key, destination, *from, *to),
+            BlockAction::Read { .. } | BlockAction::ReadGroup { .. } => {
+                unreachable!("stream readers are skipped above")
+            }
+        };
+        let served = served.map(|value| {
+            Served::Item(Popped {
+                key: key.to_string(),
+                value: value.unwrap(),
+            })
+        });
+        if let Err(Ok(Served::Item(popped))) = waiter.sender.send(served) {
+            if let BlockAction::Pop(end) = waiter.action {
+                let _ = push_values(store, popped.key, [popped.value], end);
+            }
+        }
+    }
+}
+
+/// Resolves inclusive `start`/`stop` offsets, negative ones counting back
+/// from the end, to an index range of a list of `len` items.
+pub(super) fn resolve_range(len: usize, start: i64, stop: i64) -> Option<(usize, usize)> {
+    let len = len as i64;
+    let start = if start < 0 {
+        (len + start).max(0)
+    } else {
+        start
+    };
+    let stop = if stop < 0 {
+        len + stop
+    } else {
+        stop.min(len - 1)
+    };
+    if start > stop || start >= len {
+        return None;
+    }
+    Some((start as usize, stop as usize))
+}
+
+/// Resolves a possibly negative `index` into a list of `len` items.
+fn resolve_index(len: usize, index: i64) -> Option<usize> {
+    let index = if index < 0 { len as i64 + index } else { index };
+    (0..len as i64).contains(&index).then_some(index as usize)
+}
+
+impl RedisStore {
+    /// LPUSH/RPUSH: pushes `values` in order onto `end` of the list at `key`,
+    /// returning its new length.
+    pub fn push(
+        &self,
+        key: String,
+        values: Vec<Vec<u8>>,
+        end: ListEnd,
+    ) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        push_values(&mut store, key, values, end)
+    }
+
+    /// LPOP/RPOP: removes up to `count` items from `end` of the list at
+    /// `key`, or returns `None` if it does not exist. An emptied list is
+    /// deleted.
+    pub fn pop(
+        &self,
+        key: &str,
+        end: ListEnd,
+        count: usize,
+    ) -> Result<Option<Vec<Vec<u8>>>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(items) = list_mut(&mut store, key)? else {
+            return Ok(None);
+        };
+
+        let count = count.min(items.len());
+        let popped = match end {
+            ListEnd::Left => items.drain(..count).collect(),
+            ListEnd::Right => items.drain(items.len() - count..).rev().collect(),
+        };
+        if items.is_empty() {
+            store.remove(key);
+        }
+        Ok(Some(popped))
+    }
+
+    /// LRANGE: the items between the inclusive offsets `start` and `stop`.
+    pub fn list_range(&self, key: &str, start: i64, stop: i64) -> Result<Vec<Vec<u8>>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(items) = list_mut(&mut store, key)? else {
+            return Ok(Vec::new());
+        };
+        Ok(match resolve_range(items.len(), start, stop) {
+            Some((start, stop)) => items.range(start..=stop).cloned().collect(),
+            None => Vec::new(),
+        })
+    }
+
+    /// LLEN: the length of the list at `key`, 0 if it does not exist.
+    pub fn list_len(&self, key: &str) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(list_mut(&mut store, key)?.map_or(0, |items| items.len()))
+    }
+
+    /// LINDEX: the item at `index`, negative indexes counting from the end.
+    pub fn list_index(&self, key: &str, index: i64) -> Result<Option<Vec<u8>>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(items) = list_mut(&mut store, key)? else {
+            return Ok(None);
+        };
+        Ok(resolve_index(items.len(), index).map(|i| items[i].clone()))
+    }
+
+    /// LSET: replaces the item at `index`.
+    pub fn list_set(&self, key: &str, index: i64, value: Vec<u8>) -> Result<(), StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let items = list_mut(&mut store, key)?.ok_or(StoreError::NoSuchKey)?;
+        let index = resolve_index(items.len(), index).ok_or(StoreError::IndexOutOfRange)?;
+        items[index] = value;
+        Ok(())
+    }
+
+    /// LTRIM: keeps only the items between the inclusive offsets `start` and
+    /// `stop`, deleting the key if none remain.
+    pub fn list_trim(&self, key: &str, start: i64, stop: i64) -> Result<(), StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(items) = list_mut(&mut store, key)? else {
+            return Ok(());
+        };
+        match resolve_range(items.len(), start, stop) {
+            Some((start, stop)) => {
+                items.truncate(stop + 1);
+                items.drain(..start);
+            }
+            None => {
+                store.remove(key);
+            }
+        }
+        Ok(())
+    }
+
+    /// LREM: removes items equal to `value`; the first `count` from the head
+    /// if positive, the last `-count` from the tail if negative, or all of
+    /// them if 0. Returns how many were removed.
+    pub fn list_remove(&self, key: &str, count: i64, value: &[u8]) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(items) = list_mut(&mut store, key)? else {
+            return Ok(0);
+        };
+
+        let limit = match count {
+            0 => usize::MAX,
+            n => n.unsigned_abs() as usize,
+        };
+        let mut removed = 0;
+        if count >= 0 {
+            items.retain(|item| {
+                let matched = removed < limit && item == value;
+                removed += matched as usize;
+                !matched
+            });
+        } else {
+            let mut i = items.len();
+            while i > 0 && removed < limit {
+                i -= 1;
+                if items[i] == value {
+                    items.remove(i);
+                    removed += 1;
+                }
+            }
+        }
+
+        if items.is_empty() {
+            store.remove(key);
+        }
+        Ok(removed)
+    }
+
+    /// LMOVE: atomically pops an item from `from` of `source` and pushes it
+    /// onto `to` of `destination`, returning it, or `None` if `source` does
+    /// not exist. `source` and `destination` may be the same list.
+    pub fn list_move(
+        &self,
+        source: &str,
+        destination: &str,
+        from: ListEnd,
+        to: ListEnd,
+    ) -> Result<Option<Vec<u8>>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        move_one(&mut store, source, destination, from, to)
+    }
+
+    /// BLPOP/BRPOP/BLMOVE: performs `action` on the first of `keys` holding
+    /// items, or queues the caller on all of them until a push arrives.
+    /// Every key is type-checked first, as the non-blocking commands would.
+    pub fn pop_or_block(
+        &self,
+        keys: Vec<String>,
+        action: BlockAction,
+    ) -> Result<PopOutcome, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        for key in &keys {
+            list_mut(&mut store, key)?;
+        }
+
+        for key in &keys {
+            let value = match &action {
+                BlockAction::Pop(end) => pop_one(&mut store, key, *end)?,
+                BlockAction::Move {
+                    destination,
+                    from,
+                    to,
+                } => move_one(&mut store, key, destination, *from, *to)?,
+                BlockAction::Read { .. } | BlockAction::ReadGroup { .. } => {
+                    unreachable!("stream reads block through xread and xreadgroup")
+                }
+            };
+            if let Some(value) = value {
+                return Ok(PopOutcome::Ready(Popped {
+                    key: key.clone(),
+                    value,
+                }));
+            }
+        }
+        Ok(PopOutcome::Blocked(store.blocked.add(keys, action)))
+    }
+
+    /// Releases blocked client `id`, returning false if it was already
+    /// served (its item is then waiting on the ticket's receiver).
+    pub fn unblock(&self, id: u64) -> bool {
+        let mut store = self.data.lock().unwrap();
+        store.blocked.remove(id).is_some()
+    }
+}
@@ -0,0 +1,146 @@
+use super::{is_canonical_int, RedisStore};
+use crate::keyspace::Keyspace;
+use crate::types::{RedisData, RedisValue, SetOp, StoreError};
+use std::collections::HashSet;
+
+/// Sets of integers up to this size report the `intset` encoding.
+const SET_MAX_INTSET_ENTRIES: usize = 512;
+/// Other sets at or under both limits report `listpack`.
+const SET_MAX_LISTPACK_ENTRIES: usize = 128;
+const SET_MAX_LISTPACK_VALUE: usize = 64;
+
+/// Encoding reported by OBJECT ENCODING.
+pub(super) fn encoding(members: &HashSet<Vec<u8>>) -> &'static str {
+    if members.len() <= SET_MAX_INTSET_ENTRIES && members.iter().all(|m| is_canonical_int(m)) {
+        "intset"
+    } else if members.len() <= SET_MAX_LISTPACK_ENTRIES
+        && members.iter().all(|m| m.len() <= SET_MAX_LISTPACK_VALUE)
+    {
+        "listpack"
+    } else {
+        "hashtable"
+    }
+}
+
+/// The live set at `key`, or `WrongType` if the key holds something else.
+fn set_mut<'a>(
+    store: &'a mut Keyspace,
+    key: &str,
+) -> Result<Option<&'a mut HashSet<Vec<u8>>>, StoreError> {
+    match store.get_live_mut(key) {
+        Some(RedisValue {
+            data: RedisData::Set(members),
+            ..
+        }) => Ok(Some(members)),
+        Some(_) => Err(StoreError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// Applies `op` to the sets at `keys`, a missing key counting as the empty
+/// set. Every key is type-checked before anything is combined.
+fn combine(
+    store: &mut Keyspace,
+    keys: &[String],
+    op: SetOp,
+) -> Result<HashSet<Vec<u8>>, StoreError> {
+    let mut sets = Vec::with_capacity(keys.len());
+    for key in keys {
+        sets.push(set_mut(store, key)?.map(|members| &*members).cloned());
+    }
+    let mut sets = sets.into_iter().map(Option::unwrap_or_default);
+    let first = sets.next().unwrap_or_default();
+    Ok(sets.fold(first, |acc, set| match op {
+        SetOp::Inter => acc.intersection(&set).cloned().collect(),
+        SetOp::Union => acc.union(&set).cloned().collect(),
+        SetOp::Diff => acc.difference(&set).cloned().collect(),
+    }))
+}
+
+impl RedisStore {
+    /// SADD: adds `members`, creating the set if needed, and returns how
+    /// many were not already present.
+    pub fn set_add(&self, key: String, members: Vec<Vec<u8>>) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        if let Some(set) = set_mut(&mut store, &key)? {
+            return Ok(members
+                .into_iter()
+                .filter(|m| set.insert(m.clone()))
+                .count());
+        }
+        let set: HashSet<Vec<u8>> = members.into_iter().collect();
+        let added = set.len();
+        store.insert(
+            key,
+            RedisValue {
+                data: RedisData::Set(set),
+                expires_at: None,
+            },
+        );
+        Ok(added)
+    }
+
+    /// SREM: removes `members`, deleting the key once none are left, and
+    /// returns how many were present.
+    pub fn set_remove(&self, key: &str, members: &[Vec<u8>]) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(set) = set_mut(&mut store, key)? else {
+            return Ok(0);
+        };
+        let removed = members.iter().filter(|m| set.remove(*m)).count();
+        if set.is_empty() {
+            store.remove(key);
+        }
+        Ok(removed)
+    }
+
+    /// SMEMBERS: every member, in no particular order.
+    pub fn set_members(&self, key: &str) -> Result<Vec<Vec<u8>>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(set_mut(&mut store, key)?.map_or_else(Vec::new, |set| set.iter().cloned().collect()))
+    }
+
+    /// SISMEMBER: whether `member` is in the set.
+    pub fn set_is_member(&self, key: &str, member: &[u8]) -> Result<bool, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(set_mut(&mut store, key)?.is_some_and(|set| set.contains(member)))
+    }
+
+    /// SCARD: the number of members.
+    pub fn set_card(&self, key: &str) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(set_mut(&mut store, key)?.map_or(0, |set| set.len()))
+    }
+
+    /// SINTER, SUNION and SDIFF: the members resulting from `op` over `keys`.
+    pub fn set_combine(&self, keys: &[String], op: SetOp) -> Result<Vec<Vec<u8>>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(combine(&mut store, keys, op)?.into_iter().collect())
+    }
+
+    /// The *STORE variants: writes the result of `op` over `keys` to
+    /// `destination`, replacing whatever it held, and returns its size. An
+    /// empty result deletes `destination`.
+    pub fn set_combine_store(
+        &self,
+        destination: String,
+        keys: &[String],
+        op: SetOp,
+    ) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let set = combine(&mut store, keys, op)?;
+        let len = set.len();
+        if set.is_empty() {
+            store.remove(&destination);
+        } else {
+            store.insert(
+                destination,
+                RedisValue {
+                    data: RedisData::Set(set),
+                    expires_at: None,
+                },
+            );
+        }
+        Ok(len)
+    }
+}
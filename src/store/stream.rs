@@ -0,0 +1,735 @@
+use super::RedisStore;
+use crate::keyspace::Keyspace;
+use crate::types::{
+    AutoClaim, BlockAction, ConsumerGroup, FieldValues, PendingEntry, PendingInfo, ReadOutcome,
+    RedisData, RedisValue, Served, StoreError, Stream, StreamEntry, StreamId, StreamTrim,
+    TrimThreshold, XAddId, XClaimOptions,
+};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::{Bound, RangeBounds};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// The live stream at `key`, or `WrongType` if the key holds something else.
+fn stream_mut<'a>(
+    store: &'a mut Keyspace,
+    key: &str,
+) -> Result<Option<&'a mut Stream>, StoreError> {
+    match store.get_live_mut(key) {
+        Some(RedisValue {
+            data: RedisData::Stream(stream),
+            ..
+        }) => Ok(Some(stream)),
+        Some(_) => Err(StoreError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// The stream at `key`, created empty if the key does not exist.
+fn stream_entry<'a>(store: &'a mut Keyspace, key: &str) -> Result<&'a mut Stream, StoreError> {
+    if stream_mut(store, key)?.is_none() {
+        store.insert(
+            key.to_string(),
+            RedisValue {
+                data: RedisData::Stream(Stream::default()),
+                expires_at: None,
+            },
+        );
+    }
+    Ok(stream_mut(store, key)?.unwrap())
+}
+
+/// The stream at `key` for an XGROUP subcommand, which needs it to exist.
+fn group_stream<'a>(store: &'a mut Keyspace, key: &str) -> Result<&'a mut Stream, StoreError> {
+    stream_mut(store, key)?.ok_or(StoreError::GroupKeyMissing)
+}
+
+/// The stream at `key`, provided it has consumer group `group`.
+fn stream_with_group<'a>(
+    store: &'a mut Keyspace,
+    key: &str,
+    group: &str,
+) -> Result<&'a mut Stream, StoreError> {
+    match stream_mut(store, key)? {
+        Some(stream) if stream.group(group).is_some() => Ok(stream),
+        _ => Err(StoreError::NoGroup),
+    }
+}
+
+/// Up to `count` entries of `stream` after `id`.
+fn read_after(stream: &Stream, id: StreamId, count: Option<usize>) -> Vec<StreamEntry> {
+    stream
+        .range(Bound::Excluded(id), Bound::Unbounded)
+        .take(count.unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// Hands `consumer` up to `count` entries `group` has not seen yet,
+/// recording them as pending unless `no_ack`. `None` if there is no such
+/// group.
+fn deliver_new(
+    stream: &mut Stream,
+    group: &str,
+    consumer: &str,
+    count: Option<usize>,
+    no_ack: bool,
+    now_ms: u64,
+) -> Option<Vec<StreamEntry>> {
+    let entries = read_after(stream, stream.group(group)?.last_delivered, count);
+    let group = stream.group_mut(group)?;
+    group.touch(consumer, now_ms);
+    if let Some(entry) = entries.last() {
+        group.last_delivered = entry.id;
+    }
+    if !no_ack {
+        for entry in &entries {
+            group.deliver(entry.id, consumer, now_ms);
+        }
+    }
+    Some(entries)
+}
+
+/// Up to `count` of the entries pending for `consumer` after `after`,
+/// leaving out any trimmed from the stream since.
+fn pending_history(
+    stream: &Stream,
+    group: &str,
+    consumer: &str,
+    after: StreamId,
+    count: Option<usize>,
+) -> Option<Vec<StreamEntry>> {
+    Some(
+        stream
+            .group(group)?
+            .pending
+            .range((Bound::Excluded(after), Bound::Unbounded))
+            .filter(|(_, pending)| pending.consumer == consumer)
+            .filter_map(|(id, _)| stream.get(*id))
+            .take(count.unwrap_or(usize::MAX))
+            .collect(),
+    )
+}
+
+/// Hands new entries on `key` to the XREAD and XREADGROUP clients blocked
+/// on it that have not seen them yet, longest-waiting first.
+pub(super) fn serve_blocked(store: &mut Keyspace, key: &str) {
+    let now_ms = now_ms();
+    for id in store.blocked.queue(key) {
+        let Some(action) = store.blocked.action(id).cloned() else {
+            continue;
+        };
+        let Ok(Some(stream)) = stream_mut(store, key) else {
+            return;
+        };
+        let has_new = |after| !read_after(stream, after, Some(1)).is_empty();
+        let ready = match &action {
+            BlockAction::Read { after, .. } => has_new(after[key]),
+            // A destroyed group is reported to its readers straight away
+            BlockAction::ReadGroup { group, .. } => stream
+                .group(group)
+                .is_none_or(|group| has_new(group.last_delivered)),
+            // List waiters share the queue but are served by pushes
+            BlockAction::Pop(_) | BlockAction::Move { .. } => false,
+        };
+        if !ready {
+            continue;
+        }
+
+        let waiter = store.blocked.remove(id).unwrap();
+        // The client already gave up; leave the entries undelivered
+        if waiter.sender.is_closed() {
+            continue;
+        }
+        let stream = stream_mut(store, key).unwrap().unwrap();
+        let served = match waiter.action {
+            BlockAction::Read { after, count } => Ok(read_after(stream, after[key], count)),
+            BlockAction::ReadGroup {
+                group,
+                consumer,
+                count,
+                no_ack,
+            } => deliver_new(stream, &group, &consumer, count, no_ack, now_ms)
+                .ok_or(StoreError::NoGroup),
+            BlockAction::Pop(_) | BlockAction::Move { .. } => unreachable!("not a stream reader"),
+        };
+        let _ = waiter.sender.send(served.map(|entries| Served::Entries {
+            key: key.to_string(),
+            entries,
+        }));
+    }
+}
+
+impl Stream {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The highest ID ever added, even if since trimmed away.
+    pub fn last_id(&self) -> StreamId {
+        self.last_id
+    }
+
+    /// Resolves an XADD ID against the top of the stream, `now_ms` feeding
+    /// generated IDs.
+    pub fn next_id(&self, requested: XAddId, now_ms: u64) -> Result<StreamId, StoreError> {
+        let last = self.last_id;
+        let after_last = |ms: u64| -> Option<StreamId> {
+            match ms.cmp(&last.ms) {
+                Ordering::Greater => Some(StreamId::new(ms, 0)),
+                Ordering::Equal => last.seq.checked_add(1).map(|seq| StreamId::new(ms, seq)),
+                Ordering::Less => None,
+            }
+        };
+        let id = match requested {
+            XAddId::Auto => {
+                after_last(now_ms.max(last.ms)).or_else(|| after_last(last.ms.checked_add(1)?))
+            }
+            XAddId::AutoSeq(ms) => after_last(ms),
+            XAddId::Explicit(id) if id == StreamId::MIN => {
+                return Err(StoreError::StreamIdZero);
+            }
+            XAddId::Explicit(id) => Some(id).filter(|id| *id > last),
+        };
+        id.ok_or(StoreError::StreamIdTooSmall)
+    }
+
+    /// Appends an entry; `id` must come from `next_id`.
+    pub fn push(&mut self, id: StreamId, fields: FieldValues) {
+        self.last_id = id;
+        self.entries.insert(id, fields);
+    }
+
+    /// Entries with IDs between `start` and `end`, in ascending order.
+    pub fn range(
+        &self,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+    ) -> impl DoubleEndedIterator<Item = StreamEntry> + '_ {
+        // BTreeMap::range panics on an inverted or empty-exclusive interval
+        let empty = match (start, end) {
+            (Bound::Included(s), Bound::Included(e)) => s > e,
+            (Bound::Included(s) | Bound::Excluded(s), Bound::Included(e) | Bound::Excluded(e)) => {
+                s >= e
+            }
+            _ => false,
+        };
+        let range = if empty {
+            self.entries.range(StreamId::MAX..StreamId::MAX)
+        } else {
+            self.entries.range((start, end))
+        };
+        range.map(|(id, fields)| StreamEntry {
+            id: *id,
+            fields: fields.clone(),
+        })
+    }
+
+    /// The entry with `id`, unless it was trimmed away.
+    pub fn get(&self, id: StreamId) -> Option<StreamEntry> {
+        self.entries.get(&id).map(|fields| StreamEntry {
+            id,
+            fields: fields.clone(),
+        })
+    }
+
+    pub fn group(&self, name: &str) -> Option<&ConsumerGroup> {
+        self.groups.get(name)
+    }
+
+    pub fn group_mut(&mut self, name: &str) -> Option<&mut ConsumerGroup> {
+        self.groups.get_mut(name)
+    }
+
+    /// Adds a group that has already seen every entry up to
+    /// `last_delivered`, returning false if the name is taken.
+    pub fn create_group(&mut self, name: String, last_delivered: StreamId) -> bool {
+        if self.groups.contains_key(&name) {
+            return false;
+        }
+        self.groups.insert(
+            name,
+            ConsumerGroup {
+                last_delivered,
+                ..ConsumerGroup::default()
+            },
+        );
+        true
+    }
+
+    pub fn destroy_group(&mut self, name: &str) -> bool {
+        self.groups.remove(name).is_some()
+    }
+
+    /// Drops entries from the front according to `trim`, returning how many.
+    pub fn trim(&mut self, trim: StreamTrim) -> usize {
+        let limit = trim.limit.unwrap_or(usize::MAX);
+        let excess = match trim.threshold {
+            TrimThreshold::MaxLen(max) => self.len().saturating_sub(max),
+            TrimThreshold::MinId(min) => self.entries.range(..min).count(),
+        };
+        let evicted = excess.min(limit);
+        for _ in 0..evicted {
+            self.entries.pop_first();
+        }
+        evicted
+    }
+}
+
+impl ConsumerGroup {
+    /// Records a consumer as seen at `now_ms`, creating it if needed.
+    /// Returns true if it was created.
+    pub fn touch(&mut self, consumer: &str, now_ms: u64) -> bool {
+        self.consumers
+            .insert(consumer.to_string(), now_ms)
+            .is_none()
+    }
+
+    /// Hands entry `id` to `consumer`, counting a delivery.
+    pub fn deliver(&mut self, id: StreamId, consumer: &str, now_ms: u64) {
+        let deliveries = self.pending.get(&id).map_or(0, |entry| entry.deliveries);
+        self.pending.insert(
+            id,
+            PendingEntry {
+                consumer: consumer.to_string(),
+                delivered_ms: now_ms,
+                deliveries: deliveries + 1,
+            },
+        );
+    }
+
+    /// Deletes `consumer` along with its pending entries, returning how
+    /// many it had.
+    pub fn remove_consumer(&mut self, consumer: &str) -> usize {
+        if self.consumers.remove(consumer).is_none() {
+            return 0;
+        }
+        let before = self.pending.len();
+        self.pending.retain(|_, entry| entry.consumer != consumer);
+        before - self.pending.len()
+    }
+}
+
+impl RedisStore {
+    /// XADD: appends an entry with `fields`, creating the stream unless
+    /// `create` is false, then applies `trim`. Returns the new entry's ID,
+    /// or `None` if the stream did not exist and was not created.
+    pub fn xadd(
+        &self,
+        key: String,
+        id: XAddId,
+        fields: FieldValues,
+        create: bool,
+        trim: Option<StreamTrim>,
+    ) -> Result<Option<StreamId>, StoreError> {
+        let now_ms = now_ms();
+        let mut store = self.data.lock().unwrap();
+        // Resolve the ID before creating anything, so a bad one leaves no key
+        let id = match stream_mut(&mut store, &key)? {
+            Some(stream) => stream.next_id(id, now_ms)?,
+            None if !create => return Ok(None),
+            None => Stream::default().next_id(id, now_ms)?,
+        };
+
+        let stream = stream_entry(&mut store, &key)?;
+        stream.push(id, fields);
+        if let Some(trim) = trim {
+            stream.trim(trim);
+        }
+        serve_blocked(&mut store, &key);
+        Ok(Some(id))
+    }
+
+    /// XTRIM: drops entries from the front of the stream according to
+    /// `trim`, returning how many.
+    pub fn xtrim(&self, key: &str, trim: StreamTrim) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(stream_mut(&mut store, key)?.map_or(0, |stream| stream.trim(trim)))
+    }
+
+    /// XLEN: the number of entries.
+    pub fn xlen(&self, key: &str) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(stream_mut(&mut store, key)?.map_or(0, |stream| stream.len()))
+    }
+
+    /// XRANGE and XREVRANGE: up to `count` entries with IDs between `start`
+    /// and `end`, in ascending order or descending with `rev`.
+    pub fn xrange(
+        &self,
+        key: &str,
+        start: Bound<StreamId>,
+        end: Bound<StreamId>,
+        rev: bool,
+        count: Option<usize>,
+    ) -> Result<Vec<StreamEntry>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(stream) = stream_mut(&mut store, key)? else {
+            return Ok(Vec::new());
+        };
+        let count = count.unwrap_or(usize::MAX);
+        let entries = stream.range(start, end);
+        Ok(if rev {
+            entries.rev().take(count).collect()
+        } else {
+            entries.take(count).collect()
+        })
+    }
+
+    /// XREAD: up to `count` entries after each stream's ID, where `None`
+    /// stands for `$`, the stream's current last ID. If no stream has any
+    /// and `block` is set, the client is queued on every key instead.
+    pub fn xread(
+        &self,
+        streams: Vec<(String, Option<StreamId>)>,
+        count: Option<usize>,
+        block: bool,
+    ) -> Result<ReadOutcome, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        for (key, _) in &streams {
+            stream_mut(&mut store, key)?;
+        }
+
+        let mut ready = Vec::new();
+        let mut after = HashMap::new();
+        for (key, id) in &streams {
+            let stream = stream_mut(&mut store, key)?;
+            let id = id.unwrap_or_else(|| stream.as_ref().map_or(StreamId::MIN, |s| s.last_id()));
+            if let Some(stream) = stream {
+                let entries = read_after(stream, id, count);
+                if !entries.is_empty() {
+                    ready.push((key.clone(), entries));
+                }
+            }
+            after.insert(key.clone(), id);
+        }
+        if !ready.is_empty() || !block {
+            return Ok(ReadOutcome::Ready(ready));
+        }
+
+        let keys = streams.into_iter().map(|(key, _)| key).collect();
+        Ok(ReadOutcome::Blocked(
+            store.blocked.add(keys, BlockAction::Read { after, count }),
+        ))
+    }
+
+    /// XGROUP CREATE: adds `group` to the stream at `key`, having seen
+    /// everything up to `id`, where `None` stands for `$`. With `mkstream`
+    /// a missing stream is created empty.
+    pub fn xgroup_create(
+        &self,
+        key: &str,
+        group: &str,
+        id: Option<StreamId>,
+        mkstream: bool,
+    ) -> Result<(), StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let stream = if mkstream {
+            stream_entry(&mut store, key)?
+        } else {
+            group_stream(&mut store, key)?
+        };
+        let id = id.unwrap_or(stream.last_id());
+        if !stream.create_group(group.to_string(), id) {
+            return Err(StoreError::BusyGroup);
+        }
+        Ok(())
+    }
+
+    /// XGROUP SETID: moves the group's last delivered ID, `None` standing
+    /// for `$`.
+    pub fn xgroup_set_id(
+        &self,
+        key: &str,
+        group: &str,
+        id: Option<StreamId>,
+    ) -> Result<(), StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let stream = group_stream(&mut store, key)?;
+        let id = id.unwrap_or(stream.last_id());
+        stream
+            .group_mut(group)
+            .ok_or(StoreError::NoGroup)?
+            .last_delivered = id;
+        Ok(())
+    }
+
+    /// XGROUP DESTROY: removes `group` and its pending entries, failing any
+    /// of its readers still blocked. Returns whether it existed.
+    pub fn xgroup_destroy(&self, key: &str, group: &str) -> Result<bool, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let destroyed = group_stream(&mut store, key)?.destroy_group(group);
+        if destroyed {
+            serve_blocked(&mut store, key);
+        }
+        Ok(destroyed)
+    }
+
+    /// XGROUP CREATECONSUMER: returns whether `consumer` was new.
+    pub fn xgroup_create_consumer(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+    ) -> Result<bool, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let group = group_stream(&mut store, key)?
+            .group_mut(group)
+            .ok_or(StoreError::NoGroup)?;
+        Ok(!group.consumers.contains_key(consumer) && group.touch(consumer, now_ms()))
+    }
+
+    /// XGROUP DELCONSUMER: removes `consumer`, returning how many entries
+    /// it still had pending. Those are dropped unacknowledged.
+    pub fn xgroup_del_consumer(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+    ) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let group = group_stream(&mut store, key)?
+            .group_mut(group)
+            .ok_or(StoreError::NoGroup)?;
+        Ok(group.remove_consumer(consumer))
+    }
+
+    /// XREADGROUP: reads each stream on behalf of `consumer` in `group`.
+    /// An ID of `None` (`>`) delivers up to `count` entries new to the
+    /// group, adding them to the pending entries list unless `no_ack`;
+    /// an explicit ID rereads the consumer's own pending entries after it.
+    /// If only new entries were asked for, none came, and `block` is set,
+    /// the client is queued on every key instead.
+    pub fn xreadgroup(
+        &self,
+        group: &str,
+        consumer: &str,
+        streams: Vec<(String, Option<StreamId>)>,
+        count: Option<usize>,
+        no_ack: bool,
+        block: bool,
+    ) -> Result<ReadOutcome, StoreError> {
+        let now_ms = now_ms();
+        let mut store = self.data.lock().unwrap();
+        for (key, _) in &streams {
+            stream_with_group(&mut store, key, group)?;
+        }
+
+        let mut ready = Vec::new();
+        for (key, id) in &streams {
+            let stream = stream_with_group(&mut store, key, group)?;
+            match id {
+                None => {
+                    let entries =
+                        deliver_new(stream, group, consumer, count, no_ack, now_ms).unwrap();
+                    if !entries.is_empty() {
+                        ready.push((key.clone(), entries));
+                    }
+                }
+                // History is replied even when there is none left
+                Some(after) => {
+                    stream.group_mut(group).unwrap().touch(consumer, now_ms);
+                    let entries = pending_history(stream, group, consumer, *after, count);
+                    ready.push((key.clone(), entries.unwrap()));
+                }
+            }
+        }
+        if !ready.is_empty() || !block {
+            return Ok(ReadOutcome::Ready(ready));
+        }
+
+        let keys = streams.into_iter().map(|(key, _)| key).collect();
+        let action = BlockAction::ReadGroup {
+            group: group.to_string(),
+            consumer: consumer.to_string(),
+            count,
+            no_ack,
+        };
+        Ok(ReadOutcome::Blocked(store.blocked.add(keys, action)))
+    }
+
+    /// XACK: removes `ids` from the group's pending entries list, returning
+    /// how many were pending. A missing stream or group acknowledges nothing.
+    pub fn xack(&self, key: &str, group: &str, ids: &[StreamId]) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(group) = stream_mut(&mut store, key)?.and_then(|stream| stream.group_mut(group))
+        else {
+            return Ok(0);
+        };
+        Ok(ids
+            .iter()
+            .filter(|id| group.pending.remove(id).is_some())
+            .count())
+    }
+
+    /// XPENDING: up to `count` pending entries with IDs in `range`, idle
+    /// for at least `min_idle_ms`, optionally only `consumer`'s.
+    pub fn xpending(
+        &self,
+        key: &str,
+        group: &str,
+        range: (Bound<StreamId>, Bound<StreamId>),
+        count: usize,
+        consumer: Option<&str>,
+        min_idle_ms: u64,
+    ) -> Result<Vec<PendingInfo>, StoreError> {
+        let now_ms = now_ms();
+        let mut store = self.data.lock().unwrap();
+        let group = stream_with_group(&mut store, key, group)?
+            .group(group)
+            .unwrap();
+        Ok(group
+            .pending
+            .range((range.0, Bound::Unbounded))
+            .take_while(|(id, _)| range.contains(*id))
+            .filter(|(_, pending)| consumer.is_none_or(|consumer| pending.consumer == consumer))
+            .map(|(id, pending)| PendingInfo {
+                id: *id,
+                consumer: pending.consumer.clone(),
+                idle_ms: now_ms.saturating_sub(pending.delivered_ms),
+                deliveries: pending.deliveries,
+            })
+            .filter(|info| info.idle_ms >= min_idle_ms)
+            .take(count)
+            .collect())
+    }
+
+    /// XCLAIM: makes `consumer` the owner of those of `ids` that have been
+    /// pending for at least `min_idle_ms`, and returns their entries.
+    /// Pending entries since trimmed from the stream are dropped instead.
+    pub fn xclaim(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle_ms: u64,
+        ids: &[StreamId],
+        options: XClaimOptions,
+    ) -> Result<Vec<StreamEntry>, StoreError> {
+        let now_ms = now_ms();
+        let delivered_ms = match (options.time_ms, options.idle_ms) {
+            (Some(time_ms), _) => time_ms,
+            (None, Some(idle_ms)) => now_ms.saturating_sub(idle_ms),
+            (None, None) => now_ms,
+        };
+        let group_name = group;
+        let mut store = self.data.lock().unwrap();
+        let stream = stream_with_group(&mut store, key, group_name)?;
+        if let Some(last_id) = options.last_id {
+            let group = stream.group_mut(group_name).unwrap();
+            group.last_delivered = group.last_delivered.max(last_id);
+        }
+
+        let mut claimed = Vec::new();
+        for &id in ids {
+            let entry = stream.get(id);
+            let group = stream.group_mut(group_name).unwrap();
+            let Some(entry) = entry else {
+                group.pending.remove(&id);
+                continue;
+            };
+            if options.force && !group.pending.contains_key(&id) {
+                group.pending.insert(
+                    id,
+                    PendingEntry {
+                        consumer: consumer.to_string(),
+                        delivered_ms: 0,
+                        deliveries: 0,
+                    },
+                );
+            }
+            let Some(pending) = group.pending.get_mut(&id) else {
+                continue;
+            };
+            if now_ms.saturating_sub(pending.delivered_ms) < min_idle_ms {
+                continue;
+            }
+            pending.consumer = consumer.to_string();
+            pending.delivered_ms = delivered_ms;
+            match options.retry_count {
+                Some(retry_count) => pending.deliveries = retry_count,
+                None if !options.just_id => pending.deliveries += 1,
+                None => {}
+            }
+            claimed.push(entry);
+        }
+        stream
+            .group_mut(group_name)
+            .unwrap()
+            .touch(consumer, now_ms);
+        Ok(claimed)
+    }
+
+    /// XAUTOCLAIM: scans the pending entries list from `start`, claiming
+    /// for `consumer` up to `count` entries idle for at least `min_idle_ms`
+    /// as XCLAIM would. Gives up after looking at ten times `count` entries.
+    #[allow(clippy::too_many_arguments)]
+    pub fn xautoclaim(
+        &self,
+        key: &str,
+        group: &str,
+        consumer: &str,
+        min_idle_ms: u64,
+        start: StreamId,
+        count: usize,
+        just_id: bool,
+    ) -> Result<AutoClaim, StoreError> {
+        let now_ms = now_ms();
+        let group_name = group;
+        let mut store = self.data.lock().unwrap();
+        let stream = stream_with_group(&mut store, key, group_name)?;
+        let attempts = count.saturating_mul(10);
+        // One past the attempts, to know where the next call resumes
+        let candidates: Vec<(StreamId, u64)> = stream
+            .group(group_name)
+            .unwrap()
+            .pending
+            .range(start..)
+            .take(attempts.saturating_add(1))
+            .map(|(id, pending)| (*id, pending.delivered_ms))
+            .collect();
+
+        let mut claim = AutoClaim {
+            next: StreamId::MIN,
+            claimed: Vec::new(),
+            deleted: Vec::new(),
+        };
+        for (i, (id, delivered_ms)) in candidates.into_iter().enumerate() {
+            if i == attempts || claim.claimed.len() == count {
+                claim.next = id;
+                break;
+            }
+            let entry = stream.get(id);
+            let group = stream.group_mut(group_name).unwrap();
+            let Some(entry) = entry else {
+                group.pending.remove(&id);
+                claim.deleted.push(id);
+                continue;
+            };
+            if now_ms.saturating_sub(delivered_ms) < min_idle_ms {
+                continue;
+            }
+            let pending = group.pending.get_mut(&id).unwrap();
+            pending.consumer = consumer.to_string();
+            pending.delivered_ms = now_ms;
+            if !just_id {
+                pending.deliveries += 1;
+            }
+            claim.claimed.push(entry);
+        }
+        stream
+            .group_mut(group_name)
+            .unwrap()
+            .touch(consumer, now_ms);
+        Ok(claim)
+    }
+}
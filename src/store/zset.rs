@@ -0,0 +1,453 @@
+use super::list::resolve_range;
+use super::RedisStore;
+use crate::keyspace::Keyspace;
+use crate::types::{
+    Aggregate, LexBound, RedisData, RedisValue, Score, SetOp, SortedSet, StoreError, ZAddOptions,
+    ZRangeBy,
+};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Bound;
+
+/// Sorted sets at or under both limits report the compact `listpack` encoding.
+const ZSET_MAX_LISTPACK_ENTRIES: usize = 128;
+const ZSET_MAX_LISTPACK_VALUE: usize = 64;
+
+/// The live sorted set at `key`, or `WrongType` if the key holds something
+/// else.
+fn zset_mut<'a>(
+    store: &'a mut Keyspace,
+    key: &str,
+) -> Result<Option<&'a mut SortedSet>, StoreError> {
+    match store.get_live_mut(key) {
+        Some(RedisValue {
+            data: RedisData::SortedSet(zset),
+            ..
+        }) => Ok(Some(zset)),
+        Some(_) => Err(StoreError::WrongType),
+        None => Ok(None),
+    }
+}
+
+/// Stores `zset` at `key`, or deletes the key if it is empty.
+fn store_or_remove(store: &mut Keyspace, key: String, zset: SortedSet) {
+    if zset.is_empty() {
+        store.remove(&key);
+    } else {
+        store.insert(
+            key,
+            RedisValue {
+                data: RedisData::SortedSet(zset),
+                expires_at: None,
+            },
+        );
+    }
+}
+
+fn below_max_score(score: f64, max: Bound<f64>) -> bool {
+    match max {
+        Bound::Included(max) => score <= max,
+        Bound::Excluded(max) => score < max,
+        Bound::Unbounded => true,
+    }
+}
+
+fn above_min_lex(member: &[u8], min: &LexBound) -> bool {
+    match min {
+        LexBound::Min => true,
+        LexBound::Max => false,
+        LexBound::Included(min) => member >= min.as_slice(),
+        LexBound::Excluded(min) => member > min.as_slice(),
+    }
+}
+
+fn below_max_lex(member: &[u8], max: &LexBound) -> bool {
+    match max {
+        LexBound::Min => false,
+        LexBound::Max => true,
+        LexBound::Included(max) => member <= max.as_slice(),
+        LexBound::Excluded(max) => member < max.as_slice(),
+    }
+}
+
+/// Reads the member scores at `key` for ZUNIONSTORE and friends. Plain sets
+/// are accepted too, every member scoring 1.
+fn source_scores(store: &mut Keyspace, key: &str) -> Result<HashMap<Vec<u8>, f64>, StoreError> {
+    match store.get_live(key) {
+        Some(RedisValue {
+            data: RedisData::SortedSet(zset),
+            ..
+        }) => Ok(zset
+            .iter()
+            .map(|(member, score)| (member.clone(), score))
+            .collect()),
+        Some(RedisValue {
+            data: RedisData::Set(members),
+            ..
+        }) => Ok(members.iter().map(|member| (member.clone(), 1.0)).collect()),
+        Some(_) => Err(StoreError::WrongType),
+        None => Ok(HashMap::new()),
+    }
+}
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl SortedSet {
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    pub fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    /// Sets the score of `member`, returning whether it is new.
+    pub fn insert(&mut self, member: Vec<u8>, score: f64) -> bool {
+        // -0.0 and 0.0 must sort as one score
+        let score = score + 0.0;
+        let new = match self.scores.insert(member.clone(), score) {
+            Some(old) => {
+                self.ordered.remove(&(Score(old), member.clone()));
+                false
+            }
+            None => true,
+        };
+        self.ordered.insert((Score(score), member));
+        new
+    }
+
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => {
+                self.ordered.remove(&(Score(score), member.to_vec()));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Members and scores in ascending order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&Vec<u8>, f64)> + ExactSizeIterator {
+        self.ordered.iter().map(|(score, member)| (member, score.0))
+    }
+
+    /// Members scoring from `min` upwards, in ascending order; callers stop
+    /// once past their upper bound.
+    pub fn iter_from(&self, min: Bound<f64>) -> impl Iterator<Item = (&Vec<u8>, f64)> {
+        let start = match min {
+            Bound::Included(min) | Bound::Excluded(min) => min,
+            Bound::Unbounded => f64::NEG_INFINITY,
+        };
+        self.ordered
+            .range((Score(start), Vec::new())..)
+            .map(|(score, member)| (member, score.0))
+            .skip_while(move |(_, score)| matches!(min, Bound::Excluded(min) if *score <= min))
+    }
+
+    /// Zero-based position of `member` in ascending order.
+    pub fn rank(&self, member: &[u8]) -> Option<usize> {
+        let score = self.score(member)?;
+        Some(
+            self.ordered
+                .range(..(Score(score), member.to_vec()))
+                .count(),
+        )
+    }
+
+    /// Encoding reported by OBJECT ENCODING.
+    pub(crate) fn encoding(&self) -> &'static str {
+        let small = self.len() <= ZSET_MAX_LISTPACK_ENTRIES
+            && self.iter().all(|(m, _)| m.len() <= ZSET_MAX_LISTPACK_VALUE);
+        if small {
+            "listpack"
+        } else {
+            "skiplist"
+        }
+    }
+}
+
+impl RedisStore {
+    /// ZADD: sets the score of each member where `options` allow, creating
+    /// the sorted set if needed. Returns how many members were added, or
+    /// with CH how many were added or had their score changed.
+    pub fn zadd(
+        &self,
+        key: String,
+        entries: Vec<(f64, Vec<u8>)>,
+        options: ZAddOptions,
+    ) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let mut created = None;
+        let zset = match zset_mut(&mut store, &key)? {
+            Some(zset) => zset,
+            None => created.insert(SortedSet::default()),
+        };
+
+        let mut counted = 0;
+        for (score, member) in entries {
+            let current = zset.score(&member);
+            if !options.allows(current, score) {
+                continue;
+            }
+            if current.is_none() || (options.count_changed && current != Some(score)) {
+                counted += 1;
+            }
+            zset.insert(member, score);
+        }
+        if let Some(zset) = created {
+            store_or_remove(&mut store, key, zset);
+        }
+        Ok(counted)
+    }
+
+    /// ZINCRBY, and ZADD with INCR: adds `delta` to the score of `member`,
+    /// a missing member counting as 0. Returns the new score, or `None` if
+    /// `options` did not allow the update.
+    pub fn zincr_by(
+        &self,
+        key: String,
+        member: Vec<u8>,
+        delta: f64,
+        options: ZAddOptions,
+    ) -> Result<Option<f64>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let current = zset_mut(&mut store, &key)?.and_then(|zset| zset.score(&member));
+        let score = current.unwrap_or(0.0) + delta;
+        if score.is_nan() {
+            return Err(StoreError::ScoreNaN);
+        }
+        if !options.allows(current, score) {
+            return Ok(None);
+        }
+        match zset_mut(&mut store, &key)? {
+            Some(zset) => {
+                zset.insert(member, score);
+            }
+            None => {
+                let mut zset = SortedSet::default();
+                zset.insert(member, score);
+                store_or_remove(&mut store, key, zset);
+            }
+        }
+        Ok(Some(score))
+    }
+
+    /// ZREM: removes `members`, deleting the key once none are left, and
+    /// returns how many existed.
+    pub fn zrem(&self, key: &str, members: &[Vec<u8>]) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(zset) = zset_mut(&mut store, key)? else {
+            return Ok(0);
+        };
+        let removed = members.iter().filter(|member| zset.remove(member)).count();
+        if zset.is_empty() {
+            store.remove(key);
+        }
+        Ok(removed)
+    }
+
+    /// ZSCORE: the score of `member`.
+    pub fn zscore(&self, key: &str, member: &[u8]) -> Result<Option<f64>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(zset_mut(&mut store, key)?.and_then(|zset| zset.score(member)))
+    }
+
+    /// ZCARD: the number of members.
+    pub fn zcard(&self, key: &str) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(zset_mut(&mut store, key)?.map_or(0, |zset| zset.len()))
+    }
+
+    /// ZRANK and ZREVRANK: the rank of `member` counting from the lowest
+    /// score (or the highest with `rev`), with its score.
+    pub fn zrank(
+        &self,
+        key: &str,
+        member: &[u8],
+        rev: bool,
+    ) -> Result<Option<(usize, f64)>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(zset_mut(&mut store, key)?.and_then(|zset| {
+            let rank = zset.rank(member)?;
+            let rank = if rev { zset.len() - 1 - rank } else { rank };
+            Some((rank, zset.score(member)?))
+        }))
+    }
+
+    /// ZCOUNT: the number of members scoring between `min` and `max`.
+    pub fn zcount(&self, key: &str, min: Bound<f64>, max: Bound<f64>) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        Ok(zset_mut(&mut store, key)?.map_or(0, |zset| {
+            zset.iter_from(min)
+                .take_while(|(_, score)| below_max_score(*score, max))
+                .count()
+        }))
+    }
+
+    /// ZRANGE: the members selected by `by`, in ascending order or
+    /// descending with `rev`, then windowed by an `(offset, count)` limit
+    /// where a negative count means no limit.
+    pub fn zrange(
+        &self,
+        key: &str,
+        by: &ZRangeBy,
+        rev: bool,
+        limit: Option<(i64, i64)>,
+    ) -> Result<Vec<(Vec<u8>, f64)>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(zset) = zset_mut(&mut store, key)? else {
+            return Ok(Vec::new());
+        };
+
+        let mut selected: Vec<(&Vec<u8>, f64)> = match by {
+            ZRangeBy::Index(start, stop) => {
+                let Some((start, stop)) = resolve_range(zset.len(), *start, *stop) else {
+                    return Ok(Vec::new());
+                };
+                let window = stop - start + 1;
+                // Ranks count from the highest score when reversed
+                return Ok(if rev {
+                    zset.iter()
+                        .rev()
+                        .skip(start)
+                        .take(window)
+                        .map(owned)
+                        .collect()
+                } else {
+                    zset.iter().skip(start).take(window).map(owned).collect()
+                });
+            }
+            ZRangeBy::Score(min, max) => zset
+                .iter_from(*min)
+                .take_while(|(_, score)| below_max_score(*score, *max))
+                .collect(),
+            ZRangeBy::Lex(min, max) => zset
+                .iter()
+                .skip_while(|(member, _)| !above_min_lex(member, min))
+                .take_while(|(member, _)| below_max_lex(member, max))
+                .collect(),
+        };
+        if rev {
+            selected.reverse();
+        }
+
+        let (offset, count) = limit.unwrap_or((0, -1));
+        if offset < 0 {
+            return Ok(Vec::new());
+        }
+        let count = usize::try_from(count).unwrap_or(usize::MAX);
+        Ok(selected
+            .into_iter()
+            .skip(offset as usize)
+            .take(count)
+            .map(owned)
+            .collect())
+    }
+
+    /// ZPOPMIN and ZPOPMAX: removes and returns up to `count` of the lowest
+    /// scoring members, or the highest with `rev`.
+    pub fn zpop(
+        &self,
+        key: &str,
+        rev: bool,
+        count: usize,
+    ) -> Result<Vec<(Vec<u8>, f64)>, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let Some(zset) = zset_mut(&mut store, key)? else {
+            return Ok(Vec::new());
+        };
+        let popped: Vec<(Vec<u8>, f64)> = if rev {
+            zset.iter().rev().take(count).map(owned).collect()
+        } else {
+            zset.iter().take(count).map(owned).collect()
+        };
+        for (member, _) in &popped {
+            zset.remove(member);
+        }
+        if zset.is_empty() {
+            store.remove(key);
+        }
+        Ok(popped)
+    }
+
+    /// ZUNIONSTORE and ZINTERSTORE: combines the sorted sets (or sets) at
+    /// `keys` with `op`, each source's scores scaled by its weight, and
+    /// writes the result to `destination`, replacing whatever it held.
+    /// Returns the result's size; an empty result deletes `destination`.
+    pub fn zcombine_store(
+        &self,
+        destination: String,
+        keys: &[String],
+        weights: &[f64],
+        aggregate: Aggregate,
+        op: SetOp,
+    ) -> Result<usize, StoreError> {
+        let mut store = self.data.lock().unwrap();
+        let mut sources = Vec::with_capacity(keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            let weight = weights.get(i).copied().unwrap_or(1.0);
+            let mut scores = source_scores(&mut store, key)?;
+            for score in scores.values_mut() {
+                // 0 * inf is taken as 0
+                *score = match *score * weight {
+                    weighted if weighted.is_nan() => 0.0,
+                    weighted => weighted,
+                };
+            }
+            sources.push(scores);
+        }
+
+        let mut sources = sources.into_iter();
+        let mut result = sources.next().unwrap_or_default();
+        for source in sources {
+            match op {
+                SetOp::Union => {
+                    for (member, score) in source {
+                        result
+                            .entry(member)
+                            .and_modify(|current| *current = aggregate.apply(*current, score))
+                            .or_insert(score);
+                    }
+                }
+                SetOp::Inter => {
+                    result.retain(|member, current| match source.get(member) {
+                        Some(score) => {
+                            *current = aggregate.apply(*current, *score);
+                            true
+                        }
+                        None => false,
+                    });
+                }
+                SetOp::Diff => result.retain(|member, _| !source.contains_key(member)),
+            }
+        }
+
+        let mut zset = SortedSet::default();
+        for (member, score) in result {
+            zset.insert(member, score);
+        }
+        let len = zset.len();
+        store_or_remove(&mut store, destination, zset);
+        Ok(len)
+    }
+}
+
+fn owned((member, score): (&Vec<u8>, f64)) -> (Vec<u8>, f64) {
+    (member.clone(), score)
+}
@@ -0,0 +1,17 @@
+use crate::store::RedisStore;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Background task evicting expired keys that are never read again.
+///
+/// Runs `hz` active-expire cycles per second, each allowed a quarter of the
+/// period before yielding back to request handling.
+pub async fn run_active_expire(store: Arc<RedisStore>, hz: u64) {
+    let period = Duration::from_millis(1000 / hz.max(1));
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+        store.active_expire_cycle(period / 4);
+    }
+}
@@ -1,26 +1,599 @@
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::ops::Bound;
 use std::time::SystemTime;
+use tokio::sync::oneshot;
+
+/// Typed payload of a stored value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RedisData {
+    /// Exact bytes as sent by the client, which need not be UTF-8.
+    String(Vec<u8>),
+    /// JSON array built by APPEND, kept parsed between writes and searches.
+    Json(Value),
+    List(VecDeque<Vec<u8>>),
+    Hash(RedisHash),
+    Set(HashSet<Vec<u8>>),
+    SortedSet(SortedSet),
+    Stream(Stream),
+}
+
+/// Field/value pairs of a hash or stream entry.
+pub type FieldValues = Vec<(Vec<u8>, Vec<u8>)>;
+
+/// A hash's fields, kept in insertion order so HSCAN has a stable cursor,
+/// with optional per-field expiry.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RedisHash {
+    // field -> (value, sequence); `order` maps sequence back to field
+    pub(crate) fields: HashMap<Vec<u8>, (Vec<u8>, u64)>,
+    pub(crate) order: BTreeMap<u64, Vec<u8>>,
+    pub(crate) next_sequence: u64,
+    pub(crate) expires: HashMap<Vec<u8>, SystemTime>,
+}
+
+/// A score with a total order, so it can key a `BTreeSet`. NaN is never
+/// stored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Score(pub(crate) f64);
+
+/// A sorted set's members, ordered by score and then by member.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SortedSet {
+    pub(crate) scores: HashMap<Vec<u8>, f64>,
+    pub(crate) ordered: BTreeSet<(Score, Vec<u8>)>,
+}
+
+/// A stream entry ID: milliseconds and a sequence number within them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct StreamId {
+    pub ms: u64,
+    pub seq: u64,
+}
+
+impl StreamId {
+    pub const MIN: StreamId = StreamId { ms: 0, seq: 0 };
+    pub const MAX: StreamId = StreamId {
+        ms: u64::MAX,
+        seq: u64::MAX,
+    };
+
+    pub fn new(ms: u64, seq: u64) -> Self {
+        StreamId { ms, seq }
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.ms, self.seq)
+    }
+}
+
+/// The ID argument of XADD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XAddId {
+    /// `*`: generated from the clock.
+    Auto,
+    /// `ms-*`: the given milliseconds with a generated sequence number.
+    AutoSeq(u64),
+    Explicit(StreamId),
+}
+
+/// An XADD/XTRIM trimming threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrimThreshold {
+    /// MAXLEN: keep at most this many entries.
+    MaxLen(usize),
+    /// MINID: drop entries with a lower ID.
+    MinId(StreamId),
+}
+
+/// How to trim a stream: a threshold, and with `~` an optional cap on how
+/// many entries one call may evict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamTrim {
+    pub threshold: TrimThreshold,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamEntry {
+    pub id: StreamId,
+    pub fields: FieldValues,
+}
+
+/// An append-only log of entries keyed by increasing IDs.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stream {
+    pub(crate) entries: BTreeMap<StreamId, FieldValues>,
+    // Survives trimming, so IDs never go backwards
+    pub(crate) last_id: StreamId,
+    pub(crate) groups: BTreeMap<String, ConsumerGroup>,
+}
+
+/// A delivered entry awaiting XACK.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingEntry {
+    pub consumer: String,
+    /// When it was last delivered, in milliseconds since the epoch.
+    pub delivered_ms: u64,
+    pub deliveries: u64,
+}
+
+/// A consumer group: how far it has read, and what its consumers were
+/// handed but have not acknowledged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsumerGroup {
+    pub last_delivered: StreamId,
+    /// The pending entries list, by entry ID.
+    pub pending: BTreeMap<StreamId, PendingEntry>,
+    /// Consumers by name, with when each was last seen in milliseconds
+    /// since the epoch.
+    pub consumers: BTreeMap<String, u64>,
+}
+
+/// A pending entry as reported by XPENDING.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingInfo {
+    pub id: StreamId,
+    pub consumer: String,
+    pub idle_ms: u64,
+    pub deliveries: u64,
+}
+
+/// IDLE/TIME/RETRYCOUNT/FORCE/JUSTID/LASTID options for XCLAIM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct XClaimOptions {
+    /// IDLE: claimed entries look this many milliseconds idle.
+    pub idle_ms: Option<u64>,
+    /// TIME: claimed entries look delivered at this Unix time in
+    /// milliseconds.
+    pub time_ms: Option<u64>,
+    /// RETRYCOUNT: the delivery count to set instead of incrementing it.
+    pub retry_count: Option<u64>,
+    /// FORCE: claim entries that are in the stream but not pending.
+    pub force: bool,
+    /// JUSTID: leave delivery counts alone; only IDs are replied.
+    pub just_id: bool,
+    /// LASTID: moves the group's last delivered ID forward.
+    pub last_id: Option<StreamId>,
+}
+
+/// What one XAUTOCLAIM call did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoClaim {
+    /// Where the next call should resume the scan, 0-0 once it is done.
+    pub next: StreamId,
+    pub claimed: Vec<StreamEntry>,
+    /// Pending entries found trimmed from the stream, now dropped.
+    pub deleted: Vec<StreamId>,
+}
 
 #[derive(Clone)]
 pub struct RedisValue {
-    pub data: String,
+    pub data: RedisData,
     pub expires_at: Option<SystemTime>,
 }
 
+impl RedisValue {
+    /// A string value with no TTL.
+    pub fn string(data: impl Into<Vec<u8>>) -> Self {
+        RedisValue {
+            data: RedisData::String(data.into()),
+            expires_at: None,
+        }
+    }
+
+    /// Name reported by TYPE and used by SCAN's TYPE filter.
+    pub fn type_name(&self) -> &'static str {
+        match self.data {
+            RedisData::String(_) => "string",
+            RedisData::Json(_) => "ReJSON-RL",
+            RedisData::List(_) => "list",
+            RedisData::Hash(_) => "hash",
+            RedisData::Set(_) => "set",
+            RedisData::SortedSet(_) => "zset",
+            RedisData::Stream(_) => "stream",
+        }
+    }
+
+    /// Rough number of heap bytes held, used to decide whether freeing the
+    /// value is worth handing to the lazy-free thread.
+    pub fn size(&self) -> usize {
+        fn json_size(value: &Value) -> usize {
+            std::mem::size_of::<Value>()
+                + match value {
+                    Value::String(s) => s.len(),
+                    Value::Array(items) => items.iter().map(json_size).sum(),
+                    Value::Object(map) => map.iter().map(|(k, v)| k.len() + json_size(v)).sum(),
+                    _ => 0,
+                }
+        }
+        match &self.data {
+            RedisData::String(s) => s.len(),
+            RedisData::Json(value) => json_size(value),
+            RedisData::List(items) => items.iter().map(|s| s.len() + 16).sum(),
+            RedisData::Hash(hash) => hash.iter().map(|(k, v)| k.len() + v.len() + 32).sum(),
+            RedisData::Set(members) => members.iter().map(|m| m.len() + 16).sum(),
+            RedisData::SortedSet(zset) => zset.iter().map(|(m, _)| 2 * m.len() + 48).sum(),
+            RedisData::Stream(stream) => stream
+                .range(Bound::Unbounded, Bound::Unbounded)
+                .map(|entry| {
+                    entry
+                        .fields
+                        .iter()
+                        .map(|(k, v)| k.len() + v.len() + 16)
+                        .sum::<usize>()
+                        + 32
+                })
+                .sum(),
+        }
+    }
+
+    /// The string payload, for commands that only operate on strings.
+    pub fn as_bytes(&self) -> Result<&[u8], StoreError> {
+        match &self.data {
+            RedisData::String(s) => Ok(s),
+            _ => Err(StoreError::WrongType),
+        }
+    }
+
+    /// The value as GET returns it: strings verbatim, JSON serialized.
+    pub fn render(&self) -> Result<Vec<u8>, StoreError> {
+        match &self.data {
+            RedisData::String(s) => Ok(s.clone()),
+            RedisData::Json(value) => Ok(value.to_string().into_bytes()),
+            _ => Err(StoreError::WrongType),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum RedisGetResult {
-    Value(String),
+    Value(Vec<u8>),
     None,
     Expired,
+    WrongType,
+}
+
+/// Counters maintained by lazy and active expiration, reported by INFO.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExpireStats {
+    /// Keys removed because their TTL elapsed.
+    pub expired_keys: u64,
+    /// Hash fields removed because their TTL elapsed.
+    pub expired_subkeys: u64,
+    /// Share of sampled keys found expired in the last active cycle.
+    pub expired_stale_perc: f64,
+    /// Active cycles that stopped because they used up their time budget.
+    pub expired_time_cap_reached_count: u64,
+}
+
+/// Errors reported by `RedisStore` operations, rendered as RESP error messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreError {
+    NoSuchKey,
+    SameObject,
+    NotInteger,
+    NotFloat,
+    Overflow,
+    NanOrInfinity,
+    StringTooLong,
+    WrongType,
+    IndexOutOfRange,
+    HashNotInteger,
+    ScoreNaN,
+    StreamIdTooSmall,
+    StreamIdZero,
+    NoGroup,
+    BusyGroup,
+    GroupKeyMissing,
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::NoSuchKey => write!(f, "ERR no such key"),
+            StoreError::SameObject => write!(f, "ERR source and destination objects are the same"),
+            StoreError::NotInteger => write!(f, "ERR value is not an integer or out of range"),
+            StoreError::NotFloat => write!(f, "ERR value is not a valid float"),
+            StoreError::Overflow => write!(f, "ERR increment or decrement would overflow"),
+            StoreError::NanOrInfinity => {
+                write!(f, "ERR increment would produce NaN or Infinity")
+            }
+            StoreError::StringTooLong => write!(
+                f,
+                "ERR string exceeds maximum allowed size (proto-max-bulk-len)"
+            ),
+            StoreError::IndexOutOfRange => write!(f, "ERR index out of range"),
+            StoreError::HashNotInteger => write!(f, "ERR hash value is not an integer"),
+            StoreError::ScoreNaN => write!(f, "ERR resulting score is not a number (NaN)"),
+            StoreError::StreamIdTooSmall => write!(
+                f,
+                "ERR The ID specified in XADD is equal or smaller than the target stream top item"
+            ),
+            StoreError::StreamIdZero => {
+                write!(f, "ERR The ID specified in XADD must be greater than 0-0")
+            }
+            StoreError::NoGroup => write!(f, "NOGROUP No such key or consumer group"),
+            StoreError::BusyGroup => write!(f, "BUSYGROUP Consumer Group name already exists"),
+            StoreError::GroupKeyMissing => write!(
+                f,
+                "ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically."
+            ),
+            StoreError::WrongType => write!(
+                f,
+                "WRONGTYPE Operation against a key holding the wrong kind of value"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+/// When a SET is allowed to write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SetCondition {
+    #[default]
+    Always,
+    /// NX: only if the key does not exist.
+    IfNotExists,
+    /// XX: only if the key already exists.
+    IfExists,
+}
+
+/// What a SET does with the key's time to live.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SetExpiry {
+    /// Drop any existing TTL.
+    #[default]
+    Persist,
+    /// KEEPTTL: retain the existing TTL.
+    Keep,
+    /// EX/PX/EXAT/PXAT: expire at the given instant.
+    At(SystemTime),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SetOptions {
+    pub condition: SetCondition,
+    pub expiry: SetExpiry,
+    /// GET: report the previous value.
+    pub get: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SetOutcome {
+    /// Whether the value was written.
+    pub written: bool,
+    /// The live value held before the call, if any.
+    pub previous: Option<Vec<u8>>,
+}
+
+/// How SINTER, SUNION and SDIFF combine their input sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOp {
+    Inter,
+    Union,
+    Diff,
+}
+
+/// How ZUNIONSTORE and ZINTERSTORE merge the scores of a shared member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregate {
+    /// Merges two scores; an undefined sum (`inf + -inf`) counts as 0.
+    pub fn apply(self, a: f64, b: f64) -> f64 {
+        match self {
+            Aggregate::Sum if (a + b).is_nan() => 0.0,
+            Aggregate::Sum => a + b,
+            Aggregate::Min => a.min(b),
+            Aggregate::Max => a.max(b),
+        }
+    }
+}
+
+/// One end of a ZRANGE BYLEX interval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LexBound {
+    /// `-`: before every member.
+    Min,
+    /// `+`: after every member.
+    Max,
+    Included(Vec<u8>),
+    Excluded(Vec<u8>),
+}
+
+/// Which members a ZRANGE selects, before REV and LIMIT apply.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZRangeBy {
+    /// Ranks, negative counting from the end.
+    Index(i64, i64),
+    Score(Bound<f64>, Bound<f64>),
+    Lex(LexBound, LexBound),
+}
+
+/// NX/XX/GT/LT/CH flags for ZADD.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ZAddOptions {
+    /// NX: only add new members.
+    pub only_new: bool,
+    /// XX: only update existing members.
+    pub only_existing: bool,
+    /// GT: only update a score that would grow.
+    pub only_greater: bool,
+    /// LT: only update a score that would shrink.
+    pub only_less: bool,
+    /// CH: count updated members as well as added ones.
+    pub count_changed: bool,
+}
+
+impl ZAddOptions {
+    /// Whether a member currently scored `current` may be set to `score`.
+    pub fn allows(&self, current: Option<f64>, score: f64) -> bool {
+        match current {
+            None => !self.only_existing,
+            Some(_) if self.only_new => false,
+            Some(current) if self.only_greater => score > current,
+            Some(current) if self.only_less => score < current,
+            Some(_) => true,
+        }
+    }
+}
+
+/// End of a list to push onto or pop from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListEnd {
+    Left,
+    Right,
+}
+
+/// What a blocked client does once one of its keys has data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockAction {
+    /// BLPOP/BRPOP: pop from this end of the first non-empty key.
+    Pop(ListEnd),
+    /// BLMOVE: move an item from the (single) watched key to `destination`.
+    Move {
+        destination: String,
+        from: ListEnd,
+        to: ListEnd,
+    },
+    /// XREAD: read entries after the given ID of whichever stream grows.
+    Read {
+        after: HashMap<String, StreamId>,
+        count: Option<usize>,
+    },
+    /// XREADGROUP with `>`: deliver entries new to `group` to `consumer`.
+    ReadGroup {
+        group: String,
+        consumer: String,
+        count: Option<usize>,
+        no_ack: bool,
+    },
+}
+
+/// An item popped for a blocking command, with the key it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Popped {
+    pub key: String,
+    pub value: Vec<u8>,
+}
+
+/// What a blocked client is handed once served.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Served {
+    /// A list item, for BLPOP, BRPOP and BLMOVE.
+    Item(Popped),
+    /// New entries of one stream, for XREAD and XREADGROUP.
+    Entries {
+        key: String,
+        entries: Vec<StreamEntry>,
+    },
+}
+
+/// A blocked client's place in line. What it is served arrives on
+/// `receiver`; `id` releases the place if the client gives up waiting.
+#[derive(Debug)]
+pub struct BlockTicket {
+    pub id: u64,
+    pub receiver: oneshot::Receiver<Result<Served, StoreError>>,
+    /// For BLPOP and BRPOP, the end a served item goes back on if the
+    /// client disconnects before it is delivered. BLMOVE needs none: its
+    /// item already sits in the destination list.
+    pub requeue: Option<ListEnd>,
+}
+
+/// Result of a blocking pop attempt.
+#[derive(Debug)]
+pub enum PopOutcome {
+    /// An item was available straight away.
+    Ready(Popped),
+    /// Nothing was available; the client is now queued on its keys.
+    Blocked(BlockTicket),
+}
+
+/// Result of an XREAD or XREADGROUP, which may block.
+#[derive(Debug)]
+pub enum ReadOutcome {
+    /// Entries per stream that had any; empty if none did and the read
+    /// was not blocking.
+    Ready(Vec<(String, Vec<StreamEntry>)>),
+    Blocked(BlockTicket),
+}
+
+/// NX/XX: what TTL the key must already have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TtlRequirement {
+    /// NX: only if the key has no TTL.
+    NoTtl,
+    /// XX: only if the key already has a TTL.
+    HasTtl,
+}
+
+/// GT/LT: how the new expiry must compare to the current one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpiryComparison {
+    /// GT: only if the new expiry is later than the current one.
+    Greater,
+    /// LT: only if the new expiry is earlier than the current one.
+    Less,
+}
+
+/// NX/XX/GT/LT condition for EXPIRE and friends. The default always
+/// applies; otherwise both parts that are set must hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExpireCondition {
+    pub require: Option<TtlRequirement>,
+    pub compare: Option<ExpiryComparison>,
+}
+
+impl ExpireCondition {
+    /// Whether an expiry currently at `current` may be moved to `at`. For
+    /// GT/LT, no TTL behaves as if it expires infinitely far away.
+    pub fn allows(self, current: Option<SystemTime>, at: SystemTime) -> bool {
+        let required = match self.require {
+            None => true,
+            Some(TtlRequirement::NoTtl) => current.is_none(),
+            Some(TtlRequirement::HasTtl) => current.is_some(),
+        };
+        let compared = match self.compare {
+            None => true,
+            Some(ExpiryComparison::Greater) => current.is_some_and(|c| at > c),
+            Some(ExpiryComparison::Less) => current.is_none_or(|c| at < c),
+        };
+        required && compared
+    }
+}
+
+/// Time-to-live state of a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TtlState {
+    Missing,
+    Persistent,
+    ExpiresAt(SystemTime),
 }
 
 #[derive(Debug)]
 pub enum ParserState {
     Start,
-    ReadingArrayLength(String),
-    ExpectingBulkString,
-    ReadingBulkLength(String),
+    ReadingArrayLength,
+    ExpectingBulkString {
+        remaining: usize,
+    },
+    ReadingBulkLength {
+        remaining: usize,
+    },
     ReadingBulkContent {
-        expected_length: i32,
-        current_content: String,
+        remaining: usize,
+        expected_length: usize,
     },
+    Done,
 }
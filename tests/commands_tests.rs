@@ -0,0 +1,85 @@
+use redis_test_simple::commands::{registry, CommandFlags, KeySpec};
+
+#[test]
+fn test_registry_lookup_is_case_insensitive() {
+    let command = registry().get(b"get").expect("GET should be registered");
+    assert_eq!(command.name(), "GET");
+    assert!(registry().get(b"nosuchcommand").is_none());
+}
+
+#[test]
+fn test_arity_checks() {
+    let get = registry().get(b"GET").unwrap();
+    assert!(get.check_arity(2));
+    assert!(!get.check_arity(1));
+    assert!(!get.check_arity(3));
+
+    let set = registry().get(b"SET").unwrap();
+    assert!(!set.check_arity(2));
+    assert!(set.check_arity(3));
+    assert!(set.check_arity(5));
+}
+
+#[test]
+fn test_flags() {
+    assert!(registry()
+        .get(b"SET")
+        .unwrap()
+        .flags()
+        .contains(CommandFlags::WRITE));
+    assert!(registry().get(b"GET").unwrap().is_keyspace());
+    assert!(!registry().get(b"PING").unwrap().is_keyspace());
+}
+
+#[test]
+fn test_key_spec_positions() {
+    assert_eq!(KeySpec::FIRST.positions(3), vec![1]);
+    assert_eq!(KeySpec::NONE.positions(3), Vec::<usize>::new());
+
+    // MSET-style: every other argument from the first
+    let pairs = KeySpec {
+        first: 1,
+        last: -1,
+        step: 2,
+    };
+    assert_eq!(pairs.positions(5), vec![1, 3]);
+
+    // BLPOP-style: all but the trailing timeout
+    let all_but_last = KeySpec {
+        first: 1,
+        last: -2,
+        step: 1,
+    };
+    assert_eq!(all_but_last.positions(4), vec![1, 2]);
+}
+
+#[test]
+fn test_numkeys_key_positions() {
+    let positions = |line: &str| {
+        let args: Vec<Vec<u8>> = line.split(' ').map(|s| s.as_bytes().to_vec()).collect();
+        registry().get(&args[0]).unwrap().key_positions(&args)
+    };
+
+    // The destination and the counted sources, but not WEIGHTS values
+    assert_eq!(positions("ZUNIONSTORE d 2 a b WEIGHTS 1 2"), vec![1, 3, 4]);
+    // A count running past the arguments is capped rather than panicking
+    assert_eq!(positions("ZINTERSTORE d 5 a"), vec![1, 3]);
+    assert_eq!(positions("ZUNIONSTORE d x a"), vec![1]);
+}
+
+#[test]
+fn test_stream_read_key_positions() {
+    let positions = |line: &str| {
+        let args: Vec<Vec<u8>> = line.split(' ').map(|s| s.as_bytes().to_vec()).collect();
+        registry().get(&args[0]).unwrap().key_positions(&args)
+    };
+
+    // The first half of the arguments after STREAMS, never the IDs
+    assert_eq!(positions("XREAD COUNT 2 STREAMS a b 0 $"), vec![4, 5]);
+    assert_eq!(
+        positions("XREADGROUP GROUP g c NOACK BLOCK 0 STREAMS a >"),
+        vec![8]
+    );
+    assert_eq!(positions("XREAD STREAMS a b 0"), Vec::<usize>::new());
+    assert_eq!(positions("XGROUP CREATE a g $"), vec![2]);
+}
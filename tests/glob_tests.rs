@@ -0,0 +1,30 @@
+use redis_test_simple::glob::glob_match;
+
+#[test]
+fn test_wildcards() {
+    assert!(glob_match(b"*", b"anything"));
+    assert!(glob_match(b"user:*", b"user:42"));
+    assert!(!glob_match(b"user:*", b"session:42"));
+    assert!(glob_match(b"h?llo", b"hello"));
+    assert!(!glob_match(b"h?llo", b"hllo"));
+    assert!(glob_match(b"*:*:end", b"a:b:c:end"));
+    assert!(glob_match(b"", b""));
+    assert!(!glob_match(b"", b"a"));
+}
+
+#[test]
+fn test_character_classes() {
+    assert!(glob_match(b"h[ae]llo", b"hallo"));
+    assert!(!glob_match(b"h[ae]llo", b"hillo"));
+    assert!(glob_match(b"h[^e]llo", b"hallo"));
+    assert!(!glob_match(b"h[^e]llo", b"hello"));
+    assert!(glob_match(b"key[0-9]", b"key7"));
+    assert!(!glob_match(b"key[0-9]", b"keyx"));
+}
+
+#[test]
+fn test_escapes() {
+    assert!(glob_match(b"what\\?", b"what?"));
+    assert!(!glob_match(b"what\\?", b"whats"));
+    assert!(glob_match(b"star\\*", b"star*"));
+}
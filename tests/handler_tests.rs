@@ -0,0 +1,1147 @@
+use redis_test_simple::config::Config;
+use redis_test_simple::handler::handle_connection;
+use redis_test_simple::store::RedisStore;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// Starts a server on an ephemeral port and returns its address
+async fn start_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let store = Arc::new(RedisStore::new());
+    let config = Arc::new(Config::default());
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(handle_connection(stream, store.clone(), config.clone()));
+        }
+    });
+
+    addr
+}
+
+// Starts a server and returns a connected client
+async fn connect() -> TcpStream {
+    TcpStream::connect(start_server().await).await.unwrap()
+}
+
+// Connects another client to `addr` under `tenant`
+async fn connect_as(addr: SocketAddr, tenant: &str) -> TcpStream {
+    let mut client = TcpStream::connect(addr).await.unwrap();
+    client
+        .write_all(&frame(&["CLIENT", "SETNAME", tenant]))
+        .await
+        .unwrap();
+    assert_eq!(read_exactly(&mut client, 5).await, "+OK\r\n");
+    client
+}
+
+// Reads until `expected` bytes have arrived
+async fn read_exactly(client: &mut TcpStream, expected: usize) -> String {
+    let mut buf = vec![0; expected];
+    client.read_exact(&mut buf).await.unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+fn frame(args: &[&str]) -> Vec<u8> {
+    let mut out = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        out.extend_from_slice(format!("${}\r\n{}\r\n", arg.len(), arg).as_bytes());
+    }
+    out
+}
+
+#[tokio::test]
+async fn test_pipelined_commands_reply_in_order() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["SET", "a", "1"]));
+    request.extend(frame(&["PING"]));
+    request.extend(frame(&["ECHO", "hi"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = "+OK\r\n+OK\r\n+PONG\r\n$2\r\nhi\r\n";
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_many_pipelined_appends() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    for i in 0..500 {
+        request.extend(frame(&["APPEND", "batch", &i.to_string()]));
+    }
+    client.write_all(&request).await.unwrap();
+
+    let expected = "+OK\r\n".repeat(501);
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_frame_split_across_writes() {
+    let mut client = connect().await;
+
+    let request = frame(&["ECHO", &"x".repeat(100_000)]);
+    let (first, second) = request.split_at(50_000);
+    client.write_all(first).await.unwrap();
+    client.flush().await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    client.write_all(second).await.unwrap();
+
+    let expected = format!("$100000\r\n{}\r\n", "x".repeat(100_000));
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_get_replies_with_bulk_and_null() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["SET", "multi", "line\r\nvalue"]));
+    request.extend(frame(&["GET", "multi"]));
+    request.extend(frame(&["GET", "missing"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = "+OK\r\n+OK\r\n$11\r\nline\r\nvalue\r\n$-1\r\n";
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_binary_values_round_trip() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend_from_slice(b"*3\r\n$3\r\nSET\r\n$4\r\nblob\r\n$3\r\n\xff\xfe\x00\r\n");
+    request.extend(frame(&["GET", "blob"]));
+    request.extend_from_slice(b"*3\r\n$4\r\nMSET\r\n$5\r\nother\r\n$2\r\n\xc3\x28\r\n");
+    request.extend(frame(&["MGET", "blob", "other"]));
+    request.extend_from_slice(b"*3\r\n$6\r\nGETSET\r\n$4\r\nblob\r\n$1\r\n\x80\r\n");
+    request.extend(frame(&["STRLEN", "blob"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected: &[u8] = b"+OK\r\n+OK\r\n$3\r\n\xff\xfe\x00\r\n+OK\r\n\
+        *2\r\n$3\r\n\xff\xfe\x00\r\n$2\r\n\xc3\x28\r\n$3\r\n\xff\xfe\x00\r\n:1\r\n";
+    let mut received = vec![0; expected.len()];
+    client.read_exact(&mut received).await.unwrap();
+    assert_eq!(received, expected);
+}
+
+#[tokio::test]
+async fn test_non_utf8_keys_are_rejected() {
+    let mut client = connect().await;
+
+    // \xff and \xfe would both decode to U+FFFD and name the same key
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend_from_slice(b"*3\r\n$3\r\nSET\r\n$1\r\n\xff\r\n$1\r\na\r\n");
+    request.extend_from_slice(b"*2\r\n$3\r\nGET\r\n$1\r\n\xfe\r\n");
+    request
+        .extend_from_slice(b"*5\r\n$4\r\nMSET\r\n$1\r\nk\r\n$1\r\nv\r\n$1\r\n\xfe\r\n$1\r\nv\r\n");
+    request.extend(frame(&["EXISTS", "k"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "+OK\r\n",
+        "-ERR invalid key: keys must be valid UTF-8\r\n",
+        "-ERR invalid key: keys must be valid UTF-8\r\n",
+        "-ERR invalid key: keys must be valid UTF-8\r\n",
+        ":0\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_setrange_writes_raw_bytes() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["SET", "rec", "abc"]));
+    request.extend_from_slice(b"*4\r\n$8\r\nSETRANGE\r\n$3\r\nrec\r\n$1\r\n1\r\n$1\r\n\xff\r\n");
+    request.extend(frame(&["GET", "rec"]));
+    request.extend(frame(&["STRLEN", "rec"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected: &[u8] = b"+OK\r\n+OK\r\n:3\r\n$3\r\na\xffc\r\n:3\r\n";
+    let mut received = vec![0; expected.len()];
+    client.read_exact(&mut received).await.unwrap();
+    assert_eq!(received, expected);
+}
+
+#[tokio::test]
+async fn test_hello_switches_to_resp3() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["HELLO", "3", "SETNAME", "tenant1"]);
+    request.extend(frame(&[
+        "SET",
+        "users",
+        r#"[{"name":"John","age":30},{"name":"Jane","age":25}]"#,
+    ]));
+    request.extend(frame(&["GET", "users?age_gt=26"]));
+    request.extend(frame(&["GET", "missing"]));
+    client.write_all(&request).await.unwrap();
+
+    let mut buf = vec![0; 4096];
+    let mut received = Vec::new();
+    let tail = "+OK\r\n*1\r\n%2\r\n$3\r\nage\r\n:30\r\n$4\r\nname\r\n$4\r\nJohn\r\n_\r\n";
+    while !received.ends_with(tail.as_bytes()) {
+        let n = client.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed early");
+        received.extend_from_slice(&buf[..n]);
+    }
+    assert!(received.starts_with(b"%7\r\n$6\r\nserver\r\n$5\r\nredis\r\n"));
+}
+
+#[tokio::test]
+async fn test_hello_rejects_unknown_protocol() {
+    let mut client = connect().await;
+
+    client.write_all(&frame(&["HELLO", "4"])).await.unwrap();
+
+    let expected = "-NOPROTO unsupported protocol version\r\n";
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_wrong_arity_reply() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["GET"]));
+    request.extend(frame(&["APPEND", "key"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = "+OK\r\n-ERR wrong number of arguments for 'get' command\r\n-ERR wrong number of arguments for 'append' command\r\n";
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_every_request_gets_one_reply() {
+    let mut client = connect().await;
+
+    let requests = [
+        frame(&["FOO", "bar", "baz"]),
+        frame(&["CLIENT", "KILL"]),
+        frame(&["CLIENT", "SETNAME"]),
+        frame(&["ECHO"]),
+        frame(&["GET", "key"]),
+        frame(&["PING"]),
+    ];
+    let expected = [
+        "-ERR unknown command 'FOO', with args beginning with: 'bar' 'baz' \r\n",
+        "-ERR unknown subcommand 'KILL'. Try CLIENT HELP.\r\n",
+        "-ERR wrong number of arguments for 'client|setname' command\r\n",
+        "-ERR wrong number of arguments for 'echo' command\r\n",
+        "-ERR Tenant name required (use CLIENT SETNAME)\r\n",
+        "+PONG\r\n",
+    ];
+
+    // One at a time: each request must be answered before the next is sent
+    for (request, reply) in requests.iter().zip(expected.iter()) {
+        client.write_all(request).await.unwrap();
+        assert_eq!(read_exactly(&mut client, reply.len()).await, *reply);
+    }
+
+    // Pipelined: the same requests produce the same replies in order
+    client.write_all(&requests.concat()).await.unwrap();
+    let all = expected.concat();
+    assert_eq!(read_exactly(&mut client, all.len()).await, all);
+}
+
+#[tokio::test]
+async fn test_set_options() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["SET", "k", "v1", "EX", "10"]));
+    request.extend(frame(&["SET", "k", "v2", "NX"]));
+    request.extend(frame(&["SET", "k", "v3", "XX", "GET"]));
+    request.extend(frame(&["SET", "k", "v4", "KEEPTTL", "PX", "5"]));
+    request.extend(frame(&["SET", "k", "v4", "EX", "abc"]));
+    request.extend(frame(&["SET", "k", "v4", "EX", "0"]));
+    request.extend(frame(&["SET", "k", "v4", "NX", "XX"]));
+    request.extend(frame(&["SET", "k", "v4", "EX", "10", "KEEPTTL"]));
+    request.extend(frame(&["GET", "k"]));
+    // A repeated flag is not a conflict
+    request.extend(frame(&[
+        "SET", "k", "v5", "XX", "XX", "EX", "10", "EX", "20",
+    ]));
+    request.extend(frame(&["SET", "k", "v6", "NX", "NX"]));
+    request.extend(frame(&["TTL", "k"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "+OK\r\n",
+        "+OK\r\n",
+        "$-1\r\n",
+        "$2\r\nv1\r\n",
+        "-ERR syntax error\r\n",
+        "-ERR value is not an integer or out of range\r\n",
+        "-ERR invalid expire time in 'set' command\r\n",
+        "-ERR syntax error\r\n",
+        "-ERR syntax error\r\n",
+        "$2\r\nv3\r\n",
+        "+OK\r\n",
+        "$-1\r\n",
+        ":20\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_key_commands_are_tenant_scoped() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["SET", "shared", "mine"]));
+    request.extend(frame(&["CLIENT", "SETNAME", "tenant2"]));
+    request.extend(frame(&["EXISTS", "shared"]));
+    request.extend(frame(&["DEL", "shared"]));
+    request.extend(frame(&["RENAME", "shared", "other"]));
+    request.extend(frame(&["CLIENT", "SETNAME", "tenant1"]));
+    request.extend(frame(&["COPY", "shared", "copied"]));
+    request.extend(frame(&["COPY", "copied", "copied", "REPLACE"]));
+    request.extend(frame(&["RENAME", "copied", "moved"]));
+    request.extend(frame(&["TYPE", "moved"]));
+    request.extend(frame(&["UNLINK", "shared", "moved", "nope"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "+OK\r\n+OK\r\n+OK\r\n",
+        ":0\r\n",
+        ":0\r\n",
+        "-ERR no such key\r\n",
+        "+OK\r\n",
+        ":1\r\n",
+        "-ERR source and destination objects are the same\r\n",
+        "+OK\r\n",
+        "+string\r\n",
+        ":2\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_ttl_commands() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["TTL", "missing"]));
+    request.extend(frame(&["SET", "k", "v"]));
+    request.extend(frame(&["TTL", "k"]));
+    request.extend(frame(&["EXPIRE", "k", "100", "XX"]));
+    request.extend(frame(&["EXPIRE", "k", "10", "XX", "LT"]));
+    request.extend(frame(&["EXPIRE", "k", "10", "LT", "GT"]));
+    request.extend(frame(&["TTL", "k"]));
+    request.extend(frame(&["EXPIRE", "k", "100", "NX"]));
+    request.extend(frame(&["TTL", "k"]));
+    request.extend(frame(&["EXPIREAT", "k", "32503680000"]));
+    request.extend(frame(&["EXPIRETIME", "k"]));
+    request.extend(frame(&["EXPIRE", "k", "1", "NX", "GT"]));
+    request.extend(frame(&["PERSIST", "k"]));
+    request.extend(frame(&["PTTL", "k"]));
+    request.extend(frame(&["PEXPIRE", "k", "-1"]));
+    request.extend(frame(&["EXISTS", "k"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "+OK\r\n",
+        ":-2\r\n",
+        "+OK\r\n",
+        ":-1\r\n",
+        ":0\r\n",
+        ":0\r\n",
+        "-ERR GT and LT options at the same time are not compatible\r\n",
+        ":-1\r\n",
+        ":1\r\n",
+        ":100\r\n",
+        ":1\r\n",
+        ":32503680000\r\n",
+        "-ERR NX and XX, GT or LT options at the same time are not compatible\r\n",
+        ":1\r\n",
+        ":-1\r\n",
+        ":1\r\n",
+        ":0\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_counter_commands() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["INCR", "hits"]));
+    request.extend(frame(&["INCRBY", "hits", "9"]));
+    request.extend(frame(&["DECR", "hits"]));
+    request.extend(frame(&["DECRBY", "hits", "4"]));
+    request.extend(frame(&["INCRBY", "hits", "x"]));
+    request.extend(frame(&["DECRBY", "hits", "-9223372036854775808"]));
+    request.extend(frame(&["INCRBYFLOAT", "hits", "0.5"]));
+    request.extend(frame(&["INCR", "hits"]));
+    request.extend(frame(&["CLIENT", "SETNAME", "tenant2"]));
+    request.extend(frame(&["INCR", "hits"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "+OK\r\n",
+        ":1\r\n",
+        ":10\r\n",
+        ":9\r\n",
+        ":5\r\n",
+        "-ERR value is not an integer or out of range\r\n",
+        "-ERR decrement would overflow\r\n",
+        "$3\r\n5.5\r\n",
+        "-ERR value is not an integer or out of range\r\n",
+        "+OK\r\n",
+        ":1\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_multi_key_commands_are_tenant_scoped() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["MSET", "a", "1", "b", "2"]));
+    request.extend(frame(&["MSET", "a", "1", "b"]));
+    request.extend(frame(&["CLIENT", "SETNAME", "tenant2"]));
+    request.extend(frame(&["MSETNX", "a", "x", "c", "y"]));
+    request.extend(frame(&["MGET", "a", "b", "c"]));
+    request.extend(frame(&["CLIENT", "SETNAME", "tenant1"]));
+    request.extend(frame(&["MSETNX", "a", "x", "c", "y"]));
+    request.extend(frame(&["MGET", "a", "b", "c"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "+OK\r\n",
+        "+OK\r\n",
+        "-ERR wrong number of arguments for 'mset' command\r\n",
+        "+OK\r\n",
+        ":1\r\n",
+        "*3\r\n$1\r\nx\r\n$-1\r\n$1\r\ny\r\n",
+        "+OK\r\n",
+        ":0\r\n",
+        "*3\r\n$1\r\n1\r\n$1\r\n2\r\n$-1\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_keys_and_scan_are_tenant_scoped() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant2"]);
+    request.extend(frame(&["MSET", "user:1", "x", "user:2", "x"]));
+    request.extend(frame(&["CLIENT", "SETNAME", "tenant1"]));
+    request.extend(frame(&["MSET", "user:1", "a", "session:1", "b"]));
+    request.extend(frame(&["KEYS", "user:*"]));
+    request.extend(frame(&["SCAN", "0", "MATCH", "session:*", "COUNT", "100"]));
+    request.extend(frame(&["SCAN", "0", "TYPE", "list"]));
+    request.extend(frame(&["SCAN", "abc"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "+OK\r\n+OK\r\n+OK\r\n+OK\r\n",
+        "*1\r\n$6\r\nuser:1\r\n",
+        "*2\r\n$1\r\n0\r\n*1\r\n$9\r\nsession:1\r\n",
+        "*2\r\n$1\r\n0\r\n*0\r\n",
+        "-ERR invalid cursor\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_tenant_names_cannot_contain_colon() {
+    let mut client = connect().await;
+
+    // A tenant "a:b" would store keys under "a:b:key", inside tenant "a"
+    let mut request = frame(&["CLIENT", "SETNAME", "a:b"]);
+    request.extend(frame(&["HELLO", "2", "SETNAME", "a:b"]));
+    request.extend(frame(&["CLIENT", "GETNAME"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "-ERR Client names cannot contain ':'\r\n",
+        "-ERR Client names cannot contain ':'\r\n",
+        "$-1\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_metrics_recorded_for_set_get_append_only() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["RPUSH", "queue", "a"]));
+    request.extend(frame(&["LLEN", "queue"]));
+    request.extend(frame(&["EXISTS", "_metrics"]));
+    request.extend(frame(&["SET", "k", "v"]));
+    request.extend(frame(&["EXISTS", "_metrics"]));
+    // The log is hidden from KEYS and SCAN and cannot be deleted
+    request.extend(frame(&["KEYS", "*"]));
+    request.extend(frame(&["SCAN", "0", "MATCH", "_*", "COUNT", "100"]));
+    request.extend(frame(&["DEL", "_metrics", "k"]));
+    request.extend(frame(&["UNLINK", "_metrics"]));
+    request.extend(frame(&["TYPE", "_metrics"]));
+    // One stream entry per recorded command: SET, then this GET
+    request.extend(frame(&["GET", "k"]));
+    request.extend(frame(&["XLEN", "_metrics"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "+OK\r\n",
+        ":1\r\n",
+        ":1\r\n",
+        ":0\r\n",
+        "+OK\r\n",
+        ":1\r\n",
+        "*2\r\n$5\r\nqueue\r\n$1\r\nk\r\n",
+        "*2\r\n$1\r\n0\r\n*0\r\n",
+        ":1\r\n",
+        ":0\r\n",
+        "+stream\r\n",
+        "$-1\r\n",
+        ":2\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_string_range_commands() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["SETRANGE", "rec", "2", "ab"]));
+    request.extend(frame(&["GETRANGE", "rec", "-3", "-1"]));
+    request.extend(frame(&["STRLEN", "rec"]));
+    request.extend(frame(&["SETRANGE", "rec", "-1", "x"]));
+    request.extend(frame(&["GETSET", "rec", "new"]));
+    request.extend(frame(&["GETEX", "rec", "EX", "100"]));
+    request.extend(frame(&["TTL", "rec"]));
+    request.extend(frame(&["GETEX", "rec", "PERSIST", "EX", "1"]));
+    request.extend(frame(&["GETDEL", "rec"]));
+    request.extend(frame(&["GETDEL", "rec"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "+OK\r\n",
+        ":4\r\n",
+        "$3\r\n\0ab\r\n",
+        ":4\r\n",
+        "-ERR offset is out of range\r\n",
+        "$4\r\n\0\0ab\r\n",
+        "$3\r\nnew\r\n",
+        ":100\r\n",
+        "-ERR syntax error\r\n",
+        "$3\r\nnew\r\n",
+        "$-1\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_wrongtype_and_object_encoding() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["APPEND", "doc", "1"]));
+    request.extend(frame(&["INCR", "doc"]));
+    request.extend(frame(&["GETRANGE", "doc", "0", "-1"]));
+    request.extend(frame(&["TYPE", "doc"]));
+    request.extend(frame(&["OBJECT", "ENCODING", "doc"]));
+    request.extend(frame(&["SET", "n", "42"]));
+    request.extend(frame(&["OBJECT", "ENCODING", "n"]));
+    request.extend(frame(&["OBJECT", "ENCODING", "missing"]));
+    request.extend(frame(&["OBJECT", "FREQ", "n"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "+OK\r\n",
+        "+OK\r\n",
+        "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n",
+        "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n",
+        "+ReJSON-RL\r\n",
+        "$4\r\njson\r\n",
+        "+OK\r\n",
+        "$3\r\nint\r\n",
+        "$-1\r\n",
+        "-ERR unknown subcommand 'FREQ'. Try OBJECT HELP.\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_list_commands() {
+    let mut client = connect().await;
+
+    let mut request = frame(&["CLIENT", "SETNAME", "tenant1"]);
+    request.extend(frame(&["RPUSH", "jobs", "a", "b", "c"]));
+    request.extend(frame(&["LPUSH", "jobs", "z"]));
+    request.extend(frame(&["LRANGE", "jobs", "0", "-1"]));
+    request.extend(frame(&["LPOP", "jobs"]));
+    request.extend(frame(&["RPOP", "jobs", "2"]));
+    request.extend(frame(&["LPOP", "jobs", "-1"]));
+    request.extend(frame(&["LMOVE", "jobs", "done", "LEFT", "RIGHT"]));
+    request.extend(frame(&["LLEN", "jobs"]));
+    request.extend(frame(&["LPOP", "jobs", "1"]));
+    request.extend(frame(&["LPOP", "jobs"]));
+    request.extend(frame(&["LINDEX", "done", "0"]));
+    request.extend(frame(&["GET", "done"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "+OK\r\n",
+        ":3\r\n",
+        ":4\r\n",
+        "*4\r\n$1\r\nz\r\n$1\r\na\r\n$1\r\nb\r\n$1\r\nc\r\n",
+        "$1\r\nz\r\n",
+        "*2\r\n$1\r\nc\r\n$1\r\nb\r\n",
+        "-ERR value is out of range, must be positive\r\n",
+        "$1\r\na\r\n",
+        ":0\r\n",
+        "*-1\r\n",
+        "$-1\r\n",
+        "$1\r\na\r\n",
+        "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_hash_commands() {
+    let addr = start_server().await;
+    let mut client = connect_as(addr, "tenant1").await;
+    let mut other = connect_as(addr, "tenant2").await;
+
+    let mut request = frame(&["HSET", "user", "name", "ann", "age", "30"]);
+    request.extend(frame(&["HSET", "user", "age"]));
+    request.extend(frame(&["HINCRBY", "user", "age", "2"]));
+    request.extend(frame(&["HINCRBY", "user", "name", "1"]));
+    request.extend(frame(&["HMGET", "user", "name", "nope"]));
+    request.extend(frame(&["HGETALL", "user"]));
+    request.extend(frame(&["HKEYS", "user"]));
+    request.extend(frame(&["HSCAN", "user", "0", "MATCH", "n*", "NOVALUES"]));
+    request.extend(frame(&[
+        "HEXPIRE", "user", "100", "NX", "FIELDS", "2", "age", "nope",
+    ]));
+    request.extend(frame(&["HTTL", "user", "FIELDS", "2", "age", "name"]));
+    request.extend(frame(&["HPERSIST", "user", "FIELDS", "1", "age"]));
+    request.extend(frame(&["HEXPIRE", "user", "100", "FIELDS", "2", "age"]));
+    request.extend(frame(&["HDEL", "user", "age", "nope"]));
+    request.extend(frame(&["HLEN", "user"]));
+    request.extend(frame(&["GET", "user"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        ":2\r\n",
+        "-ERR wrong number of arguments for 'hset' command\r\n",
+        ":32\r\n",
+        "-ERR hash value is not an integer\r\n",
+        "*2\r\n$3\r\nann\r\n$-1\r\n",
+        "*4\r\n$4\r\nname\r\n$3\r\nann\r\n$3\r\nage\r\n$2\r\n32\r\n",
+        "*2\r\n$4\r\nname\r\n$3\r\nage\r\n",
+        "*2\r\n$1\r\n0\r\n*1\r\n$4\r\nname\r\n",
+        "*2\r\n:1\r\n:-2\r\n",
+        "*2\r\n:100\r\n:-1\r\n",
+        "*1\r\n:1\r\n",
+        "-ERR The `numfields` parameter must match the number of arguments\r\n",
+        ":1\r\n",
+        ":1\r\n",
+        "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+
+    // Hashes are tenant-scoped like every other key
+    other
+        .write_all(&frame(&["HGET", "user", "name"]))
+        .await
+        .unwrap();
+    assert_eq!(read_exactly(&mut other, 5).await, "$-1\r\n");
+}
+
+#[tokio::test]
+async fn test_set_commands_stay_within_tenant() {
+    let addr = start_server().await;
+    let mut client = connect_as(addr, "tenant1").await;
+    let mut other = connect_as(addr, "tenant2").await;
+
+    other
+        .write_all(&frame(&["SADD", "tags", "a", "b", "c", "d"]))
+        .await
+        .unwrap();
+    assert_eq!(read_exactly(&mut other, 4).await, ":4\r\n");
+
+    let mut request = frame(&["SADD", "tags", "a", "b", "a"]);
+    request.extend(frame(&["SADD", "seen", "b"]));
+    request.extend(frame(&["SISMEMBER", "tags", "c"]));
+    request.extend(frame(&["SINTER", "tags", "seen"]));
+    // tenant2's "tags" must not leak into the union
+    request.extend(frame(&["SUNIONSTORE", "all", "tags", "seen"]));
+    request.extend(frame(&["SDIFFSTORE", "unseen", "tags", "seen"]));
+    request.extend(frame(&["SMEMBERS", "unseen"]));
+    request.extend(frame(&["SREM", "unseen", "a"]));
+    request.extend(frame(&["SCARD", "unseen"]));
+    request.extend(frame(&["SINTER", "tags", "unseen"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        ":2\r\n",
+        ":1\r\n",
+        ":0\r\n",
+        "*1\r\n$1\r\nb\r\n",
+        ":2\r\n",
+        ":1\r\n",
+        "*1\r\n$1\r\na\r\n",
+        ":1\r\n",
+        ":0\r\n",
+        "*0\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+
+    other.write_all(&frame(&["SCARD", "tags"])).await.unwrap();
+    assert_eq!(read_exactly(&mut other, 4).await, ":4\r\n");
+}
+
+#[tokio::test]
+async fn test_sorted_set_commands() {
+    let addr = start_server().await;
+    let mut client = connect_as(addr, "tenant1").await;
+    let mut other = connect_as(addr, "tenant2").await;
+
+    other
+        .write_all(&frame(&["ZADD", "scores", "100", "mallory"]))
+        .await
+        .unwrap();
+    assert_eq!(read_exactly(&mut other, 4).await, ":1\r\n");
+
+    let mut request = frame(&["ZADD", "scores", "10", "ann", "20", "bob", "30", "cy"]);
+    request.extend(frame(&[
+        "ZADD", "scores", "GT", "CH", "5", "ann", "25", "bob",
+    ]));
+    request.extend(frame(&["ZADD", "scores", "NX", "XX", "1", "ann"]));
+    request.extend(frame(&["ZADD", "scores", "INCR", "1.5", "ann"]));
+    request.extend(frame(&["ZINCRBY", "scores", "abc", "ann"]));
+    request.extend(frame(&[
+        "ZRANGE",
+        "scores",
+        "+inf",
+        "(11.5",
+        "BYSCORE",
+        "REV",
+        "LIMIT",
+        "0",
+        "1",
+        "WITHSCORES",
+    ]));
+    request.extend(frame(&["ZRANGE", "scores", "0", "-1", "LIMIT", "0", "1"]));
+    request.extend(frame(&["ZRANK", "scores", "cy", "WITHSCORE"]));
+    request.extend(frame(&["ZCOUNT", "scores", "(11.5", "+inf"]));
+    request.extend(frame(&["ZPOPMIN", "scores"]));
+    request.extend(frame(&["SADD", "bonus", "bob"]));
+    // tenant2's "scores" must not take part
+    request.extend(frame(&[
+        "ZUNIONSTORE",
+        "total",
+        "2",
+        "scores",
+        "bonus",
+        "AGGREGATE",
+        "MAX",
+    ]));
+    request.extend(frame(&["ZRANGE", "total", "0", "-1", "WITHSCORES"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        ":3\r\n",
+        ":1\r\n",
+        "-ERR XX and NX options at the same time are not compatible\r\n",
+        "$4\r\n11.5\r\n",
+        "-ERR value is not a valid float\r\n",
+        "*2\r\n$2\r\ncy\r\n$2\r\n30\r\n",
+        "-ERR syntax error, LIMIT is only supported in combination with either BYSCORE or BYLEX\r\n",
+        "*2\r\n:2\r\n$2\r\n30\r\n",
+        ":2\r\n",
+        "*2\r\n$3\r\nann\r\n$4\r\n11.5\r\n",
+        ":1\r\n",
+        ":2\r\n",
+        "*4\r\n$3\r\nbob\r\n$2\r\n25\r\n$2\r\ncy\r\n$2\r\n30\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+
+    // RESP3 pairs each member with a double score
+    let mut request = frame(&["HELLO", "3"]);
+    request.extend(frame(&["ZRANGE", "scores", "0", "0", "WITHSCORES"]));
+    other.write_all(&request).await.unwrap();
+    let mut buf = vec![0; 4096];
+    let mut received = Vec::new();
+    let tail = "*1\r\n*2\r\n$7\r\nmallory\r\n,100\r\n";
+    while !received.ends_with(tail.as_bytes()) {
+        let n = other.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed early");
+        received.extend_from_slice(&buf[..n]);
+    }
+}
+
+#[tokio::test]
+async fn test_stream_commands() {
+    let addr = start_server().await;
+    let mut client = connect_as(addr, "tenant1").await;
+
+    let mut request = frame(&["XADD", "events", "1-1", "kind", "login"]);
+    request.extend(frame(&["XADD", "events", "1-*", "kind", "click"]));
+    request.extend(frame(&["XADD", "events", "1-1", "kind", "stale"]));
+    request.extend(frame(&["XADD", "events", "abc", "kind", "bad"]));
+    request.extend(frame(&["XADD", "events", "2-0", "kind"]));
+    request.extend(frame(&[
+        "XADD", "events", "MAXLEN", "=", "2", "LIMIT", "1", "3-0", "k", "v",
+    ]));
+    request.extend(frame(&["XADD", "events", "MAXLEN", "2", "3-0", "k", "v"]));
+    request.extend(frame(&["XADD", "missing", "NOMKSTREAM", "*", "k", "v"]));
+    request.extend(frame(&["XRANGE", "events", "(1-1", "+"]));
+    request.extend(frame(&["XREVRANGE", "events", "+", "-", "COUNT", "1"]));
+    request.extend(frame(&["XTRIM", "events", "MINID", "3"]));
+    request.extend(frame(&["XLEN", "events"]));
+    request.extend(frame(&["XREAD", "STREAMS", "events", "missing", "0"]));
+    request.extend(frame(&["XREAD", "COUNT", "1", "STREAMS", "events", "0-0"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "$3\r\n1-1\r\n",
+        "$3\r\n1-2\r\n",
+        "-ERR The ID specified in XADD is equal or smaller than the target stream top item\r\n",
+        "-ERR Invalid stream ID specified as stream command argument\r\n",
+        "-ERR wrong number of arguments for 'xadd' command\r\n",
+        "-ERR syntax error, LIMIT cannot be used without the special ~ option\r\n",
+        "$3\r\n3-0\r\n",
+        "$-1\r\n",
+        "*2\r\n",
+        "*2\r\n$3\r\n1-2\r\n*2\r\n$4\r\nkind\r\n$5\r\nclick\r\n",
+        "*2\r\n$3\r\n3-0\r\n*2\r\n$1\r\nk\r\n$1\r\nv\r\n",
+        "*1\r\n*2\r\n$3\r\n3-0\r\n*2\r\n$1\r\nk\r\n$1\r\nv\r\n",
+        ":1\r\n",
+        ":1\r\n",
+        "-ERR Unbalanced 'xread' list of streams: for each stream key an ID or '$' must be specified.\r\n",
+        "*1\r\n*2\r\n$6\r\nevents\r\n*1\r\n*2\r\n$3\r\n3-0\r\n*2\r\n$1\r\nk\r\n$1\r\nv\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_blocking_xread() {
+    let addr = start_server().await;
+    let mut reader = connect_as(addr, "tenant1").await;
+    let mut popper = connect_as(addr, "tenant1").await;
+    let mut quitter = connect_as(addr, "tenant1").await;
+    let mut producer = connect_as(addr, "tenant1").await;
+    let mut outsider = connect_as(addr, "tenant2").await;
+
+    let mut request = frame(&["XREAD", "BLOCK", "-1", "STREAMS", "feed", "$"]);
+    request.extend(frame(&["XREAD", "BLOCK", "0", "STREAMS", "feed", "$"]));
+    reader.write_all(&request).await.unwrap();
+    assert_eq!(
+        read_exactly(&mut reader, 26).await,
+        "-ERR timeout is negative\r\n"
+    );
+    // A list waiter on the same key is left alone by stream entries
+    popper
+        .write_all(&frame(&["BLPOP", "feed", "0.2"]))
+        .await
+        .unwrap();
+    quitter
+        .write_all(&frame(&["XREAD", "BLOCK", "100", "STREAMS", "feed", "$"]))
+        .await
+        .unwrap();
+    drop(quitter);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // Another tenant's stream of the same name does not wake anyone
+    outsider
+        .write_all(&frame(&["XADD", "feed", "1-0", "from", "outsider"]))
+        .await
+        .unwrap();
+    assert_eq!(read_exactly(&mut outsider, 9).await, "$3\r\n1-0\r\n");
+    producer
+        .write_all(&frame(&["XADD", "feed", "5-0", "msg", "hi"]))
+        .await
+        .unwrap();
+    assert_eq!(read_exactly(&mut producer, 9).await, "$3\r\n5-0\r\n");
+
+    let expected =
+        "*1\r\n*2\r\n$4\r\nfeed\r\n*1\r\n*2\r\n$3\r\n5-0\r\n*2\r\n$3\r\nmsg\r\n$2\r\nhi\r\n";
+    assert_eq!(read_exactly(&mut reader, expected.len()).await, expected);
+    assert_eq!(read_exactly(&mut popper, 5).await, "*-1\r\n");
+
+    // Without new entries the wait times out with a null reply
+    reader
+        .write_all(&frame(&["XREAD", "BLOCK", "50", "STREAMS", "feed", "5-0"]))
+        .await
+        .unwrap();
+    assert_eq!(read_exactly(&mut reader, 5).await, "*-1\r\n");
+}
+
+#[tokio::test]
+async fn test_stream_consumer_groups() {
+    let addr = start_server().await;
+    let mut client = connect_as(addr, "tenant1").await;
+    let mut other = connect_as(addr, "tenant2").await;
+
+    let mut request = frame(&["XGROUP", "CREATE", "jobs", "workers", "$", "MKSTREAM"]);
+    request.extend(frame(&["XADD", "jobs", "9-0", "task", "theirs"]));
+    other.write_all(&request).await.unwrap();
+    assert_eq!(read_exactly(&mut other, 14).await, "+OK\r\n$3\r\n9-0\r\n");
+
+    let mut request = frame(&["XGROUP", "CREATE", "jobs", "workers", "$"]);
+    request.extend(frame(&[
+        "XGROUP", "CREATE", "jobs", "workers", "0", "MKSTREAM",
+    ]));
+    request.extend(frame(&["XGROUP", "CREATE", "jobs", "workers", "0"]));
+    request.extend(frame(&["XADD", "jobs", "1-0", "task", "a"]));
+    request.extend(frame(&["XADD", "jobs", "2-0", "task", "b"]));
+    request.extend(frame(&[
+        "XREADGROUP",
+        "GROUP",
+        "workers",
+        "w1",
+        "COUNT",
+        "1",
+        "STREAMS",
+        "jobs",
+        ">",
+    ]));
+    request.extend(frame(&[
+        "XREADGROUP",
+        "GROUP",
+        "workers",
+        "w2",
+        "STREAMS",
+        "jobs",
+        ">",
+    ]));
+    request.extend(frame(&[
+        "XREADGROUP",
+        "GROUP",
+        "workers",
+        "w1",
+        "STREAMS",
+        "jobs",
+        "$",
+    ]));
+    request.extend(frame(&["XREAD", "STREAMS", "jobs", ">"]));
+    request.extend(frame(&[
+        "XREADGROUP",
+        "GROUP",
+        "nope",
+        "w1",
+        "STREAMS",
+        "jobs",
+        ">",
+    ]));
+    request.extend(frame(&["XPENDING", "jobs", "workers"]));
+    request.extend(frame(&["XACK", "jobs", "workers", "1-0", "1-0"]));
+    request.extend(frame(&[
+        "XCLAIM", "jobs", "workers", "w1", "0", "2-0", "JUSTID",
+    ]));
+    request.extend(frame(&[
+        "XAUTOCLAIM",
+        "jobs",
+        "workers",
+        "w3",
+        "0",
+        "0",
+        "COUNT",
+        "5",
+    ]));
+    request.extend(frame(&["XGROUP", "DELCONSUMER", "jobs", "workers", "w3"]));
+    request.extend(frame(&["XPENDING", "jobs", "workers"]));
+    client.write_all(&request).await.unwrap();
+
+    let expected = concat!(
+        "-ERR The XGROUP subcommand requires the key to exist. Note that for CREATE you may want to use the MKSTREAM option to create an empty stream automatically.\r\n",
+        "+OK\r\n",
+        "-BUSYGROUP Consumer Group name already exists\r\n",
+        "$3\r\n1-0\r\n",
+        "$3\r\n2-0\r\n",
+        "*1\r\n*2\r\n$4\r\njobs\r\n*1\r\n*2\r\n$3\r\n1-0\r\n*2\r\n$4\r\ntask\r\n$1\r\na\r\n",
+        "*1\r\n*2\r\n$4\r\njobs\r\n*1\r\n*2\r\n$3\r\n2-0\r\n*2\r\n$4\r\ntask\r\n$1\r\nb\r\n",
+        "-ERR The $ ID is meaningless in the context of XREADGROUP: you want to read the history of this consumer by specifying a proper ID, or use the > ID to get new messages. The $ ID would just return an empty result set.\r\n",
+        "-ERR The > ID can be specified only when calling XREADGROUP using the GROUP <group> <consumer> option.\r\n",
+        "-NOGROUP No such key or consumer group\r\n",
+        "*4\r\n:2\r\n$3\r\n1-0\r\n$3\r\n2-0\r\n*2\r\n*2\r\n$2\r\nw1\r\n$1\r\n1\r\n*2\r\n$2\r\nw2\r\n$1\r\n1\r\n",
+        ":1\r\n",
+        "*1\r\n$3\r\n2-0\r\n",
+        "*3\r\n$3\r\n0-0\r\n*1\r\n*2\r\n$3\r\n2-0\r\n*2\r\n$4\r\ntask\r\n$1\r\nb\r\n*0\r\n",
+        ":1\r\n",
+        "*4\r\n:0\r\n$-1\r\n$-1\r\n*-1\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+
+    // A blocked group reader is woken by its own tenant's stream only
+    let mut worker = connect_as(addr, "tenant1").await;
+    worker
+        .write_all(&frame(&[
+            "XREADGROUP",
+            "GROUP",
+            "workers",
+            "w9",
+            "BLOCK",
+            "0",
+            "STREAMS",
+            "jobs",
+            ">",
+        ]))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    other
+        .write_all(&frame(&["XADD", "jobs", "10-0", "task", "theirs"]))
+        .await
+        .unwrap();
+    assert_eq!(read_exactly(&mut other, 10).await, "$4\r\n10-0\r\n");
+    client
+        .write_all(&frame(&["XADD", "jobs", "3-0", "task", "c"]))
+        .await
+        .unwrap();
+    assert_eq!(read_exactly(&mut client, 9).await, "$3\r\n3-0\r\n");
+
+    let expected =
+        "*1\r\n*2\r\n$4\r\njobs\r\n*1\r\n*2\r\n$3\r\n3-0\r\n*2\r\n$4\r\ntask\r\n$1\r\nc\r\n";
+    assert_eq!(read_exactly(&mut worker, expected.len()).await, expected);
+    // The delivery is pending for the worker until acknowledged
+    client
+        .write_all(&frame(&["XACK", "jobs", "workers", "3-0"]))
+        .await
+        .unwrap();
+    assert_eq!(read_exactly(&mut client, 4).await, ":1\r\n");
+}
+
+#[tokio::test]
+async fn test_blocking_pops() {
+    let addr = start_server().await;
+    let mut client = connect_as(addr, "tenant1").await;
+
+    // Served immediately when the list has items; times out otherwise
+    let mut request = frame(&["RPUSH", "jobs", "a"]);
+    request.extend(frame(&["BLPOP", "empty", "jobs", "1"]));
+    request.extend(frame(&["BRPOP", "empty", "0.05"]));
+    request.extend(frame(&["BLPOP", "jobs", "-1"]));
+    client.write_all(&request).await.unwrap();
+    let expected = concat!(
+        ":1\r\n",
+        "*2\r\n$4\r\njobs\r\n$1\r\na\r\n",
+        "*-1\r\n",
+        "-ERR timeout is negative\r\n",
+    );
+    assert_eq!(read_exactly(&mut client, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_blocked_clients_are_served_in_fifo_order() {
+    let addr = start_server().await;
+    let mut first = connect_as(addr, "tenant1").await;
+    let mut second = connect_as(addr, "tenant1").await;
+    let mut other_tenant = connect_as(addr, "tenant2").await;
+    let mut producer = connect_as(addr, "tenant1").await;
+
+    first
+        .write_all(&frame(&["BLPOP", "jobs", "0"]))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    second
+        .write_all(&frame(&["BRPOP", "other", "jobs", "0"]))
+        .await
+        .unwrap();
+    other_tenant
+        .write_all(&frame(&["BLPOP", "jobs", "0.2"]))
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    producer
+        .write_all(&frame(&["RPUSH", "jobs", "a", "b", "c"]))
+        .await
+        .unwrap();
+    // Both waiters are served before the producer hears back
+    assert_eq!(read_exactly(&mut producer, 4).await, ":3\r\n");
+
+    let expected = "*2\r\n$4\r\njobs\r\n$1\r\na\r\n";
+    assert_eq!(read_exactly(&mut first, expected.len()).await, expected);
+    let expected = "*2\r\n$4\r\njobs\r\n$1\r\nc\r\n";
+    assert_eq!(read_exactly(&mut second, expected.len()).await, expected);
+
+    // The other tenant's key was never pushed to
+    assert_eq!(read_exactly(&mut other_tenant, 5).await, "*-1\r\n");
+
+    producer
+        .write_all(&frame(&["LRANGE", "jobs", "0", "-1"]))
+        .await
+        .unwrap();
+    let expected = "*1\r\n$1\r\nb\r\n";
+    assert_eq!(read_exactly(&mut producer, expected.len()).await, expected);
+}
+
+#[tokio::test]
+async fn test_item_served_to_disconnected_client_is_put_back() {
+    let addr = start_server().await;
+    let mut producer = connect_as(addr, "tenant1").await;
+
+    for round in 0..5 {
+        let mut consumer = connect_as(addr, "tenant1").await;
+        consumer
+            .write_all(&frame(&["BLPOP", "jobs", "0"]))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        // The push and the disconnect reach the server together
+        producer
+            .write_all(&frame(&["RPUSH", "jobs", "a"]))
+            .await
+            .unwrap();
+        drop(consumer);
+        assert_eq!(read_exactly(&mut producer, 4).await, ":1\r\n");
+
+        let mut len = String::new();
+        for _ in 0..50 {
+            producer.write_all(&frame(&["LLEN", "jobs"])).await.unwrap();
+            len = read_exactly(&mut producer, 4).await;
+            if len == ":1\r\n" {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(len, ":1\r\n", "item lost in round {round}");
+
+        producer.write_all(&frame(&["DEL", "jobs"])).await.unwrap();
+        assert_eq!(read_exactly(&mut producer, 4).await, ":1\r\n");
+    }
+}
+
+#[tokio::test]
+async fn test_blmove_waits_for_source() {
+    let addr = start_server().await;
+    let mut worker = connect_as(addr, "tenant1").await;
+    let mut producer = connect_as(addr, "tenant1").await;
+
+    let mut request = frame(&["BLMOVE", "queue", "processing", "LEFT", "RIGHT", "0"]);
+    // Pipelined behind the blocked command; answered once it completes
+    request.extend(frame(&["LLEN", "processing"]));
+    worker.write_all(&request).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    producer
+        .write_all(&frame(&["LPUSH", "queue", "job"]))
+        .await
+        .unwrap();
+    assert_eq!(read_exactly(&mut producer, 4).await, ":1\r\n");
+
+    let expected = "$3\r\njob\r\n:1\r\n";
+    assert_eq!(read_exactly(&mut worker, expected.len()).await, expected);
+}
@@ -0,0 +1,270 @@
+use redis_test_simple::store::RedisStore;
+use redis_test_simple::types::{ExpireCondition, StoreError, TtlRequirement, TtlState};
+use std::collections::HashSet;
+use std::time::{Duration, SystemTime};
+
+fn pairs(items: &[(&str, &str)]) -> Vec<(Vec<u8>, Vec<u8>)> {
+    items
+        .iter()
+        .map(|(field, value)| (field.as_bytes().to_vec(), value.as_bytes().to_vec()))
+        .collect()
+}
+
+fn bytes(items: &[&str]) -> Vec<Vec<u8>> {
+    items.iter().map(|s| s.as_bytes().to_vec()).collect()
+}
+
+#[test]
+fn test_set_get_and_delete_fields() {
+    let store = RedisStore::new();
+    assert_eq!(
+        store.hash_set("u".to_string(), pairs(&[("name", "ann"), ("age", "30")])),
+        Ok(2)
+    );
+    // Overwriting an existing field does not count as added
+    assert_eq!(
+        store.hash_set("u".to_string(), pairs(&[("age", "31"), ("city", "oslo")])),
+        Ok(1)
+    );
+
+    assert_eq!(store.hash_get("u", b"age"), Ok(Some(b"31".to_vec())));
+    assert_eq!(store.hash_get("u", b"nope"), Ok(None));
+    assert_eq!(
+        store.hash_get_many("u", &bytes(&["name", "nope"])),
+        Ok(vec![Some(b"ann".to_vec()), None])
+    );
+    assert_eq!(
+        store.hash_get_all("u"),
+        Ok(pairs(&[("name", "ann"), ("age", "31"), ("city", "oslo")]))
+    );
+    assert_eq!(store.hash_exists("u", b"city"), Ok(true));
+    assert_eq!(store.hash_len("u"), Ok(3));
+
+    assert_eq!(store.hash_delete("u", &bytes(&["age", "nope"])), Ok(1));
+    assert_eq!(store.hash_delete("u", &bytes(&["name", "city"])), Ok(2));
+    // Deleting the last field deletes the key
+    assert_eq!(store.key_type("u"), "none");
+    assert_eq!(store.hash_len("u"), Ok(0));
+}
+
+#[test]
+fn test_incr_by() {
+    let store = RedisStore::new();
+    assert_eq!(store.hash_incr_by("h".to_string(), b"n".to_vec(), 5), Ok(5));
+    assert_eq!(
+        store.hash_incr_by("h".to_string(), b"n".to_vec(), -7),
+        Ok(-2)
+    );
+
+    store
+        .hash_set(
+            "h".to_string(),
+            pairs(&[("s", "abc"), ("max", "9223372036854775807")]),
+        )
+        .unwrap();
+    assert_eq!(
+        store.hash_incr_by("h".to_string(), b"s".to_vec(), 1),
+        Err(StoreError::HashNotInteger)
+    );
+    assert_eq!(
+        store.hash_incr_by("h".to_string(), b"max".to_vec(), 1),
+        Err(StoreError::Overflow)
+    );
+}
+
+#[test]
+fn test_hashes_are_typed() {
+    let store = RedisStore::new();
+    store.set("s".to_string(), "v".to_string(), None).unwrap();
+    store
+        .hash_set("h".to_string(), pairs(&[("f", "v")]))
+        .unwrap();
+
+    assert_eq!(
+        store.hash_set("s".to_string(), pairs(&[("f", "v")])),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(store.hash_get("s", b"f"), Err(StoreError::WrongType));
+    assert_eq!(store.strlen("h"), Err(StoreError::WrongType));
+    assert_eq!(store.key_type("h"), "hash");
+    assert_eq!(store.object_encoding("h"), Some("listpack"));
+
+    let big: Vec<(Vec<u8>, Vec<u8>)> = (0..200)
+        .map(|i| (format!("f{}", i).into_bytes(), i.to_string().into_bytes()))
+        .collect();
+    store.hash_set("h".to_string(), big).unwrap();
+    assert_eq!(store.object_encoding("h"), Some("hashtable"));
+}
+
+#[test]
+fn test_scan_visits_every_field_despite_changes() {
+    let store = RedisStore::new();
+    let initial: Vec<(Vec<u8>, Vec<u8>)> = (0..50)
+        .map(|i| (format!("f{}", i).into_bytes(), i.to_string().into_bytes()))
+        .collect();
+    store.hash_set("h".to_string(), initial).unwrap();
+
+    let mut seen = HashSet::new();
+    let mut cursor = 0;
+    let mut rounds = 0;
+    loop {
+        let (next, batch) = store.hash_scan("h", cursor, 7).unwrap();
+        seen.extend(batch.into_iter().map(|(field, _)| field));
+        // Fields added or removed mid-scan must not disturb the others
+        if rounds < 3 {
+            store
+                .hash_set("h".to_string(), pairs(&[(&format!("new{}", rounds), "x")]))
+                .unwrap();
+            store
+                .hash_delete("h", &[format!("f{}", 49 - rounds).into_bytes()])
+                .unwrap();
+        }
+        rounds += 1;
+        cursor = next;
+        if cursor == 0 {
+            break;
+        }
+    }
+
+    for i in 0..47 {
+        assert!(seen.contains(format!("f{}", i).as_bytes()), "missed f{}", i);
+    }
+    assert_eq!(store.hash_scan("missing", 0, 10), Ok((0, Vec::new())));
+}
+
+#[test]
+fn test_field_expiry() {
+    let store = RedisStore::new();
+    store
+        .hash_set("h".to_string(), pairs(&[("a", "1"), ("b", "2")]))
+        .unwrap();
+    let soon = SystemTime::now() + Duration::from_millis(50);
+    let later = SystemTime::now() + Duration::from_secs(100);
+
+    assert_eq!(
+        store.hash_expire(
+            "h",
+            &bytes(&["a", "nope"]),
+            soon,
+            ExpireCondition::default()
+        ),
+        Ok(vec![1, -2])
+    );
+    assert_eq!(
+        store.hash_expire(
+            "h",
+            &bytes(&["a", "b"]),
+            later,
+            ExpireCondition {
+                require: Some(TtlRequirement::NoTtl),
+                compare: None,
+            }
+        ),
+        Ok(vec![0, 1])
+    );
+    assert_eq!(
+        store.hash_ttl("h", &bytes(&["a", "nope"])),
+        Ok(vec![TtlState::ExpiresAt(soon), TtlState::Missing])
+    );
+    assert_eq!(
+        store.hash_persist("h", &bytes(&["b", "b"])),
+        Ok(vec![1, -1])
+    );
+
+    // Overwriting a field clears its TTL, HINCRBY keeps it
+    store
+        .hash_expire("h", &bytes(&["b"]), later, ExpireCondition::default())
+        .unwrap();
+    store
+        .hash_set("h".to_string(), pairs(&[("b", "3")]))
+        .unwrap();
+    assert_eq!(
+        store.hash_ttl("h", &bytes(&["b"])),
+        Ok(vec![TtlState::Persistent])
+    );
+    store
+        .hash_expire("h", &bytes(&["b"]), later, ExpireCondition::default())
+        .unwrap();
+    store
+        .hash_incr_by("h".to_string(), b"b".to_vec(), 1)
+        .unwrap();
+    assert_eq!(
+        store.hash_ttl("h", &bytes(&["b"])),
+        Ok(vec![TtlState::ExpiresAt(later)])
+    );
+
+    std::thread::sleep(Duration::from_millis(80));
+    assert_eq!(store.hash_get("h", b"a"), Ok(None));
+    assert_eq!(store.hash_len("h"), Ok(1));
+
+    // A time in the past deletes the field, and the key with its last field
+    assert_eq!(
+        store.hash_expire(
+            "h",
+            &bytes(&["b"]),
+            SystemTime::now() - Duration::from_secs(1),
+            ExpireCondition::default()
+        ),
+        Ok(vec![2])
+    );
+    assert_eq!(store.key_type("h"), "none");
+    assert_eq!(
+        store.hash_expire("h", &bytes(&["b"]), later, ExpireCondition::default()),
+        Ok(vec![-2])
+    );
+}
+
+#[test]
+fn test_last_field_expiring_removes_key() {
+    let store = RedisStore::new();
+    store
+        .hash_set("h".to_string(), pairs(&[("a", "1")]))
+        .unwrap();
+    store
+        .hash_expire(
+            "h",
+            &bytes(&["a"]),
+            SystemTime::now() + Duration::from_millis(30),
+            ExpireCondition::default(),
+        )
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(60));
+    assert_eq!(store.exists(&["h".to_string()]), 0);
+}
+
+#[test]
+fn test_active_expire_cycle_removes_unread_fields() {
+    let store = RedisStore::new();
+    let soon = SystemTime::now() + Duration::from_millis(10);
+    for i in 0..100 {
+        let key = format!("h{}", i);
+        store
+            .hash_set(key.clone(), pairs(&[("tmp", "1"), ("keep", "2")]))
+            .unwrap();
+        store
+            .hash_expire(&key, &bytes(&["tmp"]), soon, ExpireCondition::default())
+            .unwrap();
+    }
+    store
+        .hash_set("gone".to_string(), pairs(&[("tmp", "1")]))
+        .unwrap();
+    store
+        .hash_expire("gone", &bytes(&["tmp"]), soon, ExpireCondition::default())
+        .unwrap();
+    std::thread::sleep(Duration::from_millis(50));
+
+    // Sampling is probabilistic, so run cycles until the stale fields are gone
+    for _ in 0..1000 {
+        store.active_expire_cycle(Duration::from_millis(25));
+        if store.expire_stats().expired_subkeys == 101 {
+            break;
+        }
+    }
+
+    let stats = store.expire_stats();
+    assert_eq!(stats.expired_subkeys, 101);
+    // Emptied hashes are deleted without counting as expired keys
+    assert_eq!(stats.expired_keys, 0);
+    assert_eq!(store.exists(&["gone".to_string()]), 0);
+    assert_eq!(store.hash_get_all("h0"), Ok(pairs(&[("keep", "2")])));
+}
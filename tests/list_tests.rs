@@ -0,0 +1,196 @@
+use redis_test_simple::store::RedisStore;
+use redis_test_simple::types::{ListEnd, StoreError};
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+fn bytes(items: &[&str]) -> Vec<Vec<u8>> {
+    items.iter().map(|s| s.as_bytes().to_vec()).collect()
+}
+
+#[test]
+fn test_push_pop_both_ends() {
+    let store = RedisStore::new();
+    assert_eq!(
+        store.push("q".to_string(), bytes(&["a", "b"]), ListEnd::Left),
+        Ok(2)
+    );
+    assert_eq!(
+        store.push("q".to_string(), bytes(&["c", "d"]), ListEnd::Right),
+        Ok(4)
+    );
+    assert_eq!(
+        store.list_range("q", 0, -1),
+        Ok(bytes(&["b", "a", "c", "d"]))
+    );
+
+    assert_eq!(store.pop("q", ListEnd::Left, 1), Ok(Some(bytes(&["b"]))));
+    assert_eq!(
+        store.pop("q", ListEnd::Right, 2),
+        Ok(Some(bytes(&["d", "c"])))
+    );
+    assert_eq!(store.pop("q", ListEnd::Right, 5), Ok(Some(bytes(&["a"]))));
+
+    // Popping the last item deletes the key
+    assert_eq!(store.key_type("q"), "none");
+    assert_eq!(store.pop("q", ListEnd::Left, 1), Ok(None));
+}
+
+#[test]
+fn test_range_index_and_set() {
+    let store = RedisStore::new();
+    store
+        .push(
+            "l".to_string(),
+            bytes(&["a", "b", "c", "d"]),
+            ListEnd::Right,
+        )
+        .unwrap();
+
+    assert_eq!(store.list_range("l", -2, 100), Ok(bytes(&["c", "d"])));
+    assert_eq!(store.list_range("l", 3, 1), Ok(vec![]));
+    assert_eq!(store.list_range("l", 5, 10), Ok(vec![]));
+    assert_eq!(store.list_len("l"), Ok(4));
+    assert_eq!(store.list_index("l", -1), Ok(Some(b"d".to_vec())));
+    assert_eq!(store.list_index("l", 4), Ok(None));
+
+    assert_eq!(store.list_set("l", -4, b"z".to_vec()), Ok(()));
+    assert_eq!(store.list_index("l", 0), Ok(Some(b"z".to_vec())));
+    assert_eq!(
+        store.list_set("l", 4, b"x".to_vec()),
+        Err(StoreError::IndexOutOfRange)
+    );
+    assert_eq!(
+        store.list_set("missing", 0, b"x".to_vec()),
+        Err(StoreError::NoSuchKey)
+    );
+}
+
+#[test]
+fn test_trim_and_remove() {
+    let store = RedisStore::new();
+    store
+        .push(
+            "l".to_string(),
+            bytes(&["x", "a", "x", "b", "x", "c", "x"]),
+            ListEnd::Right,
+        )
+        .unwrap();
+
+    assert_eq!(store.list_remove("l", 2, b"x"), Ok(2));
+    assert_eq!(
+        store.list_range("l", 0, -1),
+        Ok(bytes(&["a", "b", "x", "c", "x"]))
+    );
+    assert_eq!(store.list_remove("l", -1, b"x"), Ok(1));
+    assert_eq!(
+        store.list_range("l", 0, -1),
+        Ok(bytes(&["a", "b", "x", "c"]))
+    );
+    assert_eq!(store.list_remove("l", 0, b"x"), Ok(1));
+
+    assert_eq!(store.list_trim("l", 1, -1), Ok(()));
+    assert_eq!(store.list_range("l", 0, -1), Ok(bytes(&["b", "c"])));
+    assert_eq!(store.list_trim("l", 5, 10), Ok(()));
+    assert_eq!(store.list_len("l"), Ok(0));
+    assert_eq!(store.key_type("l"), "none");
+}
+
+#[test]
+fn test_move_between_and_within_lists() {
+    let store = RedisStore::new();
+    store
+        .push("src".to_string(), bytes(&["a", "b", "c"]), ListEnd::Right)
+        .unwrap();
+
+    assert_eq!(
+        store.list_move("src", "dst", ListEnd::Right, ListEnd::Left),
+        Ok(Some(b"c".to_vec()))
+    );
+    assert_eq!(store.list_range("dst", 0, -1), Ok(bytes(&["c"])));
+
+    // Rotating a list onto itself
+    assert_eq!(
+        store.list_move("src", "src", ListEnd::Left, ListEnd::Right),
+        Ok(Some(b"a".to_vec()))
+    );
+    assert_eq!(store.list_range("src", 0, -1), Ok(bytes(&["b", "a"])));
+
+    assert_eq!(
+        store.list_move("missing", "dst", ListEnd::Left, ListEnd::Left),
+        Ok(None)
+    );
+}
+
+#[test]
+fn test_lists_are_typed() {
+    let store = RedisStore::new();
+    store.set("s".to_string(), "v".to_string(), None).unwrap();
+    store
+        .push("l".to_string(), bytes(&["a"]), ListEnd::Right)
+        .unwrap();
+
+    assert_eq!(
+        store.push("s".to_string(), bytes(&["a"]), ListEnd::Left),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(store.strlen("l"), Err(StoreError::WrongType));
+    assert_eq!(store.key_type("l"), "list");
+    assert_eq!(store.object_encoding("l"), Some("listpack"));
+
+    // A failed move leaves the source untouched
+    assert_eq!(
+        store.list_move("l", "s", ListEnd::Left, ListEnd::Left),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(store.list_len("l"), Ok(1));
+}
+
+#[test]
+fn test_push_serves_blocked_clients() {
+    use redis_test_simple::types::{BlockAction, PopOutcome, Served};
+
+    let store = RedisStore::new();
+    let PopOutcome::Blocked(mut first) = store
+        .pop_or_block(strings(&["a", "b"]), BlockAction::Pop(ListEnd::Left))
+        .unwrap()
+    else {
+        panic!("expected to block on empty lists");
+    };
+    let PopOutcome::Blocked(mut second) = store
+        .pop_or_block(strings(&["b"]), BlockAction::Pop(ListEnd::Left))
+        .unwrap()
+    else {
+        panic!("expected to block on empty lists");
+    };
+
+    // The item goes to the longest-waiting client and never reaches the list
+    assert_eq!(
+        store.push("b".to_string(), bytes(&["x"]), ListEnd::Right),
+        Ok(1)
+    );
+    let Served::Item(served) = first.receiver.try_recv().unwrap().unwrap() else {
+        panic!("expected a list item");
+    };
+    assert_eq!(
+        (served.key.as_str(), served.value.as_slice()),
+        ("b", &b"x"[..])
+    );
+    assert!(second.receiver.try_recv().is_err());
+    assert_eq!(store.list_len("b"), Ok(0));
+
+    // A client that gave up is skipped, leaving the item in place
+    assert!(store.unblock(second.id));
+    assert!(!store.unblock(first.id));
+    store
+        .push("b".to_string(), bytes(&["y"]), ListEnd::Right)
+        .unwrap();
+    assert_eq!(store.list_len("b"), Ok(1));
+
+    // An available item is popped straight away
+    assert!(matches!(
+        store.pop_or_block(strings(&["a", "b"]), BlockAction::Pop(ListEnd::Left)),
+        Ok(PopOutcome::Ready(popped)) if popped.key == "b" && popped.value == b"y"
+    ));
+}
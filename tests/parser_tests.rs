@@ -1,18 +1,23 @@
-use redis_test_simple::parser::parse_command;
+use redis_test_simple::parser::{
+    parse_command, ProtocolError, RespParser, DEFAULT_PROTO_MAX_BULK_LEN,
+};
 
 #[test]
 fn test_simple_command() {
     // Tests simple SET command: SET key value
     let input = "*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$5\r\nvalue\r\n";
-    let result = parse_command(input);
-    assert_eq!(result, vec!["SET", "key", "value"]);
+    let result = parse_command(input.as_bytes());
+    assert_eq!(
+        result,
+        vec![b"SET".to_vec(), b"key".to_vec(), b"value".to_vec()]
+    );
 }
 
 #[test]
 fn test_empty_array() {
     // Tests empty command array
     let input = "*0\r\n";
-    let result = parse_command(input);
+    let result = parse_command(input.as_bytes());
     assert_eq!(result.len(), 0);
 }
 
@@ -20,16 +25,19 @@ fn test_empty_array() {
 fn test_ping_command() {
     // Tests simple PING command
     let input = "*1\r\n$4\r\nPING\r\n";
-    let result = parse_command(input);
-    assert_eq!(result, vec!["PING"]);
+    let result = parse_command(input.as_bytes());
+    assert_eq!(result, vec![b"PING".to_vec()]);
 }
 
 #[test]
 fn test_special_characters() {
     // Tests handling of special characters in strings
     let input = "*3\r\n$3\r\nSET\r\n$4\r\nkey:\r\n$6\r\nval@ue\r\n";
-    let result = parse_command(input);
-    assert_eq!(result, vec!["SET", "key:", "val@ue"]);
+    let result = parse_command(input.as_bytes());
+    assert_eq!(
+        result,
+        vec![b"SET".to_vec(), b"key:".to_vec(), b"val@ue".to_vec()]
+    );
 }
 
 #[test]
@@ -47,7 +55,7 @@ fn test_malformed_input() {
     ];
 
     for case in test_cases {
-        let result = parse_command(case);
+        let result = parse_command(case.as_bytes());
         assert_eq!(result.len(), 0, "Malformed input should return empty vec");
     }
 }
@@ -56,24 +64,33 @@ fn test_malformed_input() {
 fn test_complex_command() {
     // Tests more complex command with longer strings
     let input = "*5\r\n$4\r\nMGET\r\n$3\r\nkey\r\n$4\r\nkey2\r\n$4\r\nkey3\r\n$4\r\nkey4\r\n";
-    let result = parse_command(input);
-    assert_eq!(result, vec!["MGET", "key", "key2", "key3", "key4"]);
+    let result = parse_command(input.as_bytes());
+    assert_eq!(
+        result,
+        vec![
+            b"MGET".to_vec(),
+            b"key".to_vec(),
+            b"key2".to_vec(),
+            b"key3".to_vec(),
+            b"key4".to_vec()
+        ]
+    );
 }
 
 #[test]
 fn test_empty_strings() {
     // Tests handling of empty strings
     let input = "*3\r\n$3\r\nSET\r\n$0\r\n\r\n$0\r\n\r\n";
-    let result = parse_command(input);
-    assert_eq!(result, vec!["SET", "", ""]);
+    let result = parse_command(input.as_bytes());
+    assert_eq!(result, vec![b"SET".to_vec(), b"".to_vec(), b"".to_vec()]);
 }
 
 #[test]
 fn test_state_transitions() {
     // This test verifies the parser moves through states correctly
     let input = "*2\r\n$4\r\nECHO\r\n$5\r\nhello\r\n";
-    let result = parse_command(input);
-    assert_eq!(result, vec!["ECHO", "hello"]);
+    let result = parse_command(input.as_bytes());
+    assert_eq!(result, vec![b"ECHO".to_vec(), b"hello".to_vec()]);
 
     // We could also test intermediate states if we modify the parser
     // to expose state information for testing
@@ -85,17 +102,141 @@ fn test_consecutive_commands() {
     let input1 = "*1\r\n$4\r\nPING\r\n";
     let input2 = "*2\r\n$4\r\nECHO\r\n$5\r\nhello\r\n";
 
-    let result1 = parse_command(input1);
-    let result2 = parse_command(input2);
+    let result1 = parse_command(input1.as_bytes());
+    let result2 = parse_command(input2.as_bytes());
 
-    assert_eq!(result1, vec!["PING"]);
-    assert_eq!(result2, vec!["ECHO", "hello"]);
+    assert_eq!(result1, vec![b"PING".to_vec()]);
+    assert_eq!(result2, vec![b"ECHO".to_vec(), b"hello".to_vec()]);
 }
 
 #[test]
 fn test_whitespace_handling() {
     // Tests handling of whitespace in values
     let input = "*3\r\n$3\r\nSET\r\n$5\r\nkey 1\r\n$7\r\nvalue 1\r\n";
-    let result = parse_command(input);
-    assert_eq!(result, vec!["SET", "key 1", "value 1"]);
+    let result = parse_command(input.as_bytes());
+    assert_eq!(
+        result,
+        vec![b"SET".to_vec(), b"key 1".to_vec(), b"value 1".to_vec()]
+    );
+}
+
+#[test]
+fn test_embedded_crlf_in_bulk() {
+    // Tests that bulk lengths are honoured as exact byte counts
+    let input = "*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$7\r\nab\r\ncde\r\n";
+    let result = parse_command(input.as_bytes());
+    assert_eq!(
+        result,
+        vec![b"SET".to_vec(), b"key".to_vec(), b"ab\r\ncde".to_vec()]
+    );
+}
+
+#[test]
+fn test_binary_payload() {
+    // Tests that non-UTF-8 bytes pass through untouched
+    let mut input = b"*3\r\n$3\r\nSET\r\n$4\r\nblob\r\n$4\r\n".to_vec();
+    input.extend_from_slice(&[0xff, 0x00, 0xc3, 0x28]);
+    input.extend_from_slice(b"\r\n");
+    let result = parse_command(&input);
+    assert_eq!(result[2], vec![0xff, 0x00, 0xc3, 0x28]);
+}
+
+#[test]
+fn test_multibyte_utf8_length() {
+    // Tests that lengths count bytes, not chars
+    let input = "*2\r\n$4\r\nECHO\r\n$4\r\nhé!\r\n";
+    let result = parse_command(input.as_bytes());
+    assert_eq!(result[1], "hé!".as_bytes().to_vec());
+}
+
+#[test]
+fn test_incremental_frame_across_reads() {
+    // Tests that a frame split across reads is resumed once the rest arrives
+    let mut parser = RespParser::new(DEFAULT_PROTO_MAX_BULK_LEN);
+    let input = b"*3\r\n$3\r\nSET\r\n$3\r\nkey\r\n$11\r\nhello world\r\n";
+    let mut buffer = Vec::new();
+    let mut result = None;
+
+    for chunk in input.chunks(5) {
+        buffer.extend_from_slice(chunk);
+        let (consumed, frame) = parser.parse(&buffer).unwrap();
+        buffer.drain(..consumed);
+        if frame.is_some() {
+            result = frame;
+        }
+    }
+
+    assert!(buffer.is_empty());
+    assert_eq!(
+        result.unwrap(),
+        vec![b"SET".to_vec(), b"key".to_vec(), b"hello world".to_vec()]
+    );
+}
+
+#[test]
+fn test_incomplete_bulk_needs_more_data() {
+    // Tests that a partially received bulk string is not consumed
+    let mut parser = RespParser::new(DEFAULT_PROTO_MAX_BULK_LEN);
+    let (consumed, frame) = parser.parse(b"*2\r\n$4\r\nECHO\r\n$5\r\nhel").unwrap();
+    assert_eq!(consumed, b"*2\r\n$4\r\nECHO\r\n$5\r\n".len());
+    assert!(frame.is_none());
+
+    let (consumed, frame) = parser.parse(b"hello\r\n").unwrap();
+    assert_eq!(consumed, 7);
+    assert_eq!(frame.unwrap(), vec![b"ECHO".to_vec(), b"hello".to_vec()]);
+}
+
+#[test]
+fn test_max_bulk_len_guard() {
+    // Tests that bulk strings above the configured limit are rejected
+    let mut parser = RespParser::new(4);
+    let result = parser.parse(b"*2\r\n$4\r\nECHO\r\n$5\r\nhello\r\n");
+    assert_eq!(result, Err(ProtocolError::InvalidBulkLength));
+}
+
+#[test]
+fn test_inline_command() {
+    // Tests telnet-style inline commands
+    assert_eq!(parse_command(b"PING\r\n"), vec![b"PING".to_vec()]);
+    assert_eq!(
+        parse_command(b"SET  key   value\n"),
+        vec![b"SET".to_vec(), b"key".to_vec(), b"value".to_vec()]
+    );
+}
+
+#[test]
+fn test_inline_quoting() {
+    // Tests quoting and escape rules for inline arguments
+    let result = parse_command(b"SET \"a key\" 'it\\'s' \"tab\\there\\x41\"\r\n");
+    assert_eq!(
+        result,
+        vec![
+            b"SET".to_vec(),
+            b"a key".to_vec(),
+            b"it's".to_vec(),
+            b"tab\there\x41".to_vec()
+        ]
+    );
+}
+
+#[test]
+fn test_inline_unbalanced_quotes() {
+    // Tests that unterminated quotes are a protocol error
+    let mut parser = RespParser::new(DEFAULT_PROTO_MAX_BULK_LEN);
+    assert_eq!(
+        parser.parse(b"SET \"oops\r\n"),
+        Err(ProtocolError::UnbalancedQuotes)
+    );
+}
+
+#[test]
+fn test_inline_then_multibulk() {
+    // Tests that inline and multibulk frames can be mixed in one buffer
+    let mut parser = RespParser::new(DEFAULT_PROTO_MAX_BULK_LEN);
+    let input = b"PING\r\n*1\r\n$4\r\nPING\r\n";
+
+    let (consumed, frame) = parser.parse(input).unwrap();
+    assert_eq!(frame.unwrap(), vec![b"PING".to_vec()]);
+    let (_, frame) = parser.parse(&input[consumed..]).unwrap();
+    assert_eq!(frame.unwrap(), vec![b"PING".to_vec()]);
 }
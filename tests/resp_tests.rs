@@ -0,0 +1,104 @@
+use redis_test_simple::resp::{Protocol, Reply};
+use serde_json::json;
+
+#[test]
+fn test_encode_scalars() {
+    assert_eq!(Reply::ok().to_bytes(Protocol::Resp2), b"+OK\r\n");
+    assert_eq!(
+        Reply::error("ERR boom").to_bytes(Protocol::Resp2),
+        b"-ERR boom\r\n"
+    );
+    assert_eq!(Reply::Integer(-42).to_bytes(Protocol::Resp2), b":-42\r\n");
+    assert_eq!(Reply::Null.to_bytes(Protocol::Resp2), b"$-1\r\n");
+    assert_eq!(Reply::NullArray.to_bytes(Protocol::Resp2), b"*-1\r\n");
+}
+
+#[test]
+fn test_encode_bulk_is_binary_safe() {
+    assert_eq!(
+        Reply::bulk("a\r\nb").to_bytes(Protocol::Resp2),
+        b"$4\r\na\r\nb\r\n"
+    );
+    assert_eq!(Reply::bulk("").to_bytes(Protocol::Resp2), b"$0\r\n\r\n");
+}
+
+#[test]
+fn test_simple_strings_cannot_break_framing() {
+    assert_eq!(
+        Reply::Simple("bad\r\nline".to_string()).to_bytes(Protocol::Resp2),
+        b"+bad  line\r\n"
+    );
+}
+
+#[test]
+fn test_encode_nested_arrays() {
+    let reply = Reply::Array(vec![
+        Reply::bulk("a"),
+        Reply::Array(vec![Reply::Integer(1), Reply::Null]),
+        Reply::Array(vec![]),
+    ]);
+    assert_eq!(
+        reply.to_bytes(Protocol::Resp2),
+        b"*3\r\n$1\r\na\r\n*2\r\n:1\r\n$-1\r\n*0\r\n"
+    );
+}
+
+#[test]
+fn test_encode_resp3_types() {
+    let p = Protocol::Resp3;
+    assert_eq!(Reply::Null.to_bytes(p), b"_\r\n");
+    assert_eq!(Reply::NullArray.to_bytes(p), b"_\r\n");
+    assert_eq!(Reply::Boolean(true).to_bytes(p), b"#t\r\n");
+    assert_eq!(Reply::Double(1.5).to_bytes(p), b",1.5\r\n");
+    assert_eq!(Reply::Double(f64::INFINITY).to_bytes(p), b",inf\r\n");
+    assert_eq!(
+        Reply::Set(vec![Reply::Integer(1)]).to_bytes(p),
+        b"~1\r\n:1\r\n"
+    );
+    assert_eq!(
+        Reply::Map(vec![(Reply::bulk("a"), Reply::Integer(1))]).to_bytes(p),
+        b"%1\r\n$1\r\na\r\n:1\r\n"
+    );
+    assert_eq!(
+        Reply::Verbatim {
+            format: "txt".to_string(),
+            text: "hi".to_string()
+        }
+        .to_bytes(p),
+        b"=6\r\ntxt:hi\r\n"
+    );
+    assert_eq!(
+        Reply::Push(vec![Reply::bulk("x")]).to_bytes(p),
+        b">1\r\n$1\r\nx\r\n"
+    );
+}
+
+#[test]
+fn test_resp3_types_downgrade_on_resp2() {
+    let p = Protocol::Resp2;
+    assert_eq!(Reply::Boolean(false).to_bytes(p), b":0\r\n");
+    assert_eq!(Reply::Double(2.5).to_bytes(p), b"$3\r\n2.5\r\n");
+    assert_eq!(
+        Reply::Map(vec![(Reply::bulk("a"), Reply::Integer(1))]).to_bytes(p),
+        b"*2\r\n$1\r\na\r\n:1\r\n"
+    );
+    assert_eq!(
+        Reply::Set(vec![Reply::Integer(1)]).to_bytes(p),
+        b"*1\r\n:1\r\n"
+    );
+}
+
+#[test]
+fn test_from_json() {
+    let value = json!([{"name": "John", "age": 30, "score": 1.5, "admin": false}]);
+    let reply = Reply::from_json(&value);
+    assert_eq!(
+        reply,
+        Reply::Array(vec![Reply::Map(vec![
+            (Reply::bulk("admin"), Reply::Boolean(false)),
+            (Reply::bulk("age"), Reply::Integer(30)),
+            (Reply::bulk("name"), Reply::bulk("John")),
+            (Reply::bulk("score"), Reply::Double(1.5)),
+        ])])
+    );
+}
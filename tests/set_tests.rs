@@ -0,0 +1,141 @@
+use redis_test_simple::store::RedisStore;
+use redis_test_simple::types::{SetOp, StoreError};
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+fn bytes(items: &[&str]) -> Vec<Vec<u8>> {
+    items.iter().map(|s| s.as_bytes().to_vec()).collect()
+}
+
+fn sorted(mut items: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+    items.sort();
+    items
+}
+
+#[test]
+fn test_add_remove_and_membership() {
+    let store = RedisStore::new();
+    assert_eq!(
+        store.set_add("s".to_string(), bytes(&["a", "b", "a"])),
+        Ok(2)
+    );
+    assert_eq!(store.set_add("s".to_string(), bytes(&["b", "c"])), Ok(1));
+
+    assert_eq!(store.set_card("s"), Ok(3));
+    assert_eq!(store.set_is_member("s", b"c"), Ok(true));
+    assert_eq!(store.set_is_member("s", b"z"), Ok(false));
+    assert_eq!(
+        store.set_members("s").map(sorted),
+        Ok(bytes(&["a", "b", "c"]))
+    );
+
+    assert_eq!(store.set_remove("s", &bytes(&["a", "z"])), Ok(1));
+    assert_eq!(store.set_remove("s", &bytes(&["b", "c"])), Ok(2));
+    // Removing the last member deletes the key
+    assert_eq!(store.key_type("s"), "none");
+    assert_eq!(store.set_card("s"), Ok(0));
+    assert_eq!(store.set_members("s"), Ok(Vec::new()));
+}
+
+#[test]
+fn test_set_algebra() {
+    let store = RedisStore::new();
+    store
+        .set_add("a".to_string(), bytes(&["1", "2", "3"]))
+        .unwrap();
+    store
+        .set_add("b".to_string(), bytes(&["2", "3", "4"]))
+        .unwrap();
+    store.set_add("c".to_string(), bytes(&["3", "5"])).unwrap();
+    let keys = strings(&["a", "b", "c"]);
+
+    assert_eq!(
+        store.set_combine(&keys, SetOp::Inter).map(sorted),
+        Ok(bytes(&["3"]))
+    );
+    assert_eq!(
+        store.set_combine(&keys, SetOp::Union).map(sorted),
+        Ok(bytes(&["1", "2", "3", "4", "5"]))
+    );
+    assert_eq!(
+        store.set_combine(&keys, SetOp::Diff).map(sorted),
+        Ok(bytes(&["1"]))
+    );
+
+    // A missing key is the empty set
+    let with_missing = strings(&["a", "missing"]);
+    assert_eq!(
+        store.set_combine(&with_missing, SetOp::Inter),
+        Ok(Vec::new())
+    );
+    assert_eq!(
+        store.set_combine(&with_missing, SetOp::Diff).map(sorted),
+        Ok(bytes(&["1", "2", "3"]))
+    );
+}
+
+#[test]
+fn test_store_variants() {
+    let store = RedisStore::new();
+    store.set_add("a".to_string(), bytes(&["1", "2"])).unwrap();
+    store.set_add("b".to_string(), bytes(&["2", "3"])).unwrap();
+    store
+        .set("dest".to_string(), "text".to_string(), None)
+        .unwrap();
+
+    // The destination is overwritten whatever it held
+    assert_eq!(
+        store.set_combine_store("dest".to_string(), &strings(&["a", "b"]), SetOp::Union),
+        Ok(3)
+    );
+    assert_eq!(store.key_type("dest"), "set");
+    assert_eq!(store.object_encoding("dest"), Some("intset"));
+
+    // A source may also be the destination
+    assert_eq!(
+        store.set_combine_store("a".to_string(), &strings(&["a", "b"]), SetOp::Inter),
+        Ok(1)
+    );
+    assert_eq!(store.set_members("a"), Ok(bytes(&["2"])));
+
+    // An empty result deletes the destination
+    assert_eq!(
+        store.set_combine_store(
+            "dest".to_string(),
+            &strings(&["a", "missing"]),
+            SetOp::Inter
+        ),
+        Ok(0)
+    );
+    assert_eq!(store.key_type("dest"), "none");
+}
+
+#[test]
+fn test_sets_are_typed() {
+    let store = RedisStore::new();
+    store.set("str".to_string(), "v".to_string(), None).unwrap();
+    store.set_add("s".to_string(), bytes(&["x"])).unwrap();
+
+    assert_eq!(
+        store.set_add("str".to_string(), bytes(&["a"])),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(store.strlen("s"), Err(StoreError::WrongType));
+    // Any wrongly typed input fails the whole operation, and nothing is stored
+    assert_eq!(
+        store.set_combine(&strings(&["missing", "str"]), SetOp::Inter),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(
+        store.set_combine_store("dest".to_string(), &strings(&["s", "str"]), SetOp::Union),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(store.key_type("dest"), "none");
+
+    assert_eq!(store.object_encoding("s"), Some("listpack"));
+    let many: Vec<Vec<u8>> = (0..200).map(|i| format!("m{}", i).into_bytes()).collect();
+    store.set_add("s".to_string(), many).unwrap();
+    assert_eq!(store.object_encoding("s"), Some("hashtable"));
+}
@@ -14,7 +14,7 @@ fn test_basic_set_get() {
         .unwrap();
 
     match store.get("test_key") {
-        RedisGetResult::Value(val) => assert_eq!(val, "test_value"),
+        RedisGetResult::Value(val) => assert_eq!(val, b"test_value"),
         _ => panic!("Expected Value variant"),
     }
 }
@@ -39,7 +39,7 @@ fn test_search_params() {
 
     // test for a get with no search params
     match store.get("test_key") {
-        RedisGetResult::Value(val) => assert_eq!(val, "[{\"name\":\"John\",\"age\":30,\"city\":\"New York\"},{\"name\":\"Jane\",\"age\":25,\"city\":\"Chicago\"}]"),
+        RedisGetResult::Value(val) => assert_eq!(val, b"[{\"name\":\"John\",\"age\":30,\"city\":\"New York\"},{\"name\":\"Jane\",\"age\":25,\"city\":\"Chicago\"}]"),
         _ => panic!("Expected Value variant"),
     }
 
@@ -47,7 +47,7 @@ fn test_search_params() {
     match store.get("test_key?name=John") {
         RedisGetResult::Value(val) => {
             // Parse both JSONs to Value objects
-            let actual: Value = serde_json::from_str(&val).unwrap();
+            let actual: Value = serde_json::from_slice(&val).unwrap();
             let expected: Value =
                 serde_json::from_str("[{\"name\":\"John\",\"age\":30,\"city\":\"New York\"}]")
                     .unwrap();
@@ -74,7 +74,7 @@ fn test_expiration() {
 
     // Should exist immediately
     match store.get("expire_key") {
-        RedisGetResult::Value(val) => assert_eq!(val, "expire_value"),
+        RedisGetResult::Value(val) => assert_eq!(val, b"expire_value"),
         _ => panic!("Expected Value variant immediately after setting"),
     }
 
@@ -104,7 +104,7 @@ fn test_json_append() {
 
     // Check final array
     match store.get("json_key") {
-        RedisGetResult::Value(val) => assert_eq!(val, "[1,2,3,4]"),
+        RedisGetResult::Value(val) => assert_eq!(val, b"[1,2,3,4]"),
         _ => panic!("Expected Value variant with appended array"),
     }
 }
@@ -119,7 +119,7 @@ fn test_json_append_to_nonexistent_key() {
         .unwrap();
 
     match store.get("new_json_key") {
-        RedisGetResult::Value(val) => assert_eq!(val, "[1]"),
+        RedisGetResult::Value(val) => assert_eq!(val, b"[1]"),
         _ => panic!("Expected Value variant with new array"),
     }
 }
@@ -138,3 +138,555 @@ fn test_invalid_json_append() {
 
     assert!(result.is_err());
 }
+
+#[test]
+fn test_set_nx_xx_conditions() {
+    use redis_test_simple::types::{SetCondition, SetOptions};
+    let store = RedisStore::new();
+
+    let nx = SetOptions {
+        condition: SetCondition::IfNotExists,
+        ..Default::default()
+    };
+    let xx = SetOptions {
+        condition: SetCondition::IfExists,
+        ..Default::default()
+    };
+
+    // XX on a missing key does nothing
+    assert!(
+        !store
+            .set_with_options("k".to_string(), "a".to_string(), &xx)
+            .unwrap()
+            .written
+    );
+    assert!(matches!(store.get("k"), RedisGetResult::None));
+
+    // NX writes once, then refuses
+    assert!(
+        store
+            .set_with_options("k".to_string(), "a".to_string(), &nx)
+            .unwrap()
+            .written
+    );
+    let outcome = store
+        .set_with_options("k".to_string(), "b".to_string(), &nx)
+        .unwrap();
+    assert!(!outcome.written);
+    assert_eq!(outcome.previous, Some(b"a".to_vec()));
+
+    // XX overwrites and reports the previous value
+    let outcome = store
+        .set_with_options("k".to_string(), "c".to_string(), &xx)
+        .unwrap();
+    assert!(outcome.written);
+    assert_eq!(outcome.previous, Some(b"a".to_vec()));
+}
+
+#[test]
+fn test_set_keepttl_and_expired_keys() {
+    use redis_test_simple::types::{SetCondition, SetExpiry, SetOptions};
+    let store = RedisStore::new();
+
+    store
+        .set("k".to_string(), "a".to_string(), Some(100))
+        .unwrap();
+    let keep = SetOptions {
+        expiry: SetExpiry::Keep,
+        ..Default::default()
+    };
+    store
+        .set_with_options("k".to_string(), "b".to_string(), &keep)
+        .unwrap();
+
+    // KEEPTTL keeps the original 100ms expiry
+    sleep(Duration::from_millis(150));
+    assert!(matches!(store.get("k"), RedisGetResult::Expired));
+
+    // An expired key counts as absent for NX
+    store
+        .set("j".to_string(), "a".to_string(), Some(50))
+        .unwrap();
+    sleep(Duration::from_millis(100));
+    let nx = SetOptions {
+        condition: SetCondition::IfNotExists,
+        ..Default::default()
+    };
+    let outcome = store
+        .set_with_options("j".to_string(), "b".to_string(), &nx)
+        .unwrap();
+    assert!(outcome.written);
+    assert_eq!(outcome.previous, None);
+}
+
+#[test]
+fn test_delete_and_exists() {
+    let store = RedisStore::new();
+    store.set("a".to_string(), "1".to_string(), None).unwrap();
+    store.set("b".to_string(), "2".to_string(), None).unwrap();
+
+    let keys = vec!["a".to_string(), "a".to_string(), "missing".to_string()];
+    assert_eq!(store.exists(&keys), 2);
+
+    assert_eq!(store.delete(&["a".to_string(), "missing".to_string()]), 1);
+    assert!(matches!(store.get("a"), RedisGetResult::None));
+
+    // UNLINK of a large value returns immediately and frees it elsewhere
+    store
+        .set("big".to_string(), "x".repeat(1 << 20), None)
+        .unwrap();
+    assert_eq!(store.unlink(&["big".to_string(), "b".to_string()]), 2);
+    assert_eq!(store.exists(&["big".to_string(), "b".to_string()]), 0);
+}
+
+#[test]
+fn test_rename_and_copy() {
+    use redis_test_simple::types::StoreError;
+    let store = RedisStore::new();
+    store.set("a".to_string(), "1".to_string(), None).unwrap();
+    store.set("b".to_string(), "2".to_string(), None).unwrap();
+
+    assert_eq!(
+        store.rename("missing", "x", false),
+        Err(StoreError::NoSuchKey)
+    );
+    assert_eq!(store.rename("a", "b", true), Ok(false));
+    assert_eq!(store.rename("a", "c", false), Ok(true));
+    assert!(matches!(store.get("a"), RedisGetResult::None));
+    assert!(matches!(store.get("c"), RedisGetResult::Value(v) if v == b"1"));
+
+    assert_eq!(store.copy("c", "b", false), Ok(false));
+    assert_eq!(store.copy("c", "b", true), Ok(true));
+    assert_eq!(store.copy("c", "c", true), Err(StoreError::SameObject));
+    assert!(matches!(store.get("b"), RedisGetResult::Value(v) if v == b"1"));
+    assert_eq!(store.key_type("b"), "string");
+    assert_eq!(store.key_type("a"), "none");
+}
+
+#[test]
+fn test_expire_ttl_and_persist() {
+    use redis_test_simple::types::{ExpireCondition, ExpiryComparison, TtlRequirement, TtlState};
+    let condition = |require, compare| ExpireCondition { require, compare };
+    let store = RedisStore::new();
+    let later = std::time::SystemTime::now() + Duration::from_secs(100);
+    let sooner = std::time::SystemTime::now() + Duration::from_secs(10);
+
+    assert_eq!(store.ttl("k"), TtlState::Missing);
+    assert!(!store.expire("k", later, ExpireCondition::default()));
+
+    store.set("k".to_string(), "v".to_string(), None).unwrap();
+    assert_eq!(store.ttl("k"), TtlState::Persistent);
+
+    // GT never applies to a key without a TTL; XX needs an existing TTL
+    let gt = condition(None, Some(ExpiryComparison::Greater));
+    let lt = condition(None, Some(ExpiryComparison::Less));
+    let xx = Some(TtlRequirement::HasTtl);
+    assert!(!store.expire("k", later, gt));
+    assert!(!store.expire("k", later, condition(xx, None)));
+    // LT alone treats no TTL as infinite, but XX still requires one
+    assert!(!store.expire("k", later, condition(xx, lt.compare)));
+    assert_eq!(store.ttl("k"), TtlState::Persistent);
+    assert!(store.expire("k", later, condition(Some(TtlRequirement::NoTtl), None)));
+    assert_eq!(store.ttl("k"), TtlState::ExpiresAt(later));
+
+    assert!(!store.expire("k", sooner, gt));
+    assert!(store.expire("k", sooner, condition(xx, lt.compare)));
+
+    assert!(store.persist("k"));
+    assert!(!store.persist("k"));
+    assert_eq!(store.ttl("k"), TtlState::Persistent);
+
+    // An expiry in the past deletes the key
+    let past = std::time::SystemTime::now() - Duration::from_secs(1);
+    assert!(store.expire("k", past, ExpireCondition::default()));
+    assert!(matches!(store.get("k"), RedisGetResult::None));
+}
+
+#[test]
+fn test_active_expire_cycle_evicts_unread_keys() {
+    let store = RedisStore::new();
+
+    for i in 0..200 {
+        store
+            .set(format!("short:{}", i), "v".to_string(), Some(10))
+            .unwrap();
+    }
+    for i in 0..50 {
+        store
+            .set(format!("long:{}", i), "v".to_string(), Some(60_000))
+            .unwrap();
+    }
+    store
+        .set("forever".to_string(), "v".to_string(), None)
+        .unwrap();
+    sleep(Duration::from_millis(50));
+
+    // Sampling is probabilistic, so run cycles until the stale keys are gone
+    let mut evicted = 0;
+    for _ in 0..1000 {
+        evicted += store.active_expire_cycle(Duration::from_millis(25));
+        if evicted == 200 {
+            break;
+        }
+    }
+
+    assert_eq!(evicted, 200);
+    assert_eq!(store.expire_stats().expired_keys, 200);
+    assert!(matches!(store.get("long:0"), RedisGetResult::Value(_)));
+    assert!(matches!(store.get("forever"), RedisGetResult::Value(_)));
+}
+
+#[test]
+fn test_search_on_expired_key() {
+    let store = RedisStore::new();
+    store
+        .set(
+            "users".to_string(),
+            "[{\"name\":\"John\",\"age\":30}]".to_string(),
+            Some(50),
+        )
+        .unwrap();
+
+    assert!(matches!(
+        store.get("users?age_gt=3"),
+        RedisGetResult::Value(_)
+    ));
+    sleep(Duration::from_millis(100));
+
+    // The search path must not return data from an expired key
+    assert!(matches!(
+        store.get("users?age_gt=3"),
+        RedisGetResult::Expired
+    ));
+    assert!(matches!(store.get("users"), RedisGetResult::None));
+}
+
+#[test]
+fn test_append_to_expired_array_starts_fresh() {
+    let store = RedisStore::new();
+    store
+        .set("json_key".to_string(), "[1,2,3]".to_string(), Some(50))
+        .unwrap();
+    sleep(Duration::from_millis(100));
+
+    store
+        .append("json_key".to_string(), "4".to_string())
+        .unwrap();
+
+    match store.get("json_key") {
+        RedisGetResult::Value(val) => assert_eq!(val, b"[4]"),
+        _ => panic!("Expected a new array after the old one expired"),
+    }
+    assert_eq!(
+        store.ttl("json_key"),
+        redis_test_simple::types::TtlState::Persistent
+    );
+}
+
+#[test]
+fn test_incr_by() {
+    use redis_test_simple::types::StoreError;
+    let store = RedisStore::new();
+
+    assert_eq!(store.incr_by("counter".to_string(), 1), Ok(1));
+    assert_eq!(store.incr_by("counter".to_string(), 41), Ok(42));
+    assert_eq!(store.incr_by("counter".to_string(), -50), Ok(-8));
+
+    store
+        .set("max".to_string(), i64::MAX.to_string(), None)
+        .unwrap();
+    assert_eq!(
+        store.incr_by("max".to_string(), 1),
+        Err(StoreError::Overflow)
+    );
+
+    store
+        .set("text".to_string(), "abc".to_string(), None)
+        .unwrap();
+    assert_eq!(
+        store.incr_by("text".to_string(), 1),
+        Err(StoreError::NotInteger)
+    );
+}
+
+#[test]
+fn test_incr_by_float_formats_like_redis() {
+    let store = RedisStore::new();
+
+    store.set("f".to_string(), "0.1".to_string(), None).unwrap();
+    assert_eq!(
+        store.incr_by_float("f".to_string(), 0.2),
+        Ok("0.3".to_string())
+    );
+    assert_eq!(
+        store.incr_by_float("f".to_string(), -0.3),
+        Ok("0".to_string())
+    );
+    assert_eq!(
+        store.incr_by_float("f".to_string(), 5.0e3),
+        Ok("5000".to_string())
+    );
+    assert_eq!(
+        store.incr_by_float("f".to_string(), 1.0 / 3.0),
+        Ok("5000.3333333333333".to_string())
+    );
+}
+
+#[test]
+fn test_incr_by_float_keeps_ttl() {
+    use redis_test_simple::types::{StoreError, TtlState};
+    let store = RedisStore::new();
+
+    store
+        .set("f".to_string(), "10.5".to_string(), Some(60_000))
+        .unwrap();
+    assert_eq!(
+        store.incr_by_float("f".to_string(), 0.1),
+        Ok("10.6".to_string())
+    );
+    assert!(matches!(store.ttl("f"), TtlState::ExpiresAt(_)));
+    assert_eq!(
+        store.incr_by_float("f".to_string(), f64::MAX),
+        Ok("1.7976931348623157e+308".to_string())
+    );
+    assert_eq!(
+        store.incr_by_float("f".to_string(), f64::MAX),
+        Err(StoreError::NanOrInfinity)
+    );
+}
+
+#[test]
+fn test_get_many_and_set_many() {
+    let store = RedisStore::new();
+
+    store.set_many(
+        vec![
+            ("a".to_string(), b"1".to_vec()),
+            ("b".to_string(), b"2".to_vec()),
+        ],
+        false,
+    );
+    assert_eq!(
+        store.get_many(&["a".to_string(), "missing".to_string(), "b".to_string()]),
+        vec![Some(b"1".to_vec()), None, Some(b"2".to_vec())]
+    );
+
+    // MSETNX semantics: one existing key blocks the whole write
+    let written = store.set_many(
+        vec![
+            ("b".to_string(), b"x".to_vec()),
+            ("c".to_string(), b"3".to_vec()),
+        ],
+        true,
+    );
+    assert!(!written);
+    assert_eq!(store.exists(&["c".to_string()]), 0);
+}
+
+#[test]
+fn test_scan_cursor_survives_inserts() {
+    let store = RedisStore::new();
+    for i in 0..100 {
+        store
+            .set(format!("t1:key{}", i), "v".to_string(), None)
+            .unwrap();
+        store
+            .set(format!("t2:key{}", i), "v".to_string(), None)
+            .unwrap();
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    let mut cursor = 0;
+    let mut round = 0;
+    loop {
+        let (next, keys) = store.scan("t1:", cursor, 7, None);
+        for key in keys {
+            assert!(key.starts_with("t1:"));
+            assert!(seen.insert(key), "key returned twice");
+        }
+        // Grow the map during the first few calls so the HashMap rehashes
+        let batch = if round < 5 { 200 } else { 0 };
+        for i in 0..batch {
+            store
+                .set(format!("t1:new{}:{}", round, i), "v".to_string(), None)
+                .unwrap();
+        }
+        round += 1;
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+    }
+
+    for i in 0..100 {
+        assert!(seen.contains(&format!("t1:key{}", i)));
+    }
+}
+
+#[test]
+fn test_keys_by_prefix() {
+    let store = RedisStore::new();
+    store
+        .set("a:b:c".to_string(), "1".to_string(), None)
+        .unwrap();
+    store.set("a:x".to_string(), "1".to_string(), None).unwrap();
+    store
+        .set("ab:y".to_string(), "1".to_string(), None)
+        .unwrap();
+
+    // A tenant name containing ':' still only sees its own keys
+    assert_eq!(store.keys("a:b:"), vec!["a:b:c".to_string()]);
+    let mut keys = store.keys("a:");
+    keys.sort();
+    assert_eq!(keys, vec!["a:b:c".to_string(), "a:x".to_string()]);
+}
+
+#[test]
+fn test_get_range_offsets() {
+    let store = RedisStore::new();
+    store
+        .set("k".to_string(), "This is a string".to_string(), None)
+        .unwrap();
+
+    assert_eq!(store.get_range("k", 0, 3), Ok(b"This".to_vec()));
+    assert_eq!(store.get_range("k", -3, -1), Ok(b"ing".to_vec()));
+    assert_eq!(
+        store.get_range("k", 0, -1),
+        Ok(b"This is a string".to_vec())
+    );
+    assert_eq!(store.get_range("k", 10, 100), Ok(b"string".to_vec()));
+    assert_eq!(store.get_range("k", 5, 3), Ok(b"".to_vec()));
+    assert_eq!(store.get_range("k", -1, -5), Ok(b"".to_vec()));
+    assert_eq!(store.get_range("missing", 0, -1), Ok(b"".to_vec()));
+    assert_eq!(store.strlen("k"), Ok(16));
+    assert_eq!(store.strlen("missing"), Ok(0));
+}
+
+#[test]
+fn test_set_range_pads_and_keeps_ttl() {
+    use redis_test_simple::types::TtlState;
+
+    let store = RedisStore::new();
+    assert_eq!(store.set_range("k".to_string(), 3, b"abc"), Ok(6));
+    assert_eq!(store.get_range("k", 0, -1), Ok(b"\0\0\0abc".to_vec()));
+
+    store
+        .set("t".to_string(), "Hello World".to_string(), Some(60_000))
+        .unwrap();
+    assert_eq!(store.set_range("t".to_string(), 6, b"Redis"), Ok(11));
+    assert!(matches!(store.get("t"), RedisGetResult::Value(v) if v == b"Hello Redis"));
+    assert!(matches!(store.ttl("t"), TtlState::ExpiresAt(_)));
+
+    // An empty write reports the length without creating the key
+    assert_eq!(store.set_range("none".to_string(), 5, b""), Ok(0));
+    assert_eq!(store.exists(&["none".to_string()]), 0);
+}
+
+#[test]
+fn test_set_range_writes_exact_bytes() {
+    let store = RedisStore::new();
+    assert_eq!(store.set_range("k".to_string(), 1, b"\xff"), Ok(2));
+    assert_eq!(store.get_range("k", 0, -1), Ok(b"\0\xff".to_vec()));
+    assert_eq!(store.strlen("k"), Ok(2));
+
+    // Overwriting half of a multibyte character keeps the other half as is
+    store
+        .set("u".to_string(), "h\u{e9}llo".to_string(), None)
+        .unwrap();
+    assert_eq!(store.set_range("u".to_string(), 2, b"\xff"), Ok(6));
+    assert_eq!(store.get_range("u", 0, -1), Ok(b"h\xc3\xffllo".to_vec()));
+    assert_eq!(store.strlen("u"), Ok(6));
+}
+
+#[test]
+fn test_get_del_and_get_ex() {
+    use redis_test_simple::types::{SetExpiry, TtlState};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let store = RedisStore::new();
+    store.set("k".to_string(), "v".to_string(), None).unwrap();
+    assert_eq!(store.get_del("k"), Ok(Some(b"v".to_vec())));
+    assert_eq!(store.get_del("k"), Ok(None));
+
+    store.set("k".to_string(), "v".to_string(), None).unwrap();
+    let at = SystemTime::now() + Duration::from_secs(60);
+    assert_eq!(
+        store.get_ex("k", SetExpiry::At(at)),
+        Ok(Some(b"v".to_vec()))
+    );
+    assert_eq!(store.ttl("k"), TtlState::ExpiresAt(at));
+    assert_eq!(store.get_ex("k", SetExpiry::Keep), Ok(Some(b"v".to_vec())));
+    assert_eq!(store.ttl("k"), TtlState::ExpiresAt(at));
+    assert_eq!(
+        store.get_ex("k", SetExpiry::Persist),
+        Ok(Some(b"v".to_vec()))
+    );
+    assert_eq!(store.ttl("k"), TtlState::Persistent);
+
+    // A past instant returns the value one last time
+    assert_eq!(
+        store.get_ex("k", SetExpiry::At(UNIX_EPOCH)),
+        Ok(Some(b"v".to_vec()))
+    );
+    assert_eq!(store.ttl("k"), TtlState::Missing);
+}
+
+#[test]
+fn test_json_values_are_typed() {
+    use redis_test_simple::types::{SetOptions, StoreError};
+
+    let store = RedisStore::new();
+    store
+        .append("doc".to_string(), "{\"a\":1}".to_string())
+        .unwrap();
+    assert_eq!(store.key_type("doc"), "ReJSON-RL");
+    assert_eq!(store.object_encoding("doc"), Some("json"));
+    assert!(matches!(store.get("doc"), RedisGetResult::Value(v) if v == b"[{\"a\":1}]"));
+
+    // String-only operations refuse the JSON document
+    assert_eq!(
+        store.incr_by("doc".to_string(), 1),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(store.strlen("doc"), Err(StoreError::WrongType));
+    assert_eq!(
+        store.set_range("doc".to_string(), 0, b"x"),
+        Err(StoreError::WrongType)
+    );
+
+    // SET ... GET reads it like GET does, then replaces it with a string
+    let get = SetOptions {
+        get: true,
+        ..Default::default()
+    };
+    let outcome = store
+        .set_with_options("doc".to_string(), "s".to_string(), &get)
+        .unwrap();
+    assert_eq!(outcome.previous, Some(b"[{\"a\":1}]".to_vec()));
+    assert_eq!(store.key_type("doc"), "string");
+}
+
+#[test]
+fn test_append_leaves_non_array_string_alone() {
+    let store = RedisStore::new();
+    store.set("k".to_string(), "5".to_string(), None).unwrap();
+    assert!(store.append("k".to_string(), "1".to_string()).is_err());
+    assert_eq!(store.key_type("k"), "string");
+    assert_eq!(store.object_encoding("k"), Some("int"));
+}
+
+#[test]
+fn test_string_encodings() {
+    let store = RedisStore::new();
+    store
+        .set("int".to_string(), "123".to_string(), None)
+        .unwrap();
+    store
+        .set("lead".to_string(), "0123".to_string(), None)
+        .unwrap();
+    store.set("raw".to_string(), "x".repeat(45), None).unwrap();
+    assert_eq!(store.object_encoding("int"), Some("int"));
+    assert_eq!(store.object_encoding("lead"), Some("embstr"));
+    assert_eq!(store.object_encoding("raw"), Some("raw"));
+    assert_eq!(store.object_encoding("missing"), None);
+}
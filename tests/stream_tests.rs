@@ -0,0 +1,680 @@
+use redis_test_simple::store::RedisStore;
+use redis_test_simple::types::{
+    ReadOutcome, Served, StoreError, StreamEntry, StreamId, StreamTrim, TrimThreshold, XAddId,
+    XClaimOptions,
+};
+use std::ops::Bound;
+
+fn fields(pairs: &[(&str, &str)]) -> Vec<(Vec<u8>, Vec<u8>)> {
+    pairs
+        .iter()
+        .map(|(field, value)| (field.as_bytes().to_vec(), value.as_bytes().to_vec()))
+        .collect()
+}
+
+fn add(store: &RedisStore, key: &str, ms: u64, seq: u64) -> StreamId {
+    store
+        .xadd(
+            key.to_string(),
+            XAddId::Explicit(StreamId::new(ms, seq)),
+            fields(&[("n", &format!("{ms}-{seq}"))]),
+            true,
+            None,
+        )
+        .unwrap()
+        .unwrap()
+}
+
+fn ids(entries: &[StreamEntry]) -> Vec<String> {
+    entries.iter().map(|entry| entry.id.to_string()).collect()
+}
+
+/// A stream holding 1-0, 1-1, 2-0, 3-0 and 5-0.
+fn events() -> RedisStore {
+    let store = RedisStore::new();
+    for (ms, seq) in [(1, 0), (1, 1), (2, 0), (3, 0), (5, 0)] {
+        add(&store, "events", ms, seq);
+    }
+    store
+}
+
+#[test]
+fn test_xadd_generates_increasing_ids() {
+    let store = RedisStore::new();
+    let first = store
+        .xadd(
+            "s".to_string(),
+            XAddId::Auto,
+            fields(&[("a", "1")]),
+            true,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+    let second = store
+        .xadd(
+            "s".to_string(),
+            XAddId::Auto,
+            fields(&[("a", "2")]),
+            true,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+    assert!(first.ms > 0);
+    assert!(second > first);
+
+    // A fixed millisecond part takes the next free sequence number
+    let store = RedisStore::new();
+    add(&store, "s", 7, 3);
+    let id = store
+        .xadd("s".to_string(), XAddId::AutoSeq(7), Vec::new(), true, None)
+        .unwrap();
+    assert_eq!(id, Some(StreamId::new(7, 4)));
+    let id = store
+        .xadd("s".to_string(), XAddId::AutoSeq(9), Vec::new(), true, None)
+        .unwrap();
+    assert_eq!(id, Some(StreamId::new(9, 0)));
+    assert_eq!(store.xlen("s"), Ok(3));
+}
+
+#[test]
+fn test_xadd_rejects_stale_ids() {
+    let store = RedisStore::new();
+    let add_id = |ms, seq| {
+        store.xadd(
+            "s".to_string(),
+            XAddId::Explicit(StreamId::new(ms, seq)),
+            fields(&[("f", "v")]),
+            true,
+            None,
+        )
+    };
+    assert_eq!(add_id(0, 0), Err(StoreError::StreamIdZero));
+    // The rejected ID did not create the key
+    assert_eq!(store.xlen("s"), Ok(0));
+    assert_eq!(store.key_type("s"), "none");
+
+    assert_eq!(add_id(5, 1), Ok(Some(StreamId::new(5, 1))));
+    assert_eq!(add_id(5, 1), Err(StoreError::StreamIdTooSmall));
+    assert_eq!(add_id(4, 9), Err(StoreError::StreamIdTooSmall));
+    assert_eq!(
+        store.xadd("s".to_string(), XAddId::AutoSeq(4), Vec::new(), true, None),
+        Err(StoreError::StreamIdTooSmall)
+    );
+    assert_eq!(store.xlen("s"), Ok(1));
+}
+
+#[test]
+fn test_xadd_nomkstream() {
+    let store = RedisStore::new();
+    assert_eq!(
+        store.xadd(
+            "s".to_string(),
+            XAddId::Auto,
+            fields(&[("f", "v")]),
+            false,
+            None
+        ),
+        Ok(None)
+    );
+    assert_eq!(store.xlen("s"), Ok(0));
+}
+
+#[test]
+fn test_xrange() {
+    let store = events();
+    let range =
+        |start, end, rev, count| ids(&store.xrange("events", start, end, rev, count).unwrap());
+
+    assert_eq!(
+        range(Bound::Unbounded, Bound::Unbounded, false, None),
+        ["1-0", "1-1", "2-0", "3-0", "5-0"]
+    );
+    assert_eq!(
+        range(
+            Bound::Excluded(StreamId::new(1, 0)),
+            Bound::Included(StreamId::new(3, 0)),
+            false,
+            None
+        ),
+        ["1-1", "2-0", "3-0"]
+    );
+    assert_eq!(
+        range(Bound::Unbounded, Bound::Unbounded, true, Some(2)),
+        ["5-0", "3-0"]
+    );
+    // Inverted and empty intervals select nothing rather than panicking
+    assert!(range(
+        Bound::Included(StreamId::new(4, 0)),
+        Bound::Included(StreamId::new(2, 0)),
+        false,
+        None
+    )
+    .is_empty());
+    assert!(range(
+        Bound::Excluded(StreamId::new(2, 0)),
+        Bound::Excluded(StreamId::new(2, 0)),
+        false,
+        None
+    )
+    .is_empty());
+
+    let entries = store
+        .xrange("events", Bound::Unbounded, Bound::Unbounded, false, Some(1))
+        .unwrap();
+    assert_eq!(entries[0].fields, fields(&[("n", "1-0")]));
+    assert_eq!(
+        store.xrange("missing", Bound::Unbounded, Bound::Unbounded, false, None),
+        Ok(Vec::new())
+    );
+}
+
+#[test]
+fn test_trimming() {
+    let store = events();
+    let maxlen = |len| StreamTrim {
+        threshold: TrimThreshold::MaxLen(len),
+        limit: None,
+    };
+
+    assert_eq!(store.xtrim("events", maxlen(10)), Ok(0));
+    assert_eq!(store.xtrim("events", maxlen(3)), Ok(2));
+    let all = |store: &RedisStore| {
+        ids(&store
+            .xrange("events", Bound::Unbounded, Bound::Unbounded, false, None)
+            .unwrap())
+    };
+    assert_eq!(all(&store), ["2-0", "3-0", "5-0"]);
+
+    let min_id = StreamTrim {
+        threshold: TrimThreshold::MinId(StreamId::new(5, 0)),
+        limit: Some(1),
+    };
+    // LIMIT caps how many entries one call evicts
+    assert_eq!(store.xtrim("events", min_id), Ok(1));
+    assert_eq!(all(&store), ["3-0", "5-0"]);
+
+    // XADD trims after appending, and IDs keep increasing past trimmed ones
+    let id = store
+        .xadd(
+            "events".to_string(),
+            XAddId::AutoSeq(5),
+            Vec::new(),
+            true,
+            Some(maxlen(1)),
+        )
+        .unwrap();
+    assert_eq!(id, Some(StreamId::new(5, 1)));
+    assert_eq!(all(&store), ["5-1"]);
+    assert_eq!(store.xtrim("events", maxlen(0)), Ok(1));
+    assert_eq!(
+        store.xadd(
+            "events".to_string(),
+            XAddId::Explicit(StreamId::new(5, 1)),
+            Vec::new(),
+            true,
+            None
+        ),
+        Err(StoreError::StreamIdTooSmall)
+    );
+}
+
+#[test]
+fn test_wrong_type() {
+    let store = RedisStore::new();
+    store
+        .set_add("set".to_string(), vec![b"a".to_vec()])
+        .unwrap();
+    assert_eq!(
+        store.xadd("set".to_string(), XAddId::Auto, Vec::new(), true, None),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(store.xlen("set"), Err(StoreError::WrongType));
+    assert!(matches!(
+        store.xread(vec![("set".to_string(), None)], None, false),
+        Err(StoreError::WrongType)
+    ));
+
+    add(&store, "s", 1, 0);
+    assert_eq!(store.key_type("s"), "stream");
+    assert_eq!(store.set_card("s"), Err(StoreError::WrongType));
+}
+
+#[test]
+fn test_xread() {
+    let store = events();
+    add(&store, "other", 1, 0);
+
+    let ReadOutcome::Ready(streams) = store
+        .xread(
+            vec![
+                ("events".to_string(), Some(StreamId::new(2, 0))),
+                ("other".to_string(), Some(StreamId::new(1, 0))),
+                ("missing".to_string(), Some(StreamId::MIN)),
+            ],
+            Some(1),
+            false,
+        )
+        .unwrap()
+    else {
+        panic!("expected entries");
+    };
+    // Streams with nothing new are left out
+    assert_eq!(streams.len(), 1);
+    assert_eq!(streams[0].0, "events");
+    assert_eq!(ids(&streams[0].1), ["3-0"]);
+
+    // `$` only sees entries added from now on
+    let ReadOutcome::Ready(streams) = store
+        .xread(vec![("events".to_string(), None)], None, false)
+        .unwrap()
+    else {
+        panic!("expected not to block");
+    };
+    assert!(streams.is_empty());
+}
+
+#[test]
+fn test_xadd_serves_blocked_readers() {
+    let store = events();
+    let ReadOutcome::Blocked(mut first) = store
+        .xread(
+            vec![("events".to_string(), None), ("new".to_string(), None)],
+            None,
+            true,
+        )
+        .unwrap()
+    else {
+        panic!("expected to block");
+    };
+    let ReadOutcome::Blocked(mut second) = store
+        .xread(
+            vec![("new".to_string(), Some(StreamId::new(9, 0)))],
+            None,
+            true,
+        )
+        .unwrap()
+    else {
+        panic!("expected to block");
+    };
+
+    add(&store, "new", 7, 0);
+    let Served::Entries { key, entries } = first.receiver.try_recv().unwrap().unwrap() else {
+        panic!("expected stream entries");
+    };
+    assert_eq!(key, "new");
+    assert_eq!(ids(&entries), ["7-0"]);
+    // The entry is not past the second reader's ID, so it keeps waiting
+    assert!(second.receiver.try_recv().is_err());
+    // Unlike a list pop, reading leaves the entry in the stream
+    assert_eq!(store.xlen("new"), Ok(1));
+
+    add(&store, "new", 10, 0);
+    let Served::Entries { entries, .. } = second.receiver.try_recv().unwrap().unwrap() else {
+        panic!("expected stream entries");
+    };
+    assert_eq!(ids(&entries), ["10-0"]);
+    assert!(!store.unblock(second.id));
+}
+
+/// `events` with group "g" created at the start of the stream.
+fn events_with_group() -> RedisStore {
+    let store = events();
+    store
+        .xgroup_create("events", "g", Some(StreamId::MIN), false)
+        .unwrap();
+    store
+}
+
+/// Reads new entries of `events` for `consumer` without blocking.
+fn read_new(store: &RedisStore, consumer: &str, count: usize, no_ack: bool) -> Vec<String> {
+    let ReadOutcome::Ready(streams) = store
+        .xreadgroup(
+            "g",
+            consumer,
+            vec![("events".to_string(), None)],
+            Some(count),
+            no_ack,
+            false,
+        )
+        .unwrap()
+    else {
+        panic!("expected not to block");
+    };
+    streams
+        .first()
+        .map_or_else(Vec::new, |(_, entries)| ids(entries))
+}
+
+/// IDs pending in group "g" of `events`, with their owners.
+fn pending(store: &RedisStore) -> Vec<(String, String)> {
+    store
+        .xpending(
+            "events",
+            "g",
+            (Bound::Unbounded, Bound::Unbounded),
+            usize::MAX,
+            None,
+            0,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|info| (info.id.to_string(), info.consumer))
+        .collect()
+}
+
+fn owned(items: &[(&str, &str)]) -> Vec<(String, String)> {
+    items
+        .iter()
+        .map(|(id, consumer)| (id.to_string(), consumer.to_string()))
+        .collect()
+}
+
+#[test]
+fn test_xgroup_create() {
+    let store = events_with_group();
+    assert_eq!(
+        store.xgroup_create("events", "g", None, false),
+        Err(StoreError::BusyGroup)
+    );
+    assert_eq!(
+        store.xgroup_create("missing", "g", None, false),
+        Err(StoreError::GroupKeyMissing)
+    );
+    assert_eq!(store.xgroup_create("created", "g", None, true), Ok(()));
+    assert_eq!(store.key_type("created"), "stream");
+
+    // `$` starts the group after everything already in the stream
+    store.xgroup_create("events", "late", None, false).unwrap();
+    let ReadOutcome::Ready(streams) = store
+        .xreadgroup(
+            "late",
+            "alice",
+            vec![("events".to_string(), None)],
+            None,
+            false,
+            false,
+        )
+        .unwrap()
+    else {
+        panic!("expected not to block");
+    };
+    assert!(streams.is_empty());
+
+    assert_eq!(
+        store.xgroup_set_id("events", "nope", None),
+        Err(StoreError::NoGroup)
+    );
+    store
+        .xgroup_set_id("events", "late", Some(StreamId::new(3, 0)))
+        .unwrap();
+    assert_eq!(store.xgroup_destroy("events", "late"), Ok(true));
+    assert_eq!(store.xgroup_destroy("events", "late"), Ok(false));
+}
+
+#[test]
+fn test_xreadgroup_tracks_pending_entries() {
+    let store = events_with_group();
+
+    // Each consumer is handed entries no one in the group has seen
+    assert_eq!(read_new(&store, "alice", 2, false), ["1-0", "1-1"]);
+    assert_eq!(read_new(&store, "bob", 2, false), ["2-0", "3-0"]);
+    assert_eq!(
+        pending(&store),
+        owned(&[
+            ("1-0", "alice"),
+            ("1-1", "alice"),
+            ("2-0", "bob"),
+            ("3-0", "bob")
+        ])
+    );
+
+    // NOACK entries are delivered but never pending
+    assert_eq!(read_new(&store, "carol", 10, true), ["5-0"]);
+    assert!(read_new(&store, "carol", 10, false).is_empty());
+    assert_eq!(pending(&store).len(), 4);
+
+    // An explicit ID rereads the consumer's own pending entries
+    let history = |store: &RedisStore| {
+        let ReadOutcome::Ready(streams) = store
+            .xreadgroup(
+                "g",
+                "alice",
+                vec![("events".to_string(), Some(StreamId::MIN))],
+                None,
+                false,
+                true,
+            )
+            .unwrap()
+        else {
+            panic!("history reads never block");
+        };
+        ids(&streams[0].1)
+    };
+    assert_eq!(history(&store), ["1-0", "1-1"]);
+
+    assert_eq!(
+        store.xack("events", "g", &[StreamId::new(1, 0), StreamId::new(9, 9)]),
+        Ok(1)
+    );
+    assert_eq!(history(&store), ["1-1"]);
+    assert_eq!(store.xack("events", "nope", &[StreamId::new(1, 1)]), Ok(0));
+
+    // Deleting a consumer drops what it still had pending
+    assert_eq!(store.xgroup_del_consumer("events", "g", "bob"), Ok(2));
+    assert_eq!(pending(&store), owned(&[("1-1", "alice")]));
+    assert_eq!(
+        store.xgroup_create_consumer("events", "g", "alice"),
+        Ok(false)
+    );
+    assert_eq!(
+        store.xgroup_create_consumer("events", "g", "dave"),
+        Ok(true)
+    );
+}
+
+#[test]
+fn test_xreadgroup_errors() {
+    let store = events_with_group();
+    add(&store, "other", 1, 0);
+    // Every stream needs the group, or nothing is read
+    assert!(matches!(
+        store.xreadgroup(
+            "g",
+            "alice",
+            vec![("events".to_string(), None), ("other".to_string(), None)],
+            None,
+            false,
+            false,
+        ),
+        Err(StoreError::NoGroup)
+    ));
+    assert!(pending(&store).is_empty());
+    assert_eq!(
+        store.xpending(
+            "missing",
+            "g",
+            (Bound::Unbounded, Bound::Unbounded),
+            10,
+            None,
+            0
+        ),
+        Err(StoreError::NoGroup)
+    );
+}
+
+#[test]
+fn test_xclaim() {
+    let store = events_with_group();
+    read_new(&store, "alice", 3, false);
+    let claim = |consumer: &str, min_idle_ms, claimed: &[StreamId], options| {
+        let entries = store
+            .xclaim("events", "g", consumer, min_idle_ms, claimed, options)
+            .unwrap();
+        ids(&entries)
+    };
+    let first = [StreamId::new(1, 0), StreamId::new(1, 1)];
+
+    // Freshly delivered entries are not idle long enough to claim
+    assert!(claim("bob", 60_000, &first, XClaimOptions::default()).is_empty());
+
+    let idle = XClaimOptions {
+        idle_ms: Some(120_000),
+        ..XClaimOptions::default()
+    };
+    assert_eq!(claim("bob", 0, &first, idle), ["1-0", "1-1"]);
+    assert_eq!(claim("carol", 60_000, &first[..1], idle), ["1-0"]);
+
+    let info = store
+        .xpending(
+            "events",
+            "g",
+            (Bound::Unbounded, Bound::Unbounded),
+            10,
+            None,
+            0,
+        )
+        .unwrap();
+    assert_eq!(
+        (info[0].consumer.as_str(), info[0].deliveries),
+        ("carol", 3)
+    );
+    assert!(info[0].idle_ms >= 120_000);
+    assert_eq!((info[1].consumer.as_str(), info[1].deliveries), ("bob", 2));
+
+    // JUSTID leaves the delivery count alone; RETRYCOUNT sets it
+    let just_id = XClaimOptions {
+        just_id: true,
+        ..XClaimOptions::default()
+    };
+    claim("dave", 0, &first[1..], just_id);
+    let retry = XClaimOptions {
+        retry_count: Some(7),
+        ..XClaimOptions::default()
+    };
+    claim("dave", 0, &[StreamId::new(2, 0)], retry);
+    let deliveries: Vec<u64> = store
+        .xpending(
+            "events",
+            "g",
+            (Bound::Included(StreamId::new(1, 1)), Bound::Unbounded),
+            10,
+            Some("dave"),
+            0,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|info| info.deliveries)
+        .collect();
+    assert_eq!(deliveries, [2, 7]);
+
+    // Unpending entries are only claimed with FORCE
+    let five = [StreamId::new(5, 0)];
+    assert!(claim("bob", 0, &five, XClaimOptions::default()).is_empty());
+    let force = XClaimOptions {
+        force: true,
+        ..XClaimOptions::default()
+    };
+    assert_eq!(claim("bob", 0, &five, force), ["5-0"]);
+
+    // Trimmed entries are dropped from the pending entries list
+    store
+        .xtrim(
+            "events",
+            StreamTrim {
+                threshold: TrimThreshold::MinId(StreamId::new(2, 0)),
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert!(claim("bob", 0, &first, XClaimOptions::default()).is_empty());
+    assert_eq!(pending(&store), owned(&[("2-0", "dave"), ("5-0", "bob")]));
+}
+
+#[test]
+fn test_xautoclaim() {
+    let store = events_with_group();
+    read_new(&store, "alice", 5, false);
+    store
+        .xtrim(
+            "events",
+            StreamTrim {
+                threshold: TrimThreshold::MaxLen(4),
+                limit: None,
+            },
+        )
+        .unwrap();
+
+    // Pages through the pending entries list, dropping trimmed entries
+    let claim = store
+        .xautoclaim("events", "g", "bob", 0, StreamId::MIN, 2, false)
+        .unwrap();
+    assert_eq!(ids(&claim.claimed), ["1-1", "2-0"]);
+    assert_eq!(claim.deleted, [StreamId::new(1, 0)]);
+    assert_eq!(claim.next, StreamId::new(3, 0));
+
+    let claim = store
+        .xautoclaim("events", "g", "bob", 0, claim.next, 2, true)
+        .unwrap();
+    assert_eq!(ids(&claim.claimed), ["3-0", "5-0"]);
+    assert_eq!(claim.next, StreamId::MIN);
+
+    // Nothing has been idle for a minute
+    let claim = store
+        .xautoclaim("events", "g", "carol", 60_000, StreamId::MIN, 10, false)
+        .unwrap();
+    assert!(claim.claimed.is_empty());
+    assert_eq!(
+        pending(&store),
+        owned(&[
+            ("1-1", "bob"),
+            ("2-0", "bob"),
+            ("3-0", "bob"),
+            ("5-0", "bob")
+        ])
+    );
+}
+
+#[test]
+fn test_xadd_serves_blocked_group_readers() {
+    let store = events_with_group();
+    read_new(&store, "alice", 10, false);
+    let block = |consumer: &str| {
+        let ReadOutcome::Blocked(ticket) = store
+            .xreadgroup(
+                "g",
+                consumer,
+                vec![("events".to_string(), None)],
+                None,
+                false,
+                true,
+            )
+            .unwrap()
+        else {
+            panic!("expected to block");
+        };
+        ticket
+    };
+    let mut first = block("bob");
+    let mut second = block("carol");
+
+    // Only the longest-waiting reader gets the entry, which is now pending
+    add(&store, "events", 7, 0);
+    let Served::Entries { entries, .. } = first.receiver.try_recv().unwrap().unwrap() else {
+        panic!("expected stream entries");
+    };
+    assert_eq!(ids(&entries), ["7-0"]);
+    assert!(second.receiver.try_recv().is_err());
+    assert_eq!(
+        pending(&store).last(),
+        Some(&("7-0".to_string(), "bob".to_string()))
+    );
+
+    // Destroying the group fails the readers still waiting on it
+    store.xgroup_destroy("events", "g").unwrap();
+    assert_eq!(
+        second.receiver.try_recv().unwrap(),
+        Err(StoreError::NoGroup)
+    );
+}
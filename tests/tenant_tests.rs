@@ -24,12 +24,12 @@ fn test_tenant_isolation() {
 
     // Verify each tenant gets their own data
     match store.get("tenant1:users") {
-        RedisGetResult::Value(val) => assert_eq!(val, "[{\"name\":\"John\"}]"),
+        RedisGetResult::Value(val) => assert_eq!(val, b"[{\"name\":\"John\"}]"),
         _ => panic!("Expected tenant1 data"),
     }
 
     match store.get("tenant2:users") {
-        RedisGetResult::Value(val) => assert_eq!(val, "[{\"name\":\"Jane\"}]"),
+        RedisGetResult::Value(val) => assert_eq!(val, b"[{\"name\":\"Jane\"}]"),
         _ => panic!("Expected tenant2 data"),
     }
 
@@ -67,7 +67,7 @@ fn test_tenant_search() {
     // Search within tenant1's data
     match store.get("tenant1:users?age_gt=28") {
         RedisGetResult::Value(val) => {
-            let result: serde_json::Value = serde_json::from_str(&val).unwrap();
+            let result: serde_json::Value = serde_json::from_slice(&val).unwrap();
             assert_eq!(result.as_array().unwrap().len(), 1);
             assert_eq!(result[0]["name"], "John");
             assert_eq!(result[0]["age"], 30);
@@ -78,7 +78,7 @@ fn test_tenant_search() {
     // Search within tenant2's data
     match store.get("tenant2:users?name=John") {
         RedisGetResult::Value(val) => {
-            let result: serde_json::Value = serde_json::from_str(&val).unwrap();
+            let result: serde_json::Value = serde_json::from_slice(&val).unwrap();
             assert_eq!(result.as_array().unwrap().len(), 1);
             assert_eq!(result[0]["age"], 35);
         }
@@ -118,7 +118,7 @@ fn test_tenant_append() {
     // Verify append worked for tenant1 but didn't affect tenant2
     match store.get("tenant1:users") {
         RedisGetResult::Value(val) => {
-            let data: serde_json::Value = serde_json::from_str(&val).unwrap();
+            let data: serde_json::Value = serde_json::from_slice(&val).unwrap();
             assert_eq!(data.as_array().unwrap().len(), 2);
             assert_eq!(data[1]["name"], "Alice");
         }
@@ -127,7 +127,7 @@ fn test_tenant_append() {
 
     match store.get("tenant2:users") {
         RedisGetResult::Value(val) => {
-            let data: serde_json::Value = serde_json::from_str(&val).unwrap();
+            let data: serde_json::Value = serde_json::from_slice(&val).unwrap();
             assert_eq!(data.as_array().unwrap().len(), 1);
             assert_eq!(data[0]["name"], "Jane");
         }
@@ -149,7 +149,7 @@ fn test_tenant_expiration() {
 
     // verify data is present
     match store.get("tenant1:temp") {
-        RedisGetResult::Value(val) => assert_eq!(val, "\"tenant1 data\""),
+        RedisGetResult::Value(val) => assert_eq!(val, b"\"tenant1 data\""),
         _ => panic!("Expected tenant1 data"),
     }
 
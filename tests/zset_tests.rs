@@ -0,0 +1,363 @@
+use redis_test_simple::store::RedisStore;
+use redis_test_simple::types::{Aggregate, LexBound, SetOp, StoreError, ZAddOptions, ZRangeBy};
+use std::ops::Bound;
+
+fn entries(items: &[(f64, &str)]) -> Vec<(f64, Vec<u8>)> {
+    items
+        .iter()
+        .map(|(score, member)| (*score, member.as_bytes().to_vec()))
+        .collect()
+}
+
+fn scored(items: &[(&str, f64)]) -> Vec<(Vec<u8>, f64)> {
+    items
+        .iter()
+        .map(|(member, score)| (member.as_bytes().to_vec(), *score))
+        .collect()
+}
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+fn bytes(items: &[&str]) -> Vec<Vec<u8>> {
+    items.iter().map(|s| s.as_bytes().to_vec()).collect()
+}
+
+fn leaderboard() -> RedisStore {
+    let store = RedisStore::new();
+    store
+        .zadd(
+            "board".to_string(),
+            entries(&[
+                (30.0, "carol"),
+                (10.0, "alice"),
+                (20.0, "bob"),
+                (20.0, "bea"),
+            ]),
+            ZAddOptions::default(),
+        )
+        .unwrap();
+    store
+}
+
+#[test]
+fn test_add_orders_by_score_then_member() {
+    let store = leaderboard();
+    assert_eq!(
+        store.zrange("board", &ZRangeBy::Index(0, -1), false, None),
+        Ok(scored(&[
+            ("alice", 10.0),
+            ("bea", 20.0),
+            ("bob", 20.0),
+            ("carol", 30.0)
+        ]))
+    );
+    assert_eq!(store.zcard("board"), Ok(4));
+    assert_eq!(store.zscore("board", b"bob"), Ok(Some(20.0)));
+    assert_eq!(store.zrank("board", b"bob", false), Ok(Some((2, 20.0))));
+    assert_eq!(store.zrank("board", b"bob", true), Ok(Some((1, 20.0))));
+    assert_eq!(store.zrank("board", b"nobody", false), Ok(None));
+
+    // Re-adding moves a member rather than duplicating it
+    assert_eq!(
+        store.zadd(
+            "board".to_string(),
+            entries(&[(5.0, "carol"), (40.0, "dave")]),
+            ZAddOptions::default()
+        ),
+        Ok(1)
+    );
+    assert_eq!(
+        store.zrange("board", &ZRangeBy::Index(0, 1), false, None),
+        Ok(scored(&[("carol", 5.0), ("alice", 10.0)]))
+    );
+    assert_eq!(store.zcard("board"), Ok(5));
+}
+
+#[test]
+fn test_add_conditions() {
+    let store = leaderboard();
+    let add = |items: &[(f64, &str)], options: ZAddOptions| {
+        store.zadd("board".to_string(), entries(items), options)
+    };
+
+    let nx = ZAddOptions {
+        only_new: true,
+        ..Default::default()
+    };
+    assert_eq!(add(&[(99.0, "alice"), (1.0, "erin")], nx), Ok(1));
+    assert_eq!(store.zscore("board", b"alice"), Ok(Some(10.0)));
+
+    let xx_ch = ZAddOptions {
+        only_existing: true,
+        count_changed: true,
+        ..Default::default()
+    };
+    assert_eq!(add(&[(11.0, "alice"), (1.0, "frank")], xx_ch), Ok(1));
+    assert_eq!(store.zscore("board", b"frank"), Ok(None));
+
+    // GT only raises existing scores but still adds new members
+    let gt = ZAddOptions {
+        only_greater: true,
+        count_changed: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        add(&[(5.0, "alice"), (35.0, "carol"), (1.0, "gus")], gt),
+        Ok(2)
+    );
+    assert_eq!(store.zscore("board", b"alice"), Ok(Some(11.0)));
+    assert_eq!(store.zscore("board", b"carol"), Ok(Some(35.0)));
+
+    let lt = ZAddOptions {
+        only_less: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        store.zincr_by("board".to_string(), b"bob".to_vec(), 5.0, lt),
+        Ok(None)
+    );
+    assert_eq!(
+        store.zincr_by("board".to_string(), b"bob".to_vec(), -5.0, lt),
+        Ok(Some(15.0))
+    );
+
+    // XX on a missing key must not create it
+    assert_eq!(
+        store.zadd("none".to_string(), entries(&[(1.0, "a")]), xx_ch),
+        Ok(0)
+    );
+    assert_eq!(store.key_type("none"), "none");
+}
+
+#[test]
+fn test_incr_by() {
+    let store = RedisStore::new();
+    let incr = |delta| {
+        store.zincr_by(
+            "z".to_string(),
+            b"m".to_vec(),
+            delta,
+            ZAddOptions::default(),
+        )
+    };
+    assert_eq!(incr(2.5), Ok(Some(2.5)));
+    assert_eq!(incr(-1.0), Ok(Some(1.5)));
+    assert_eq!(incr(f64::INFINITY), Ok(Some(f64::INFINITY)));
+    assert_eq!(incr(f64::NEG_INFINITY), Err(StoreError::ScoreNaN));
+    assert_eq!(store.zscore("z", b"m"), Ok(Some(f64::INFINITY)));
+}
+
+#[test]
+fn test_range_by_score() {
+    let store = leaderboard();
+    let by_score = |min, max| ZRangeBy::Score(min, max);
+
+    assert_eq!(
+        store.zrange(
+            "board",
+            &by_score(Bound::Excluded(10.0), Bound::Included(30.0)),
+            false,
+            None
+        ),
+        Ok(scored(&[("bea", 20.0), ("bob", 20.0), ("carol", 30.0)]))
+    );
+    assert_eq!(
+        store.zrange(
+            "board",
+            &by_score(Bound::Included(f64::NEG_INFINITY), Bound::Excluded(30.0)),
+            true,
+            Some((1, 2))
+        ),
+        Ok(scored(&[("bea", 20.0), ("alice", 10.0)]))
+    );
+    // An inverted interval is empty rather than an error
+    assert_eq!(
+        store.zrange(
+            "board",
+            &by_score(Bound::Included(30.0), Bound::Included(10.0)),
+            false,
+            None
+        ),
+        Ok(Vec::new())
+    );
+    assert_eq!(
+        store.zrange(
+            "board",
+            &by_score(Bound::Included(0.0), Bound::Included(100.0)),
+            false,
+            Some((-1, 2))
+        ),
+        Ok(Vec::new())
+    );
+
+    assert_eq!(
+        store.zcount("board", Bound::Included(20.0), Bound::Included(20.0)),
+        Ok(2)
+    );
+    assert_eq!(
+        store.zcount(
+            "board",
+            Bound::Excluded(20.0),
+            Bound::Included(f64::INFINITY)
+        ),
+        Ok(1)
+    );
+}
+
+#[test]
+fn test_range_by_lex() {
+    let store = RedisStore::new();
+    store
+        .zadd(
+            "names".to_string(),
+            entries(&[(0.0, "a"), (0.0, "b"), (0.0, "c"), (0.0, "d"), (0.0, "e")]),
+            ZAddOptions::default(),
+        )
+        .unwrap();
+    let lex = |min, max, rev| {
+        store
+            .zrange("names", &ZRangeBy::Lex(min, max), rev, None)
+            .map(|items| items.into_iter().map(|(m, _)| m).collect::<Vec<_>>())
+    };
+
+    assert_eq!(
+        lex(
+            LexBound::Included(b"b".to_vec()),
+            LexBound::Excluded(b"d".to_vec()),
+            false
+        ),
+        Ok(bytes(&["b", "c"]))
+    );
+    assert_eq!(
+        lex(LexBound::Excluded(b"c".to_vec()), LexBound::Max, false),
+        Ok(bytes(&["d", "e"]))
+    );
+    assert_eq!(
+        lex(LexBound::Min, LexBound::Included(b"b".to_vec()), true),
+        Ok(bytes(&["b", "a"]))
+    );
+    assert_eq!(lex(LexBound::Max, LexBound::Min, false), Ok(Vec::new()));
+}
+
+#[test]
+fn test_pop_and_remove() {
+    let store = leaderboard();
+    assert_eq!(
+        store.zpop("board", false, 1),
+        Ok(scored(&[("alice", 10.0)]))
+    );
+    assert_eq!(
+        store.zpop("board", true, 2),
+        Ok(scored(&[("carol", 30.0), ("bob", 20.0)]))
+    );
+    assert_eq!(store.zrem("board", &bytes(&["bea", "nobody"])), Ok(1));
+    // Removing the last member deletes the key
+    assert_eq!(store.key_type("board"), "none");
+    assert_eq!(store.zpop("board", false, 1), Ok(Vec::new()));
+}
+
+#[test]
+fn test_union_and_inter_store() {
+    let store = RedisStore::new();
+    store
+        .zadd(
+            "a".to_string(),
+            entries(&[(1.0, "x"), (2.0, "y")]),
+            ZAddOptions::default(),
+        )
+        .unwrap();
+    store
+        .zadd(
+            "b".to_string(),
+            entries(&[(10.0, "y"), (20.0, "z")]),
+            ZAddOptions::default(),
+        )
+        .unwrap();
+    store.set_add("s".to_string(), bytes(&["y", "w"])).unwrap();
+    let keys = strings(&["a", "b"]);
+
+    assert_eq!(
+        store.zcombine_store("u".to_string(), &keys, &[], Aggregate::Sum, SetOp::Union),
+        Ok(3)
+    );
+    assert_eq!(
+        store.zrange("u", &ZRangeBy::Index(0, -1), false, None),
+        Ok(scored(&[("x", 1.0), ("y", 12.0), ("z", 20.0)]))
+    );
+
+    assert_eq!(
+        store.zcombine_store(
+            "i".to_string(),
+            &keys,
+            &[2.0, 0.5],
+            Aggregate::Max,
+            SetOp::Inter
+        ),
+        Ok(1)
+    );
+    assert_eq!(store.zscore("i", b"y"), Ok(Some(5.0)));
+
+    // Plain sets take part with every member scoring 1
+    assert_eq!(
+        store.zcombine_store(
+            "i".to_string(),
+            &strings(&["a", "s"]),
+            &[],
+            Aggregate::Min,
+            SetOp::Inter
+        ),
+        Ok(1)
+    );
+    assert_eq!(store.zscore("i", b"y"), Ok(Some(1.0)));
+
+    // An empty result deletes the destination
+    assert_eq!(
+        store.zcombine_store(
+            "i".to_string(),
+            &strings(&["a", "missing"]),
+            &[],
+            Aggregate::Sum,
+            SetOp::Inter
+        ),
+        Ok(0)
+    );
+    assert_eq!(store.key_type("i"), "none");
+}
+
+#[test]
+fn test_sorted_sets_are_typed() {
+    let store = leaderboard();
+    store.set("str".to_string(), "v".to_string(), None).unwrap();
+
+    assert_eq!(
+        store.zadd(
+            "str".to_string(),
+            entries(&[(1.0, "a")]),
+            ZAddOptions::default()
+        ),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(store.zscore("str", b"a"), Err(StoreError::WrongType));
+    assert_eq!(
+        store.zcombine_store(
+            "d".to_string(),
+            &strings(&["board", "str"]),
+            &[],
+            Aggregate::Sum,
+            SetOp::Union
+        ),
+        Err(StoreError::WrongType)
+    );
+    assert_eq!(store.key_type("board"), "zset");
+    assert_eq!(store.object_encoding("board"), Some("listpack"));
+
+    let many: Vec<(f64, Vec<u8>)> = (0..200)
+        .map(|i| (i as f64, format!("m{}", i).into_bytes()))
+        .collect();
+    store
+        .zadd("board".to_string(), many, ZAddOptions::default())
+        .unwrap();
+    assert_eq!(store.object_encoding("board"), Some("skiplist"));
+}